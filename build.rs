@@ -6,6 +6,9 @@ use std::path::PathBuf;
 fn main() {
     let bindings = bindgen::Builder::default()
         .header("wrapper.h")
+        // Name the values of named enums (e.g. RTA_DST, RTNLGRP_LINK) the same
+        // as those of anonymous ones.
+        .prepend_enum_name(false)
         //        .whitelist_var("IFLA.*")
         //        .whitelist_var("IFF*")
         .generate()
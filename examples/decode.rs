@@ -0,0 +1,41 @@
+use netlink::proto::NetlinkMessage;
+use std::io::Read;
+
+/// Decodes netlink messages given as hex on stdin, e.g. copied out of a
+/// packet capture or a debug log. Whitespace, ':' and a leading 0x on each
+/// word are ignored.
+fn main() {
+    let mut input = String::new();
+    if let Err(e) = std::io::stdin().read_to_string(&mut input) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+
+    let digits: String = input
+        .split(|c: char| c.is_whitespace() || c == ':')
+        .map(|w| w.trim_start_matches("0x"))
+        .collect();
+
+    let mut buf = vec![];
+    for i in (0..digits.len()).step_by(2) {
+        match digits.get(i..i + 2).map(|b| u8::from_str_radix(b, 16)) {
+            Some(Ok(b)) => buf.push(b),
+            _ => {
+                eprintln!("error: invalid hex at offset {}", i);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    match NetlinkMessage::from_bytes(&buf) {
+        Ok(msgs) => {
+            for msg in msgs {
+                println!("{}", msg);
+            }
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
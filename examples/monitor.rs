@@ -0,0 +1,29 @@
+use netlink::hl::iface::LinkMsg;
+use netlink::proto::conn::NetlinkSocket;
+use netlink::type_route::RtmType;
+use netlink::uapi;
+
+fn main() {
+    let mut sock = NetlinkSocket::new(uapi::NETLINK_ROUTE as i32).unwrap();
+    sock.add_membership(uapi::RTNLGRP_LINK).unwrap();
+
+    for ev in sock.events() {
+        let msg = match ev {
+            Err(e) => {
+                println!("error: {}", e);
+                return;
+            }
+            Ok(msg) => msg,
+        };
+
+        let action = match RtmType(msg.header.typ) {
+            RtmType::NEWLINK => "new",
+            RtmType::DELLINK => "del",
+            _ => continue,
+        };
+        match LinkMsg::from_message(&msg) {
+            Ok(link) => println!("{} link {}: {:?}", action, link.index, link.name),
+            Err(e) => println!("could not parse link: {}", e),
+        }
+    }
+}
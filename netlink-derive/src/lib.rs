@@ -0,0 +1,342 @@
+//! Derives `netlink::type_route::AttrSet`, which maps a struct to a list of
+//! netlink attributes and back.
+//!
+//! Each field is annotated with the attribute it holds:
+//!
+//! ```ignore
+//! #[derive(AttrSet)]
+//! struct Vlan {
+//!     #[nla(type = IflaVlan::ID)]
+//!     id: Option<u16>,
+//!     #[nla(type = IflaVlan::INGRESS_QOS, nested)]
+//!     ingress_qos: Option<QosMap>,
+//!     #[nla(unknown)]
+//!     other: Vec<RtAttr>,
+//! }
+//! ```
+//!
+//! - `type = EXPR` is the attribute type, usually a typed constant such as
+//!   `IflaVlan::ID`. Anything that converts `Into<u16>` will do, as will an
+//!   integer literal.
+//! - An `Option<T>` field is optional. Any other field is required, and
+//!   parsing fails if it is missing.
+//! - `nested` means the attribute holds a list of attributes, parsed into a
+//!   type that is itself an AttrSet. Otherwise the type must be an AttrValue.
+//! - `repeated` collects every occurrence of the attribute into a `Vec<T>`.
+//! - `unknown` marks a `Vec<RtAttr>` field that keeps any attributes not
+//!   listed, so that they survive being parsed and serialized again.
+//! - `read_only` fields are parsed but never serialized, for state the
+//!   kernel reports but won't accept back.
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Error, Expr, ExprLit, Fields, GenericArgument, Ident,
+    Lit, LitInt, PathArguments, Token, Type,
+};
+
+#[proc_macro_derive(AttrSet, attributes(nla))]
+pub fn derive_attr_set(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(out) => out.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// One of the comma-separated arguments to `#[nla(...)]`.
+enum Arg {
+    Type(Expr),
+    Nested,
+    Repeated,
+    Unknown,
+    ReadOnly,
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        // `type` is a keyword, so it has to be parsed as "any" ident.
+        let name = Ident::parse_any(input)?;
+        match name.to_string().as_str() {
+            "type" => {
+                input.parse::<Token![=]>()?;
+                Ok(Arg::Type(input.parse()?))
+            }
+            "nested" => Ok(Arg::Nested),
+            "repeated" => Ok(Arg::Repeated),
+            "unknown" => Ok(Arg::Unknown),
+            "read_only" => Ok(Arg::ReadOnly),
+            _ => Err(Error::new(
+                name.span(),
+                "expected `type = ...`, `nested`, `repeated`, `unknown` or `read_only`",
+            )),
+        }
+    }
+}
+
+/// How a field holds its attribute.
+enum Shape {
+    /// A plain T: the attribute must be present.
+    Required,
+    /// Option<T>
+    Optional,
+    /// Vec<T>, one element per occurrence.
+    Repeated,
+}
+
+struct Field {
+    ident: Ident,
+    /// The attribute type, or None for the unknown-attribute field.
+    typ: Option<Expr>,
+    nested: bool,
+    read_only: bool,
+    shape: Shape,
+    /// The type of a single value: T in Option<T> or Vec<T>.
+    value: Type,
+}
+
+/// Returns T if `ty` is `wrapper<T>`.
+fn unwrap_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let path = match ty {
+        Type::Path(p) if p.qself.is_none() => &p.path,
+        _ => return None,
+    };
+    let seg = path.segments.last()?;
+    if seg.ident != wrapper {
+        return None;
+    }
+    match &seg.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            GenericArgument::Type(t) => Some(t),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn parse_field(f: &syn::Field) -> syn::Result<Option<Field>> {
+    let mut typ = None;
+    let mut nested = false;
+    let mut repeated = false;
+    let mut unknown = false;
+    let mut read_only = false;
+    let mut annotated = false;
+    for attr in f.attrs.iter().filter(|a| a.path.is_ident("nla")) {
+        annotated = true;
+        let args = attr.parse_args_with(Punctuated::<Arg, Token![,]>::parse_terminated)?;
+        for arg in args {
+            match arg {
+                Arg::Type(e) => typ = Some(e),
+                Arg::Nested => nested = true,
+                Arg::Repeated => repeated = true,
+                Arg::Unknown => unknown = true,
+                Arg::ReadOnly => read_only = true,
+            }
+        }
+    }
+    if !annotated {
+        return Ok(None);
+    }
+
+    let ident = f.ident.clone().unwrap();
+    if unknown {
+        if typ.is_some() || nested || repeated || read_only {
+            return Err(Error::new(
+                f.span(),
+                "`unknown` can't be combined with other options",
+            ));
+        }
+        if unwrap_type(&f.ty, "Vec").is_none() {
+            return Err(Error::new(f.ty.span(), "`unknown` needs a Vec<RtAttr>"));
+        }
+        return Ok(Some(Field {
+            ident,
+            typ: None,
+            nested: false,
+            read_only: false,
+            shape: Shape::Repeated,
+            value: f.ty.clone(),
+        }));
+    }
+
+    if typ.is_none() {
+        return Err(Error::new(f.span(), "missing `type = ...`"));
+    }
+    let (shape, value) = if repeated {
+        match unwrap_type(&f.ty, "Vec") {
+            Some(t) => (Shape::Repeated, t.clone()),
+            None => return Err(Error::new(f.ty.span(), "`repeated` needs a Vec<T>")),
+        }
+    } else if let Some(t) = unwrap_type(&f.ty, "Option") {
+        (Shape::Optional, t.clone())
+    } else {
+        (Shape::Required, f.ty.clone())
+    };
+
+    Ok(Some(Field {
+        ident,
+        typ,
+        nested,
+        read_only,
+        shape,
+        value,
+    }))
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let named = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(named) => &named.named,
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "AttrSet can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "AttrSet can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut fields = vec![];
+    let mut others = vec![];
+    for f in named.iter() {
+        match parse_field(f)? {
+            Some(field) => fields.push(field),
+            // Fields without #[nla] are left at their default.
+            None => others.push(f.ident.clone().unwrap()),
+        }
+    }
+    if fields.iter().filter(|f| f.typ.is_none()).count() > 1 {
+        return Err(Error::new(input.span(), "only one field can be `unknown`"));
+    }
+
+    let nl = quote!(::netlink);
+    let tr = quote!(::netlink::type_route);
+
+    let mut decls = vec![];
+    let mut arms = vec![];
+    let mut unknown_arm = quote!(_ => {});
+    let mut inits = vec![];
+    let mut serialize = vec![];
+    for (i, f) in fields.iter().enumerate() {
+        let var = format_ident!("__f{}", i);
+        let ident = &f.ident;
+        let value = &f.value;
+
+        let typ = match &f.typ {
+            // A bare literal would otherwise default to i32.
+            Some(Expr::Lit(ExprLit {
+                lit: Lit::Int(lit), ..
+            })) => {
+                let lit = LitInt::new(&format!("{}u16", lit.base10_digits()), lit.span());
+                quote!(#lit)
+            }
+            Some(typ) => quote!(::std::convert::Into::<u16>::into(#typ)),
+            None => {
+                decls.push(quote!(let mut #var = ::std::vec::Vec::new();));
+                unknown_arm = quote!(_ => #var.push(__attr.to_rtattr()),);
+                inits.push(quote!(#ident: #var));
+                serialize.push(quote! {
+                    __out.extend(self.#ident.iter().cloned());
+                });
+                continue;
+            }
+        };
+
+        let parse = if f.nested {
+            quote!(<#value as #tr::AttrSet>::from_attrs(__attr.nested())?)
+        } else {
+            quote!(<#value as #tr::AttrValue>::from_attr(&__attr)?)
+        };
+        let to_attr = if f.nested {
+            quote!(#tr::RtAttr::new_nested(#typ, #tr::AttrSet::to_attrs(__v)))
+        } else {
+            quote!(#tr::AttrValue::to_attr(__v, #typ))
+        };
+
+        let serialized = serialize.len();
+        match f.shape {
+            Shape::Repeated => {
+                decls.push(quote!(let mut #var: ::std::vec::Vec<#value> = ::std::vec::Vec::new();));
+                arms.push(quote!(__t if __t == #typ => #var.push(#parse),));
+                inits.push(quote!(#ident: #var));
+                serialize.push(quote! {
+                    for __v in self.#ident.iter() {
+                        __out.push(#to_attr);
+                    }
+                });
+            }
+            Shape::Optional | Shape::Required => {
+                decls.push(quote!(let mut #var: ::std::option::Option<#value> = None;));
+                arms.push(quote!(__t if __t == #typ => #var = Some(#parse),));
+                if let Shape::Optional = f.shape {
+                    inits.push(quote!(#ident: #var));
+                    serialize.push(quote! {
+                        if let Some(__v) = &self.#ident {
+                            __out.push(#to_attr);
+                        }
+                    });
+                } else {
+                    inits.push(quote! {
+                        #ident: match #var {
+                            Some(__v) => __v,
+                            None => return Err(#nl::NetlinkError::Malformed {
+                                what: "missing attribute",
+                                offset: 0,
+                                attr: Some(#typ),
+                            }),
+                        }
+                    });
+                    serialize.push(quote! {
+                        let __v = &self.#ident;
+                        __out.push(#to_attr);
+                    });
+                }
+            }
+        }
+        if f.read_only {
+            serialize.truncate(serialized);
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #tr::AttrSet for #name #ty_generics #where_clause {
+            fn from_attrs<'__a, __I>(__attrs: __I) -> #nl::Result<Self>
+            where
+                __I: ::std::iter::Iterator<Item = #nl::Result<#tr::RtAttrRef<'__a>>>,
+            {
+                #(#decls)*
+                for __attr in __attrs {
+                    let __attr = __attr?;
+                    match __attr.get_typ() {
+                        #(#arms)*
+                        #unknown_arm
+                    }
+                }
+                Ok(#name {
+                    #(#inits,)*
+                    #(#others: ::std::default::Default::default(),)*
+                })
+            }
+
+            fn to_attrs(&self) -> ::std::vec::Vec<#tr::RtAttr> {
+                let mut __out = ::std::vec::Vec::new();
+                #(#serialize)*
+                __out
+            }
+        }
+    })
+}
@@ -0,0 +1,948 @@
+//! netlink-gen turns the kernel's machine-readable netlink family specs
+//! (Documentation/netlink/specs/*.yaml, the YNL format) into Rust bindings
+//! built on the netlink crate:
+//!
+//! - enum and flags definitions become newtypes with a constant per entry,
+//!   and struct definitions (family headers) get field-by-field
+//!   `from_bytes`/`to_bytes`,
+//! - each attribute set becomes a module of attribute type constants, plus a
+//!   struct deriving `AttrSet`, with an `Option` field per attribute,
+//! - each operation gets request builders, and a parser for its replies.
+//!
+//! It can be run from build.rs:
+//!
+//! ```ignore
+//! let out = PathBuf::from(env::var("OUT_DIR").unwrap());
+//! netlink_gen::generate_file("specs/rt-link.yaml", out.join("rt_link.rs")).unwrap();
+//! ```
+//!
+//! and then included in a module with
+//! `include!(concat!(env!("OUT_DIR"), "/rt_link.rs"));`. Alternatively, run
+//! the netlink-gen binary and check the output in. Either way, the generated
+//! code refers to the netlink crate as `::netlink`.
+//!
+//! Attribute types without a better Rust type (binary, uint, sint,
+//! sub-message, indexed-array, nest-type-value, bitfield32) are kept as raw
+//! bytes.
+pub mod spec;
+
+use self::spec::{AttrSetSpec, AttrSpec, Definition, Operation, Spec};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Yaml(serde_yaml::Error),
+    /// The spec is valid YAML, but doesn't make sense.
+    Spec(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Yaml(e) => write!(f, "invalid spec: {}", e),
+            Error::Spec(e) => write!(f, "invalid spec: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Self {
+        Error::Yaml(e)
+    }
+}
+
+/// Generates bindings for the spec in `spec`, returning Rust source.
+pub fn generate(spec: &str) -> Result<String> {
+    let spec: Spec = serde_yaml::from_str(spec)?;
+    Gen::new(&spec)?.run()
+}
+
+/// Generates bindings for the spec file `spec`, writing them to `out`.
+pub fn generate_file<P: AsRef<Path>, Q: AsRef<Path>>(spec: P, out: Q) -> Result<()> {
+    let src = generate(&fs::read_to_string(spec)?)?;
+    fs::write(out, src)?;
+    Ok(())
+}
+
+/// An attribute with its type number resolved.
+struct Attr<'a> {
+    spec: &'a AttrSpec,
+    value: u16,
+    typ: &'a str,
+}
+
+struct Gen<'a> {
+    spec: &'a Spec,
+    genetlink: bool,
+
+    /// The attributes of each set, by set name.
+    sets: BTreeMap<&'a str, Vec<Attr<'a>>>,
+    structs: HashSet<&'a str>,
+    out: String,
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "crate", "dyn", "else", "enum",
+    "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+    "mut", "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try",
+    "type", "unsafe", "use", "where", "while", "yield",
+];
+
+/// Splits a spec name (kebab-case, sometimes with underscores) into words.
+fn words(name: &str) -> impl Iterator<Item = &str> {
+    name.split(&['-', '_', ' '][..]).filter(|w| !w.is_empty())
+}
+
+fn fix_ident(mut s: String) -> String {
+    if s.starts_with(|c: char| c.is_ascii_digit()) {
+        s.insert(0, '_');
+    }
+    if KEYWORDS.contains(&s.as_str()) {
+        s.push('_');
+    }
+    s
+}
+
+fn snake(name: &str) -> String {
+    fix_ident(words(name).collect::<Vec<_>>().join("_").to_lowercase())
+}
+
+fn upper(name: &str) -> String {
+    fix_ident(words(name).collect::<Vec<_>>().join("_").to_uppercase())
+}
+
+fn camel(name: &str) -> String {
+    let mut out = String::new();
+    for w in words(name) {
+        let mut chars = w.chars();
+        if let Some(c) = chars.next() {
+            out.extend(c.to_uppercase());
+            out.push_str(&chars.as_str().to_lowercase());
+        }
+    }
+    fix_ident(out)
+}
+
+/// The Rust type and size of a scalar struct member or attribute.
+fn scalar(typ: &str) -> Option<(&'static str, usize)> {
+    Some(match typ {
+        "u8" => ("u8", 1),
+        "u16" => ("u16", 2),
+        "u32" => ("u32", 4),
+        "u64" => ("u64", 8),
+        "s8" => ("i8", 1),
+        "s16" => ("i16", 2),
+        "s32" => ("i32", 4),
+        "s64" => ("i64", 8),
+        _ => return None,
+    })
+}
+
+fn big_endian(byte_order: &Option<String>) -> bool {
+    byte_order.as_deref() == Some("big-endian")
+}
+
+impl<'a> Gen<'a> {
+    fn new(spec: &'a Spec) -> Result<Gen<'a>> {
+        let genetlink = spec.protocol.starts_with("genetlink");
+        if !genetlink && spec.protocol != "netlink-raw" {
+            return Err(Error::Spec(format!("unknown protocol {}", spec.protocol)));
+        }
+
+        let mut sets = BTreeMap::new();
+        for set in spec.attribute_sets.iter() {
+            sets.insert(set.name.as_str(), Gen::resolve_set(spec, set, &mut vec![])?);
+        }
+        let structs = spec
+            .definitions
+            .iter()
+            .filter(|d| d.typ == "struct")
+            .map(|d| d.name.as_str())
+            .collect();
+
+        Ok(Gen {
+            spec,
+            genetlink,
+            sets,
+            structs,
+            out: String::new(),
+        })
+    }
+
+    /// Numbers the attributes of a set. Unless given, an attribute's type is
+    /// one more than the previous one's, starting at 1. Subsets take
+    /// everything but the name from the full set. `chain` holds the subsets
+    /// being resolved, which `set` is a subset of.
+    fn resolve_set(
+        spec: &'a Spec,
+        set: &'a AttrSetSpec,
+        chain: &mut Vec<&'a str>,
+    ) -> Result<Vec<Attr<'a>>> {
+        let mut out = vec![];
+        if let Some(parent) = &set.subset_of {
+            if chain.contains(&set.name.as_str()) {
+                return Err(Error::Spec(format!(
+                    "attribute set {} is a subset of itself",
+                    set.name
+                )));
+            }
+            chain.push(&set.name);
+            let full = match spec.attribute_sets.iter().find(|s| &s.name == parent) {
+                Some(full) => Gen::resolve_set(spec, full, chain)?,
+                None => return Err(Error::Spec(format!("unknown attribute set {}", parent))),
+            };
+            for a in set.attributes.iter() {
+                match full.iter().find(|f| f.spec.name == a.name) {
+                    Some(f) => out.push(Attr {
+                        spec: f.spec,
+                        value: f.value,
+                        typ: f.typ,
+                    }),
+                    None => {
+                        return Err(Error::Spec(format!(
+                            "{} is not in attribute set {}",
+                            a.name, parent
+                        )))
+                    }
+                }
+            }
+            return Ok(out);
+        }
+
+        let mut next = 1;
+        for a in set.attributes.iter() {
+            let value = a.value.unwrap_or(next);
+            next = value + 1;
+            let typ = match &a.typ {
+                Some(typ) => typ.as_str(),
+                None => return Err(Error::Spec(format!("attribute {} has no type", a.name))),
+            };
+            out.push(Attr {
+                spec: a,
+                value,
+                typ,
+            });
+        }
+        Ok(out)
+    }
+
+    /// Whether attribute set `from` contains `to`, at any depth.
+    fn reaches(&self, from: &str, to: &str, seen: &mut HashSet<String>) -> bool {
+        if !seen.insert(from.to_string()) {
+            return false;
+        }
+        let attrs = match self.sets.get(from) {
+            Some(attrs) => attrs,
+            None => return false,
+        };
+        attrs.iter().any(|a| match &a.spec.nested_attributes {
+            Some(n) if a.typ == "nest" => n == to || self.reaches(n, to, seen),
+            _ => false,
+        })
+    }
+
+    fn doc(&mut self, indent: &str, doc: &Option<String>) {
+        if let Some(doc) = doc {
+            for line in doc.trim().lines() {
+                let line = line.trim_end();
+                if line.is_empty() {
+                    writeln!(self.out, "{}///", indent).unwrap();
+                } else {
+                    writeln!(self.out, "{}/// {}", indent, line.trim_start()).unwrap();
+                }
+            }
+        }
+    }
+
+    fn run(mut self) -> Result<String> {
+        let spec = self.spec;
+        writeln!(
+            self.out,
+            "// Generated by netlink-gen from the {} netlink spec. Do not edit.\n",
+            spec.name
+        )
+        .unwrap();
+        if self.genetlink {
+            writeln!(self.out, "/// The name to resolve the family id with.").unwrap();
+            writeln!(self.out, "pub const FAMILY_NAME: &str = {:?};", spec.name).unwrap();
+            writeln!(
+                self.out,
+                "pub const FAMILY_VERSION: u8 = {};\n",
+                spec.version.unwrap_or(1)
+            )
+            .unwrap();
+        } else if let Some(protonum) = spec.protonum {
+            writeln!(self.out, "/// The netlink protocol to open a socket with.").unwrap();
+            writeln!(self.out, "pub const PROTONUM: i32 = {};\n", protonum).unwrap();
+        }
+
+        for def in spec.definitions.iter() {
+            match def.typ.as_str() {
+                "const" => self.constant(def),
+                "enum" => self.enumeration(def, false)?,
+                "flags" => self.enumeration(def, true)?,
+                "struct" => self.structure(def)?,
+                _ => {}
+            }
+        }
+        for set in spec.attribute_sets.iter() {
+            self.attr_set(set)?;
+        }
+        if let Some(ops) = &spec.operations {
+            let mut next = 1;
+            for op in ops.list.iter() {
+                let value = op
+                    .do_
+                    .iter()
+                    .chain(op.dump.iter())
+                    .filter_map(|m| m.request.as_ref().and_then(|r| r.value))
+                    .next()
+                    .or(op.value)
+                    .unwrap_or(next);
+                next = value + 1;
+                let fixed_header = op.fixed_header.as_ref().or(ops.fixed_header.as_ref());
+                self.operation(op, value, fixed_header)?;
+            }
+        }
+        Ok(self.out)
+    }
+
+    fn constant(&mut self, def: &Definition) {
+        if let Some(v) = def.value.as_ref().and_then(|v| v.as_i64()) {
+            self.doc("", &def.doc);
+            writeln!(self.out, "pub const {}: i64 = {};\n", upper(&def.name), v).unwrap();
+        }
+    }
+
+    /// Enums and flags become a newtype with an associated constant per
+    /// entry, since the kernel may well send values we don't know about.
+    /// The newtype wraps a u32, unless some value needs a u64.
+    fn enumeration(&mut self, def: &Definition, flags: bool) -> Result<()> {
+        let name = camel(&def.name);
+        let mut entries = vec![];
+        let mut next = Some(def.value_start);
+        for e in def.entries.iter() {
+            let v = match (e, next) {
+                (spec::Entry::Full { value: Some(v), .. }, _) => *v,
+                (_, Some(v)) => v,
+                (_, None) => {
+                    return Err(Error::Spec(format!(
+                        "{} in {} is out of range",
+                        e.name(),
+                        def.name
+                    )))
+                }
+            };
+            next = v.checked_add(1);
+            let (v, doc) = match e {
+                spec::Entry::Full { doc, .. } => (v, doc.clone()),
+                _ => (v, None),
+            };
+            let v = match (flags, v) {
+                (false, v) => v,
+                (true, v) if v < 64 => 1u64 << v,
+                (true, v) => {
+                    return Err(Error::Spec(format!(
+                        "flag {} in {} is bit {}, which doesn't fit in a u64",
+                        e.name(),
+                        def.name,
+                        v
+                    )))
+                }
+            };
+            entries.push((e.name(), v, doc));
+        }
+        let repr = if entries.iter().all(|(_, v, _)| *v <= u32::MAX as u64) {
+            "u32"
+        } else {
+            "u64"
+        };
+
+        self.doc("", &def.doc);
+        writeln!(
+            self.out,
+            "#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]\npub struct {}(pub {});\n",
+            name, repr
+        )
+        .unwrap();
+        writeln!(self.out, "impl {} {{", name).unwrap();
+        for (n, v, doc) in entries.iter() {
+            self.doc("    ", doc);
+            writeln!(
+                self.out,
+                "    pub const {}: {} = {}({:#x});",
+                upper(n),
+                name,
+                name,
+                v
+            )
+            .unwrap();
+        }
+        if flags {
+            writeln!(
+                self.out,
+                "\n    pub fn contains(&self, other: {}) -> bool {{\n        self.0 & other.0 == other.0\n    }}",
+                name
+            )
+            .unwrap();
+        } else {
+            writeln!(
+                self.out,
+                "\n    /// The name of the value in the spec, if it has one.\n    pub fn name(&self) -> Option<&'static str> {{\n        match self.0 {{"
+            )
+            .unwrap();
+            let mut seen = HashSet::new();
+            for (n, v, _) in entries.iter() {
+                if seen.insert(*v) {
+                    writeln!(self.out, "            {:#x} => Some({:?}),", v, n).unwrap();
+                }
+            }
+            writeln!(self.out, "            _ => None,\n        }}\n    }}").unwrap();
+        }
+        writeln!(self.out, "}}\n").unwrap();
+
+        writeln!(self.out, "impl ::std::fmt::Debug for {} {{", name).unwrap();
+        writeln!(
+            self.out,
+            "    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {{"
+        )
+        .unwrap();
+        if flags {
+            writeln!(self.out, "        let mut names = vec![];").unwrap();
+            for (n, v, _) in entries.iter() {
+                writeln!(
+                    self.out,
+                    "        if self.0 & {:#x} != 0 {{\n            names.push({:?});\n        }}",
+                    v, n
+                )
+                .unwrap();
+            }
+            writeln!(
+                self.out,
+                "        write!(f, \"{}({{:#x}}: {{}})\", self.0, names.join(\"|\"))\n    }}\n}}\n",
+                name
+            )
+            .unwrap();
+            writeln!(
+                self.out,
+                "impl ::std::ops::BitOr for {} {{\n    type Output = {};\n\n    fn bitor(self, other: {}) -> {} {{\n        {}(self.0 | other.0)\n    }}\n}}\n",
+                name, name, name, name, name
+            )
+            .unwrap();
+        } else {
+            writeln!(
+                self.out,
+                "        match self.name() {{\n            Some(n) => f.write_str(n),\n            None => write!(f, \"{}({{}})\", self.0),\n        }}\n    }}\n}}\n",
+                name
+            )
+            .unwrap();
+        }
+        Ok(())
+    }
+
+    /// Fixed-layout structs, such as family headers. They are packed in
+    /// the order given; specs spell out any padding.
+    fn structure(&mut self, def: &Definition) -> Result<()> {
+        let name = camel(&def.name);
+        let mut fields = vec![];
+        let mut size = 0;
+        for m in def.members.iter() {
+            let len = m.len.as_ref().and_then(|l| match l {
+                serde_yaml::Value::Number(n) => n.as_u64(),
+                serde_yaml::Value::String(s) => s.parse().ok(),
+                _ => None,
+            });
+            let field = match (m.typ.as_str(), scalar(&m.typ), len) {
+                (_, Some((t, n)), _) => (Some(t), n),
+                ("pad", _, Some(n)) => (None, n as usize),
+                ("binary", _, Some(n)) => (Some("Vec<u8>"), n as usize),
+                _ => {
+                    return Err(Error::Spec(format!(
+                        "member {} of {} has unsupported type {}",
+                        m.name, def.name, m.typ
+                    )))
+                }
+            };
+            fields.push((m, field.0, size, field.1));
+            size += field.1;
+        }
+
+        self.doc("", &def.doc);
+        writeln!(
+            self.out,
+            "#[derive(Debug, Clone, Default, PartialEq, Eq)]\npub struct {} {{",
+            name
+        )
+        .unwrap();
+        for (m, t, _, _) in fields.iter() {
+            if let Some(t) = t {
+                self.doc("    ", &m.doc);
+                writeln!(self.out, "    pub {}: {},", snake(&m.name), t).unwrap();
+            }
+        }
+        writeln!(self.out, "}}\n").unwrap();
+
+        writeln!(self.out, "impl {} {{", name).unwrap();
+        writeln!(self.out, "    pub const SIZE: usize = {};\n", size).unwrap();
+        writeln!(
+            self.out,
+            "    pub fn from_bytes(v: &[u8]) -> ::netlink::Result<{}> {{",
+            name
+        )
+        .unwrap();
+        writeln!(
+            self.out,
+            "        if v.len() < Self::SIZE {{\n            return Err(::netlink::NetlinkError::Truncated {{\n                what: {:?},\n                offset: 0,\n                attr: None,\n            }});\n        }}",
+            def.name
+        )
+        .unwrap();
+        writeln!(self.out, "        Ok({} {{", name).unwrap();
+        for (m, t, off, len) in fields.iter() {
+            let t = match t {
+                Some(t) => t,
+                None => continue,
+            };
+            let value = if *t == "Vec<u8>" {
+                format!("v[{}..{}].to_vec()", off, off + len)
+            } else {
+                let bytes: Vec<String> = (*off..off + len).map(|i| format!("v[{}]", i)).collect();
+                let end = if big_endian(&m.byte_order) {
+                    "be"
+                } else {
+                    "ne"
+                };
+                format!("{}::from_{}_bytes([{}])", t, end, bytes.join(", "))
+            };
+            writeln!(self.out, "            {}: {},", snake(&m.name), value).unwrap();
+        }
+        writeln!(self.out, "        }})\n    }}\n").unwrap();
+
+        writeln!(self.out, "    pub fn to_bytes(&self) -> Vec<u8> {{").unwrap();
+        writeln!(
+            self.out,
+            "        let mut out = Vec::with_capacity(Self::SIZE);"
+        )
+        .unwrap();
+        for (m, t, _, len) in fields.iter() {
+            let f = snake(&m.name);
+            match t {
+                None => writeln!(self.out, "        out.extend_from_slice(&[0; {}]);", len),
+                Some("Vec<u8>") => writeln!(
+                    self.out,
+                    "        let mut {f} = self.{f}.clone();\n        {f}.resize({}, 0);\n        out.extend({f});",
+                    len,
+                    f = f
+                ),
+                Some(_) => {
+                    let end = if big_endian(&m.byte_order) { "be" } else { "ne" };
+                    writeln!(
+                        self.out,
+                        "        out.extend_from_slice(&self.{}.to_{}_bytes());",
+                        f, end
+                    )
+                }
+            }
+            .unwrap();
+        }
+        writeln!(self.out, "        out\n    }}\n}}\n").unwrap();
+        Ok(())
+    }
+
+    fn attr_set(&mut self, set: &'a AttrSetSpec) -> Result<()> {
+        let name = camel(&set.name);
+        let module = snake(&set.name);
+        let attrs = &self.sets[set.name.as_str()];
+
+        // Subsets reuse the full set's constants.
+        if set.subset_of.is_none() {
+            writeln!(
+                self.out,
+                "/// Attribute types in the {} attribute set.\npub mod {} {{",
+                set.name, module
+            )
+            .unwrap();
+            for a in attrs.iter() {
+                writeln!(
+                    self.out,
+                    "    pub const {}: u16 = {};",
+                    upper(&a.spec.name),
+                    a.value
+                )
+                .unwrap();
+            }
+            writeln!(self.out, "}}\n").unwrap();
+        }
+
+        let mut fields = vec![];
+        for a in attrs.iter() {
+            let be = big_endian(&a.spec.byte_order);
+            let mut nested = false;
+            let t = match (a.typ, scalar(a.typ)) {
+                ("unused", _) | ("pad", _) => continue,
+                ("flag", _) => "()".to_string(),
+                ("string", _) => "String".to_string(),
+                ("u16", _) | ("u32", _) | ("u64", _) if be => {
+                    format!("::netlink::type_route::Be<{}>", a.typ)
+                }
+                (_, Some((t, _))) if !be => t.to_string(),
+                ("nest", _) => {
+                    let inner = match &a.spec.nested_attributes {
+                        Some(inner) if self.sets.contains_key(inner.as_str()) => inner,
+                        _ => {
+                            return Err(Error::Spec(format!(
+                                "nested attribute {} has no known nested-attributes",
+                                a.spec.name
+                            )))
+                        }
+                    };
+                    nested = true;
+                    let mut seen = HashSet::new();
+                    if inner == &set.name || self.reaches(inner, &set.name, &mut seen) {
+                        format!("Box<{}>", camel(inner))
+                    } else {
+                        camel(inner)
+                    }
+                }
+                _ => "Vec<u8>".to_string(),
+            };
+            let parent = set.subset_of.as_ref().unwrap_or(&set.name);
+            let typ = format!("{}::{}", snake(parent), upper(&a.spec.name));
+            fields.push((a.spec, t, typ, nested));
+        }
+
+        self.doc("", &set.doc);
+        writeln!(
+            self.out,
+            "#[derive(Debug, Clone, Default, PartialEq, ::netlink::type_route::AttrSet)]\npub struct {} {{",
+            name
+        )
+        .unwrap();
+        for (a, t, typ, nested) in fields {
+            self.doc("    ", &a.doc);
+            let mut opts = typ;
+            if nested {
+                opts.push_str(", nested");
+            }
+            let t = if a.multi_attr {
+                opts.push_str(", repeated");
+                format!("Vec<{}>", t)
+            } else {
+                format!("Option<{}>", t)
+            };
+            writeln!(
+                self.out,
+                "    #[nla(type = {})]\n    pub {}: {},",
+                opts,
+                snake(&a.name),
+                t
+            )
+            .unwrap();
+        }
+        writeln!(
+            self.out,
+            "    /// Attributes that aren't in the spec.\n    #[nla(unknown)]\n    pub unknown_attrs: Vec<::netlink::type_route::RtAttr>,\n}}\n"
+        )
+        .unwrap();
+        Ok(())
+    }
+
+    fn operation(
+        &mut self,
+        op: &Operation,
+        value: u16,
+        fixed_header: Option<&String>,
+    ) -> Result<()> {
+        let name = snake(&op.name);
+        let header = match fixed_header {
+            Some(h) if self.structs.contains(h.as_str()) => Some(camel(h)),
+            Some(h) => return Err(Error::Spec(format!("unknown fixed header {}", h))),
+            None => None,
+        };
+        let set = match &op.attribute_set {
+            Some(s) if self.sets.contains_key(s.as_str()) => Some(camel(s)),
+            Some(s) => return Err(Error::Spec(format!("unknown attribute set {}", s))),
+            None => None,
+        };
+
+        let mut params = vec![];
+        if self.genetlink {
+            params.push("family: u16".to_string());
+        }
+        if let Some(h) = &header {
+            params.push(format!("header: &{}", h));
+        }
+        if let Some(s) = &set {
+            params.push(format!("attrs: &{}", s));
+        }
+
+        let modes = [
+            (
+                &op.do_,
+                "request",
+                "flags | ::netlink::proto::NlmFlags::REQUEST",
+                true,
+            ),
+            (
+                &op.dump,
+                "dump_request",
+                "::netlink::proto::NlmFlags::REQUEST | ::netlink::proto::NlmFlags::DUMP",
+                false,
+            ),
+        ];
+        for (mode, suffix, flags, takes_flags) in modes.iter() {
+            if mode.as_ref().and_then(|m| m.request.as_ref()).is_none() {
+                continue;
+            }
+            self.doc("", &op.doc);
+            let mut ps = params.clone();
+            if *takes_flags {
+                ps.insert(
+                    if self.genetlink { 1 } else { 0 },
+                    "flags: ::netlink::proto::NlmFlags".to_string(),
+                );
+            }
+            writeln!(
+                self.out,
+                "pub fn {}_{}({}) -> ::netlink::proto::NetlinkMessage {{",
+                name,
+                suffix,
+                ps.join(", ")
+            )
+            .unwrap();
+            writeln!(
+                self.out,
+                "    let mut msg = ::netlink::proto::NetlinkMessage::new({}, {});",
+                if self.genetlink {
+                    "family".to_string()
+                } else {
+                    format!("{}u16", value)
+                },
+                flags
+            )
+            .unwrap();
+            if self.genetlink {
+                writeln!(
+                    self.out,
+                    "    msg.add_data(vec![{}, FAMILY_VERSION, 0, 0]);",
+                    value
+                )
+                .unwrap();
+            }
+            if header.is_some() {
+                writeln!(self.out, "    msg.add_data(header.to_bytes());").unwrap();
+            }
+            if set.is_some() {
+                writeln!(
+                    self.out,
+                    "    for attr in ::netlink::type_route::AttrSet::to_attrs(attrs) {{\n        msg.add_data(::netlink::Serializable::to_bytes(&attr));\n    }}"
+                )
+                .unwrap();
+            }
+            writeln!(self.out, "    msg\n}}\n").unwrap();
+        }
+
+        let has_reply = op
+            .do_
+            .iter()
+            .chain(op.dump.iter())
+            .any(|m| m.reply.is_some());
+        if !has_reply || (header.is_none() && set.is_none()) {
+            return Ok(());
+        }
+
+        let mut ret = vec![];
+        ret.extend(header.clone());
+        ret.extend(set.clone());
+        let ret = if ret.len() == 1 {
+            ret[0].clone()
+        } else {
+            format!("({})", ret.join(", "))
+        };
+        writeln!(
+            self.out,
+            "/// Parses a reply to {}.\npub fn {}_reply(msg: &::netlink::proto::NetlinkMessageRef) -> ::netlink::Result<{}> {{",
+            op.name, name, ret
+        )
+        .unwrap();
+        if self.genetlink {
+            writeln!(
+                self.out,
+                "    if msg.data.len() < 4 {{\n        return Err(::netlink::NetlinkError::Truncated {{\n            what: \"genlmsghdr\",\n            offset: 0,\n            attr: None,\n        }});\n    }}\n    let data = &msg.data[4..];"
+            )
+            .unwrap();
+        } else {
+            writeln!(self.out, "    let data = msg.data;").unwrap();
+        }
+        let mut vals = vec![];
+        if let Some(h) = &header {
+            writeln!(
+                self.out,
+                "    let header = {}::from_bytes(data)?;\n    let data = &data[{}::SIZE..];",
+                h, h
+            )
+            .unwrap();
+            vals.push("header");
+        }
+        if let Some(s) = &set {
+            writeln!(
+                self.out,
+                "    let attrs = <{} as ::netlink::type_route::AttrSet>::from_bytes(data)?;",
+                s
+            )
+            .unwrap();
+            vals.push("attrs");
+        }
+        if vals.len() == 1 {
+            writeln!(self.out, "    Ok({})\n}}\n", vals[0]).unwrap();
+        } else {
+            writeln!(self.out, "    Ok(({}))\n}}\n", vals.join(", ")).unwrap();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{camel, generate, snake, upper, Error};
+
+    const SPEC: &str = r#"
+name: rt-test
+protocol: netlink-raw
+protonum: 0
+definitions:
+  - name: ifinfo-msg
+    type: struct
+    members:
+      - name: ifi-family
+        type: u8
+      - name: padding
+        type: pad
+        len: 1
+      - name: ifi-type
+        type: u16
+      - name: ifi-index
+        type: s32
+  - name: oper-state
+    type: enum
+    entries: [unknown, notpresent, down]
+  - name: ifinfo-flags
+    type: flags
+    entries: [up, broadcast]
+  - name: wide-flags
+    type: flags
+    entries:
+      - name: low
+      - name: high
+        value: 40
+attribute-sets:
+  - name: link-attrs
+    attributes:
+      - name: address
+        type: binary
+      - name: mtu
+        type: u32
+        value: 4
+      - name: linkinfo
+        type: nest
+        nested-attributes: linkinfo-attrs
+      - name: alt-ifname
+        type: string
+        multi-attr: true
+  - name: linkinfo-attrs
+    attributes:
+      - name: kind
+        type: string
+      - name: port
+        type: u16
+        byte-order: big-endian
+  - name: link-subset
+    subset-of: link-attrs
+    attributes:
+      - name: mtu
+operations:
+  fixed-header: ifinfo-msg
+  list:
+    - name: getlink
+      doc: Get or dump links.
+      attribute-set: link-attrs
+      do:
+        request:
+          value: 18
+        reply:
+          value: 16
+      dump:
+        request:
+          value: 18
+"#;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(camel("ifinfo-msg"), "IfinfoMsg");
+        assert_eq!(snake("alt-ifname"), "alt_ifname");
+        assert_eq!(snake("type"), "type_");
+        assert_eq!(upper("8021q"), "_8021Q");
+    }
+
+    #[test]
+    fn test_generate() {
+        let out = generate(SPEC).unwrap();
+        for s in &[
+            "pub const PROTONUM: i32 = 0;",
+            "pub struct IfinfoMsg {",
+            "    pub ifi_index: i32,",
+            "            ifi_type: u16::from_ne_bytes([v[2], v[3]]),",
+            "        out.extend_from_slice(&[0; 1]);",
+            "    pub const DOWN: OperState = OperState(0x2);",
+            "    pub const BROADCAST: IfinfoFlags = IfinfoFlags(0x2);",
+            "pub struct IfinfoFlags(pub u32);",
+            "pub struct WideFlags(pub u64);",
+            "    pub const HIGH: WideFlags = WideFlags(0x10000000000);",
+            "    pub const MTU: u16 = 4;",
+            "    pub const LINKINFO: u16 = 5;",
+            "    #[nla(type = link_attrs::LINKINFO, nested)]\n    pub linkinfo: Option<LinkinfoAttrs>,",
+            "    #[nla(type = link_attrs::ALT_IFNAME, repeated)]\n    pub alt_ifname: Vec<String>,",
+            "    pub port: Option<::netlink::type_route::Be<u16>>,",
+            "    #[nla(type = link_attrs::MTU)]\n    pub mtu: Option<u32>,\n    /// Attributes",
+            "/// Get or dump links.\npub fn getlink_request(flags: ::netlink::proto::NlmFlags, header: &IfinfoMsg, attrs: &LinkAttrs)",
+            "NetlinkMessage::new(18u16, flags | ::netlink::proto::NlmFlags::REQUEST);",
+            "pub fn getlink_dump_request(header: &IfinfoMsg, attrs: &LinkAttrs)",
+            "-> ::netlink::Result<(IfinfoMsg, LinkAttrs)> {",
+        ] {
+            assert!(out.contains(s), "missing {:?} in:\n{}", s, out);
+        }
+        // the subset doesn't get its own constants
+        assert!(!out.contains("pub mod link_subset"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let spec = "name: x\nattribute-sets:\n  - name: a\n    attributes:\n      - name: b\n        type: nest\n";
+        assert!(generate(spec).is_err());
+        assert!(generate("name: x\nprotocol: bogus\n").is_err());
+        assert!(generate("- not a spec").is_err());
+
+        let spec = "name: x\ndefinitions:\n  - name: f\n    type: flags\n    entries:\n      - name: a\n        value: 64\n";
+        assert!(matches!(generate(spec), Err(Error::Spec(_))));
+        let spec = "name: x\ndefinitions:\n  - name: e\n    type: enum\n    entries:\n      - name: a\n        value: 18446744073709551615\n      - b\n";
+        assert!(matches!(generate(spec), Err(Error::Spec(_))));
+
+        let spec = "name: x\nattribute-sets:\n  - name: a\n    subset-of: b\n  - name: b\n    subset-of: a\n";
+        assert!(matches!(generate(spec), Err(Error::Spec(_))));
+    }
+}
@@ -0,0 +1,26 @@
+use std::env;
+use std::io::{self, Write};
+use std::process;
+
+/// Usage: netlink-gen SPEC [OUT]
+///
+/// Generates bindings for a YNL netlink spec, writing them to OUT, or to
+/// stdout if it isn't given.
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let res = match args.as_slice() {
+        [spec] => std::fs::read_to_string(spec)
+            .map_err(netlink_gen::Error::from)
+            .and_then(|s| netlink_gen::generate(&s))
+            .and_then(|out| Ok(io::stdout().write_all(out.as_bytes())?)),
+        [spec, out] => netlink_gen::generate_file(spec, out),
+        _ => {
+            eprintln!("usage: netlink-gen SPEC [OUT]");
+            process::exit(2);
+        }
+    };
+    if let Err(e) = res {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,138 @@
+//! The parts of the YNL spec format that the generator understands. See
+//! Documentation/userspace-api/netlink/specs.rst in the kernel tree.
+//! Anything else in a spec is ignored.
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Spec {
+    pub name: String,
+    /// genetlink, genetlink-legacy, genetlink-c or netlink-raw
+    #[serde(default = "default_protocol")]
+    pub protocol: String,
+    /// The netlink protocol number, for netlink-raw families.
+    pub protonum: Option<i32>,
+    pub doc: Option<String>,
+    /// The genetlink family version.
+    pub version: Option<u8>,
+    #[serde(default)]
+    pub definitions: Vec<Definition>,
+    #[serde(default)]
+    pub attribute_sets: Vec<AttrSetSpec>,
+    pub operations: Option<Operations>,
+}
+
+fn default_protocol() -> String {
+    "genetlink".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Definition {
+    pub name: String,
+    /// const, enum, flags or struct
+    #[serde(rename = "type")]
+    pub typ: String,
+    pub doc: Option<String>,
+    pub value: Option<serde_yaml::Value>,
+    #[serde(default)]
+    pub value_start: u64,
+    #[serde(default)]
+    pub entries: Vec<Entry>,
+    #[serde(default)]
+    pub members: Vec<Member>,
+}
+
+/// An enum or flags entry: either just a name, or a name with extras.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Entry {
+    Name(String),
+    Full {
+        name: String,
+        value: Option<u64>,
+        doc: Option<String>,
+    },
+}
+
+impl Entry {
+    pub fn name(&self) -> &str {
+        match self {
+            Entry::Name(name) => name,
+            Entry::Full { name, .. } => name,
+        }
+    }
+}
+
+/// A member of a fixed-layout struct, such as a family header.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Member {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub typ: String,
+    pub len: Option<serde_yaml::Value>,
+    pub byte_order: Option<String>,
+    pub doc: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AttrSetSpec {
+    pub name: String,
+    pub doc: Option<String>,
+    /// If set, the attributes are a subset of this set's, and only need
+    /// their names listed.
+    pub subset_of: Option<String>,
+    #[serde(default)]
+    pub attributes: Vec<AttrSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AttrSpec {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub typ: Option<String>,
+    pub value: Option<u16>,
+    pub doc: Option<String>,
+    pub nested_attributes: Option<String>,
+    #[serde(default)]
+    pub multi_attr: bool,
+    pub byte_order: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Operations {
+    /// The default family header for all operations.
+    pub fixed_header: Option<String>,
+    #[serde(default)]
+    pub list: Vec<Operation>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Operation {
+    pub name: String,
+    pub doc: Option<String>,
+    pub value: Option<u16>,
+    pub attribute_set: Option<String>,
+    pub fixed_header: Option<String>,
+    #[serde(rename = "do")]
+    pub do_: Option<OpMode>,
+    pub dump: Option<OpMode>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpMode {
+    pub request: Option<OpMessage>,
+    pub reply: Option<OpMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpMessage {
+    pub value: Option<u16>,
+    #[serde(default)]
+    pub attributes: Vec<String>,
+}
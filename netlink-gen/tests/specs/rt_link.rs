@@ -0,0 +1,660 @@
+// Generated by netlink-gen from the rt-link netlink spec. Do not edit.
+
+/// The netlink protocol to open a socket with.
+pub const PROTONUM: i32 = 0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct IfinfoFlags(pub u32);
+
+impl IfinfoFlags {
+    pub const UP: IfinfoFlags = IfinfoFlags(0x1);
+    pub const BROADCAST: IfinfoFlags = IfinfoFlags(0x2);
+    pub const DEBUG: IfinfoFlags = IfinfoFlags(0x4);
+    pub const LOOPBACK: IfinfoFlags = IfinfoFlags(0x8);
+    pub const POINT_TO_POINT: IfinfoFlags = IfinfoFlags(0x10);
+    pub const NO_TRAILERS: IfinfoFlags = IfinfoFlags(0x20);
+    pub const RUNNING: IfinfoFlags = IfinfoFlags(0x40);
+    pub const NO_ARP: IfinfoFlags = IfinfoFlags(0x80);
+    pub const PROMISC: IfinfoFlags = IfinfoFlags(0x100);
+    pub const ALL_MULTI: IfinfoFlags = IfinfoFlags(0x200);
+    pub const MASTER: IfinfoFlags = IfinfoFlags(0x400);
+    pub const SLAVE: IfinfoFlags = IfinfoFlags(0x800);
+    pub const MULTICAST: IfinfoFlags = IfinfoFlags(0x1000);
+    pub const PORTSEL: IfinfoFlags = IfinfoFlags(0x2000);
+    pub const AUTO_MEDIA: IfinfoFlags = IfinfoFlags(0x4000);
+    pub const DYNAMIC: IfinfoFlags = IfinfoFlags(0x8000);
+    pub const LOWER_UP: IfinfoFlags = IfinfoFlags(0x10000);
+    pub const DORMANT: IfinfoFlags = IfinfoFlags(0x20000);
+    pub const ECHO: IfinfoFlags = IfinfoFlags(0x40000);
+
+    pub fn contains(&self, other: IfinfoFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl ::std::fmt::Debug for IfinfoFlags {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        let mut names = vec![];
+        if self.0 & 0x1 != 0 {
+            names.push("up");
+        }
+        if self.0 & 0x2 != 0 {
+            names.push("broadcast");
+        }
+        if self.0 & 0x4 != 0 {
+            names.push("debug");
+        }
+        if self.0 & 0x8 != 0 {
+            names.push("loopback");
+        }
+        if self.0 & 0x10 != 0 {
+            names.push("point-to-point");
+        }
+        if self.0 & 0x20 != 0 {
+            names.push("no-trailers");
+        }
+        if self.0 & 0x40 != 0 {
+            names.push("running");
+        }
+        if self.0 & 0x80 != 0 {
+            names.push("no-arp");
+        }
+        if self.0 & 0x100 != 0 {
+            names.push("promisc");
+        }
+        if self.0 & 0x200 != 0 {
+            names.push("all-multi");
+        }
+        if self.0 & 0x400 != 0 {
+            names.push("master");
+        }
+        if self.0 & 0x800 != 0 {
+            names.push("slave");
+        }
+        if self.0 & 0x1000 != 0 {
+            names.push("multicast");
+        }
+        if self.0 & 0x2000 != 0 {
+            names.push("portsel");
+        }
+        if self.0 & 0x4000 != 0 {
+            names.push("auto-media");
+        }
+        if self.0 & 0x8000 != 0 {
+            names.push("dynamic");
+        }
+        if self.0 & 0x10000 != 0 {
+            names.push("lower-up");
+        }
+        if self.0 & 0x20000 != 0 {
+            names.push("dormant");
+        }
+        if self.0 & 0x40000 != 0 {
+            names.push("echo");
+        }
+        write!(f, "IfinfoFlags({:#x}: {})", self.0, names.join("|"))
+    }
+}
+
+impl ::std::ops::BitOr for IfinfoFlags {
+    type Output = IfinfoFlags;
+
+    fn bitor(self, other: IfinfoFlags) -> IfinfoFlags {
+        IfinfoFlags(self.0 | other.0)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct VlanProtocols(pub u32);
+
+impl VlanProtocols {
+    pub const _8021Q: VlanProtocols = VlanProtocols(0x8100);
+    pub const _8021AD: VlanProtocols = VlanProtocols(0x88a8);
+
+    /// The name of the value in the spec, if it has one.
+    pub fn name(&self) -> Option<&'static str> {
+        match self.0 {
+            0x8100 => Some("8021q"),
+            0x88a8 => Some("8021ad"),
+            _ => None,
+        }
+    }
+}
+
+impl ::std::fmt::Debug for VlanProtocols {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match self.name() {
+            Some(n) => f.write_str(n),
+            None => write!(f, "VlanProtocols({})", self.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IfinfoMsg {
+    pub ifi_family: u8,
+    pub ifi_type: u16,
+    pub ifi_index: i32,
+    pub ifi_flags: u32,
+    pub ifi_change: u32,
+}
+
+impl IfinfoMsg {
+    pub const SIZE: usize = 16;
+
+    pub fn from_bytes(v: &[u8]) -> ::netlink::Result<IfinfoMsg> {
+        if v.len() < Self::SIZE {
+            return Err(::netlink::NetlinkError::Truncated {
+                what: "ifinfo-msg",
+                offset: 0,
+                attr: None,
+            });
+        }
+        Ok(IfinfoMsg {
+            ifi_family: u8::from_ne_bytes([v[0]]),
+            ifi_type: u16::from_ne_bytes([v[2], v[3]]),
+            ifi_index: i32::from_ne_bytes([v[4], v[5], v[6], v[7]]),
+            ifi_flags: u32::from_ne_bytes([v[8], v[9], v[10], v[11]]),
+            ifi_change: u32::from_ne_bytes([v[12], v[13], v[14], v[15]]),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(Self::SIZE);
+        out.extend_from_slice(&self.ifi_family.to_ne_bytes());
+        out.extend_from_slice(&[0; 1]);
+        out.extend_from_slice(&self.ifi_type.to_ne_bytes());
+        out.extend_from_slice(&self.ifi_index.to_ne_bytes());
+        out.extend_from_slice(&self.ifi_flags.to_ne_bytes());
+        out.extend_from_slice(&self.ifi_change.to_ne_bytes());
+        out
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IflaCacheinfo {
+    pub max_reasm_len: u32,
+    pub tstamp: u32,
+    pub reachable_time: i32,
+    pub retrans_time: u32,
+}
+
+impl IflaCacheinfo {
+    pub const SIZE: usize = 16;
+
+    pub fn from_bytes(v: &[u8]) -> ::netlink::Result<IflaCacheinfo> {
+        if v.len() < Self::SIZE {
+            return Err(::netlink::NetlinkError::Truncated {
+                what: "ifla-cacheinfo",
+                offset: 0,
+                attr: None,
+            });
+        }
+        Ok(IflaCacheinfo {
+            max_reasm_len: u32::from_ne_bytes([v[0], v[1], v[2], v[3]]),
+            tstamp: u32::from_ne_bytes([v[4], v[5], v[6], v[7]]),
+            reachable_time: i32::from_ne_bytes([v[8], v[9], v[10], v[11]]),
+            retrans_time: u32::from_ne_bytes([v[12], v[13], v[14], v[15]]),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(Self::SIZE);
+        out.extend_from_slice(&self.max_reasm_len.to_ne_bytes());
+        out.extend_from_slice(&self.tstamp.to_ne_bytes());
+        out.extend_from_slice(&self.reachable_time.to_ne_bytes());
+        out.extend_from_slice(&self.retrans_time.to_ne_bytes());
+        out
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct RtextFilter(pub u32);
+
+impl RtextFilter {
+    pub const VF: RtextFilter = RtextFilter(0x1);
+    pub const BRVLAN: RtextFilter = RtextFilter(0x2);
+    pub const BRVLAN_COMPRESSED: RtextFilter = RtextFilter(0x4);
+    pub const SKIP_STATS: RtextFilter = RtextFilter(0x8);
+    pub const MRP: RtextFilter = RtextFilter(0x10);
+    pub const CFM_CONFIG: RtextFilter = RtextFilter(0x20);
+    pub const CFM_STATUS: RtextFilter = RtextFilter(0x40);
+    pub const MST: RtextFilter = RtextFilter(0x80);
+
+    pub fn contains(&self, other: RtextFilter) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl ::std::fmt::Debug for RtextFilter {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        let mut names = vec![];
+        if self.0 & 0x1 != 0 {
+            names.push("vf");
+        }
+        if self.0 & 0x2 != 0 {
+            names.push("brvlan");
+        }
+        if self.0 & 0x4 != 0 {
+            names.push("brvlan-compressed");
+        }
+        if self.0 & 0x8 != 0 {
+            names.push("skip-stats");
+        }
+        if self.0 & 0x10 != 0 {
+            names.push("mrp");
+        }
+        if self.0 & 0x20 != 0 {
+            names.push("cfm-config");
+        }
+        if self.0 & 0x40 != 0 {
+            names.push("cfm-status");
+        }
+        if self.0 & 0x80 != 0 {
+            names.push("mst");
+        }
+        write!(f, "RtextFilter({:#x}: {})", self.0, names.join("|"))
+    }
+}
+
+impl ::std::ops::BitOr for RtextFilter {
+    type Output = RtextFilter;
+
+    fn bitor(self, other: RtextFilter) -> RtextFilter {
+        RtextFilter(self.0 | other.0)
+    }
+}
+
+/// Attribute types in the link-attrs attribute set.
+pub mod link_attrs {
+    pub const ADDRESS: u16 = 1;
+    pub const BROADCAST: u16 = 2;
+    pub const IFNAME: u16 = 3;
+    pub const MTU: u16 = 4;
+    pub const LINK: u16 = 5;
+    pub const QDISC: u16 = 6;
+    pub const STATS: u16 = 7;
+    pub const COST: u16 = 8;
+    pub const PRIORITY: u16 = 9;
+    pub const MASTER: u16 = 10;
+    pub const WIRELESS: u16 = 11;
+    pub const PROTINFO: u16 = 12;
+    pub const TXQLEN: u16 = 13;
+    pub const MAP: u16 = 14;
+    pub const WEIGHT: u16 = 15;
+    pub const OPERSTATE: u16 = 16;
+    pub const LINKMODE: u16 = 17;
+    pub const LINKINFO: u16 = 18;
+    pub const NET_NS_PID: u16 = 19;
+    pub const IFALIAS: u16 = 20;
+    pub const NUM_VF: u16 = 21;
+    pub const VFINFO_LIST: u16 = 22;
+    pub const STATS64: u16 = 23;
+    pub const VF_PORTS: u16 = 24;
+    pub const PORT_SELF: u16 = 25;
+    pub const AF_SPEC: u16 = 26;
+    pub const GROUP: u16 = 27;
+    pub const NET_NS_FD: u16 = 28;
+    pub const EXT_MASK: u16 = 29;
+    pub const PROMISCUITY: u16 = 30;
+    pub const NUM_TX_QUEUES: u16 = 31;
+    pub const NUM_RX_QUEUES: u16 = 32;
+    pub const CARRIER: u16 = 33;
+    pub const PHYS_PORT_ID: u16 = 34;
+    pub const CARRIER_CHANGES: u16 = 35;
+    pub const PHYS_SWITCH_ID: u16 = 36;
+    pub const LINK_NETNSID: u16 = 37;
+    pub const PHYS_PORT_NAME: u16 = 38;
+    pub const PROTO_DOWN: u16 = 39;
+    pub const GSO_MAX_SEGS: u16 = 40;
+    pub const GSO_MAX_SIZE: u16 = 41;
+    pub const PAD: u16 = 42;
+    pub const XDP: u16 = 43;
+    pub const EVENT: u16 = 44;
+    pub const NEW_NETNSID: u16 = 45;
+    pub const TARGET_NETNSID: u16 = 46;
+    pub const CARRIER_UP_COUNT: u16 = 47;
+    pub const CARRIER_DOWN_COUNT: u16 = 48;
+    pub const NEW_IFINDEX: u16 = 49;
+    pub const MIN_MTU: u16 = 50;
+    pub const MAX_MTU: u16 = 51;
+    pub const PROP_LIST: u16 = 52;
+    pub const ALT_IFNAME: u16 = 53;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, ::netlink::type_route::AttrSet)]
+pub struct LinkAttrs {
+    #[nla(type = link_attrs::ADDRESS)]
+    pub address: Option<Vec<u8>>,
+    #[nla(type = link_attrs::BROADCAST)]
+    pub broadcast: Option<Vec<u8>>,
+    #[nla(type = link_attrs::IFNAME)]
+    pub ifname: Option<String>,
+    #[nla(type = link_attrs::MTU)]
+    pub mtu: Option<u32>,
+    #[nla(type = link_attrs::LINK)]
+    pub link: Option<u32>,
+    #[nla(type = link_attrs::QDISC)]
+    pub qdisc: Option<String>,
+    #[nla(type = link_attrs::STATS)]
+    pub stats: Option<Vec<u8>>,
+    #[nla(type = link_attrs::COST)]
+    pub cost: Option<String>,
+    #[nla(type = link_attrs::PRIORITY)]
+    pub priority: Option<String>,
+    #[nla(type = link_attrs::MASTER)]
+    pub master: Option<u32>,
+    #[nla(type = link_attrs::WIRELESS)]
+    pub wireless: Option<String>,
+    #[nla(type = link_attrs::PROTINFO)]
+    pub protinfo: Option<String>,
+    #[nla(type = link_attrs::TXQLEN)]
+    pub txqlen: Option<u32>,
+    #[nla(type = link_attrs::MAP)]
+    pub map: Option<Vec<u8>>,
+    #[nla(type = link_attrs::WEIGHT)]
+    pub weight: Option<u32>,
+    #[nla(type = link_attrs::OPERSTATE)]
+    pub operstate: Option<u8>,
+    #[nla(type = link_attrs::LINKMODE)]
+    pub linkmode: Option<u8>,
+    #[nla(type = link_attrs::LINKINFO, nested)]
+    pub linkinfo: Option<LinkinfoAttrs>,
+    #[nla(type = link_attrs::NET_NS_PID)]
+    pub net_ns_pid: Option<u32>,
+    #[nla(type = link_attrs::IFALIAS)]
+    pub ifalias: Option<String>,
+    #[nla(type = link_attrs::NUM_VF)]
+    pub num_vf: Option<u32>,
+    #[nla(type = link_attrs::VFINFO_LIST, nested)]
+    pub vfinfo_list: Option<VfinfoListAttrs>,
+    #[nla(type = link_attrs::STATS64)]
+    pub stats64: Option<Vec<u8>>,
+    #[nla(type = link_attrs::VF_PORTS, nested)]
+    pub vf_ports: Option<VfPortsAttrs>,
+    #[nla(type = link_attrs::PORT_SELF, nested)]
+    pub port_self: Option<PortSelfAttrs>,
+    #[nla(type = link_attrs::AF_SPEC, nested)]
+    pub af_spec: Option<AfSpecAttrs>,
+    #[nla(type = link_attrs::GROUP)]
+    pub group: Option<u32>,
+    #[nla(type = link_attrs::NET_NS_FD)]
+    pub net_ns_fd: Option<u32>,
+    #[nla(type = link_attrs::EXT_MASK)]
+    pub ext_mask: Option<u32>,
+    #[nla(type = link_attrs::PROMISCUITY)]
+    pub promiscuity: Option<u32>,
+    #[nla(type = link_attrs::NUM_TX_QUEUES)]
+    pub num_tx_queues: Option<u32>,
+    #[nla(type = link_attrs::NUM_RX_QUEUES)]
+    pub num_rx_queues: Option<u32>,
+    #[nla(type = link_attrs::CARRIER)]
+    pub carrier: Option<u8>,
+    #[nla(type = link_attrs::PHYS_PORT_ID)]
+    pub phys_port_id: Option<Vec<u8>>,
+    #[nla(type = link_attrs::CARRIER_CHANGES)]
+    pub carrier_changes: Option<u32>,
+    #[nla(type = link_attrs::PHYS_SWITCH_ID)]
+    pub phys_switch_id: Option<Vec<u8>>,
+    #[nla(type = link_attrs::LINK_NETNSID)]
+    pub link_netnsid: Option<i32>,
+    #[nla(type = link_attrs::PHYS_PORT_NAME)]
+    pub phys_port_name: Option<String>,
+    #[nla(type = link_attrs::PROTO_DOWN)]
+    pub proto_down: Option<u8>,
+    #[nla(type = link_attrs::GSO_MAX_SEGS)]
+    pub gso_max_segs: Option<u32>,
+    #[nla(type = link_attrs::GSO_MAX_SIZE)]
+    pub gso_max_size: Option<u32>,
+    #[nla(type = link_attrs::XDP, nested)]
+    pub xdp: Option<XdpAttrs>,
+    #[nla(type = link_attrs::EVENT)]
+    pub event: Option<u32>,
+    #[nla(type = link_attrs::NEW_NETNSID)]
+    pub new_netnsid: Option<i32>,
+    #[nla(type = link_attrs::TARGET_NETNSID)]
+    pub target_netnsid: Option<i32>,
+    #[nla(type = link_attrs::CARRIER_UP_COUNT)]
+    pub carrier_up_count: Option<u32>,
+    #[nla(type = link_attrs::CARRIER_DOWN_COUNT)]
+    pub carrier_down_count: Option<u32>,
+    #[nla(type = link_attrs::NEW_IFINDEX)]
+    pub new_ifindex: Option<i32>,
+    #[nla(type = link_attrs::MIN_MTU)]
+    pub min_mtu: Option<u32>,
+    #[nla(type = link_attrs::MAX_MTU)]
+    pub max_mtu: Option<u32>,
+    #[nla(type = link_attrs::PROP_LIST, nested)]
+    pub prop_list: Option<PropListLinkAttrs>,
+    #[nla(type = link_attrs::ALT_IFNAME, repeated)]
+    pub alt_ifname: Vec<String>,
+    /// Attributes that aren't in the spec.
+    #[nla(unknown)]
+    pub unknown_attrs: Vec<::netlink::type_route::RtAttr>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, ::netlink::type_route::AttrSet)]
+pub struct PropListLinkAttrs {
+    #[nla(type = link_attrs::ALT_IFNAME, repeated)]
+    pub alt_ifname: Vec<String>,
+    /// Attributes that aren't in the spec.
+    #[nla(unknown)]
+    pub unknown_attrs: Vec<::netlink::type_route::RtAttr>,
+}
+
+/// Attribute types in the af-spec-attrs attribute set.
+pub mod af_spec_attrs {
+    pub const INET: u16 = 2;
+    pub const INET6: u16 = 10;
+    pub const MCTP: u16 = 45;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, ::netlink::type_route::AttrSet)]
+pub struct AfSpecAttrs {
+    #[nla(type = af_spec_attrs::INET)]
+    pub inet: Option<Vec<u8>>,
+    #[nla(type = af_spec_attrs::INET6)]
+    pub inet6: Option<Vec<u8>>,
+    #[nla(type = af_spec_attrs::MCTP)]
+    pub mctp: Option<Vec<u8>>,
+    /// Attributes that aren't in the spec.
+    #[nla(unknown)]
+    pub unknown_attrs: Vec<::netlink::type_route::RtAttr>,
+}
+
+/// Attribute types in the vfinfo-list-attrs attribute set.
+pub mod vfinfo_list_attrs {
+    pub const INFO: u16 = 1;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, ::netlink::type_route::AttrSet)]
+pub struct VfinfoListAttrs {
+    #[nla(type = vfinfo_list_attrs::INFO, repeated)]
+    pub info: Vec<Vec<u8>>,
+    /// Attributes that aren't in the spec.
+    #[nla(unknown)]
+    pub unknown_attrs: Vec<::netlink::type_route::RtAttr>,
+}
+
+/// Attribute types in the vf-ports-attrs attribute set.
+pub mod vf_ports_attrs {
+}
+
+#[derive(Debug, Clone, Default, PartialEq, ::netlink::type_route::AttrSet)]
+pub struct VfPortsAttrs {
+    /// Attributes that aren't in the spec.
+    #[nla(unknown)]
+    pub unknown_attrs: Vec<::netlink::type_route::RtAttr>,
+}
+
+/// Attribute types in the port-self-attrs attribute set.
+pub mod port_self_attrs {
+}
+
+#[derive(Debug, Clone, Default, PartialEq, ::netlink::type_route::AttrSet)]
+pub struct PortSelfAttrs {
+    /// Attributes that aren't in the spec.
+    #[nla(unknown)]
+    pub unknown_attrs: Vec<::netlink::type_route::RtAttr>,
+}
+
+/// Attribute types in the linkinfo-attrs attribute set.
+pub mod linkinfo_attrs {
+    pub const KIND: u16 = 1;
+    pub const DATA: u16 = 2;
+    pub const XSTATS: u16 = 3;
+    pub const SLAVE_KIND: u16 = 4;
+    pub const SLAVE_DATA: u16 = 5;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, ::netlink::type_route::AttrSet)]
+pub struct LinkinfoAttrs {
+    #[nla(type = linkinfo_attrs::KIND)]
+    pub kind: Option<String>,
+    #[nla(type = linkinfo_attrs::DATA)]
+    pub data: Option<Vec<u8>>,
+    #[nla(type = linkinfo_attrs::XSTATS)]
+    pub xstats: Option<Vec<u8>>,
+    #[nla(type = linkinfo_attrs::SLAVE_KIND)]
+    pub slave_kind: Option<String>,
+    #[nla(type = linkinfo_attrs::SLAVE_DATA)]
+    pub slave_data: Option<Vec<u8>>,
+    /// Attributes that aren't in the spec.
+    #[nla(unknown)]
+    pub unknown_attrs: Vec<::netlink::type_route::RtAttr>,
+}
+
+/// Attribute types in the linkinfo-vlan-attrs attribute set.
+pub mod linkinfo_vlan_attrs {
+    pub const ID: u16 = 1;
+    pub const FLAGS: u16 = 2;
+    pub const EGRESS_QOS: u16 = 3;
+    pub const INGRESS_QOS: u16 = 4;
+    pub const PROTOCOL: u16 = 5;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, ::netlink::type_route::AttrSet)]
+pub struct LinkinfoVlanAttrs {
+    #[nla(type = linkinfo_vlan_attrs::ID)]
+    pub id: Option<u16>,
+    #[nla(type = linkinfo_vlan_attrs::FLAGS)]
+    pub flags: Option<Vec<u8>>,
+    #[nla(type = linkinfo_vlan_attrs::EGRESS_QOS, nested)]
+    pub egress_qos: Option<IflaVlanQos>,
+    #[nla(type = linkinfo_vlan_attrs::INGRESS_QOS, nested)]
+    pub ingress_qos: Option<IflaVlanQos>,
+    #[nla(type = linkinfo_vlan_attrs::PROTOCOL)]
+    pub protocol: Option<::netlink::type_route::Be<u16>>,
+    /// Attributes that aren't in the spec.
+    #[nla(unknown)]
+    pub unknown_attrs: Vec<::netlink::type_route::RtAttr>,
+}
+
+/// Attribute types in the ifla-vlan-qos attribute set.
+pub mod ifla_vlan_qos {
+    pub const MAPPING: u16 = 1;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, ::netlink::type_route::AttrSet)]
+pub struct IflaVlanQos {
+    #[nla(type = ifla_vlan_qos::MAPPING, repeated)]
+    pub mapping: Vec<Vec<u8>>,
+    /// Attributes that aren't in the spec.
+    #[nla(unknown)]
+    pub unknown_attrs: Vec<::netlink::type_route::RtAttr>,
+}
+
+/// Attribute types in the xdp-attrs attribute set.
+pub mod xdp_attrs {
+    pub const FD: u16 = 1;
+    pub const ATTACHED: u16 = 2;
+    pub const FLAGS: u16 = 3;
+    pub const PROG_ID: u16 = 4;
+    pub const DRV_PROG_ID: u16 = 5;
+    pub const SKB_PROG_ID: u16 = 6;
+    pub const HW_PROG_ID: u16 = 7;
+    pub const EXPECTED_FD: u16 = 8;
+}
+
+#[derive(Debug, Clone, Default, PartialEq, ::netlink::type_route::AttrSet)]
+pub struct XdpAttrs {
+    #[nla(type = xdp_attrs::FD)]
+    pub fd: Option<i32>,
+    #[nla(type = xdp_attrs::ATTACHED)]
+    pub attached: Option<u8>,
+    #[nla(type = xdp_attrs::FLAGS)]
+    pub flags: Option<u32>,
+    #[nla(type = xdp_attrs::PROG_ID)]
+    pub prog_id: Option<u32>,
+    #[nla(type = xdp_attrs::DRV_PROG_ID)]
+    pub drv_prog_id: Option<u32>,
+    #[nla(type = xdp_attrs::SKB_PROG_ID)]
+    pub skb_prog_id: Option<u32>,
+    #[nla(type = xdp_attrs::HW_PROG_ID)]
+    pub hw_prog_id: Option<u32>,
+    #[nla(type = xdp_attrs::EXPECTED_FD)]
+    pub expected_fd: Option<i32>,
+    /// Attributes that aren't in the spec.
+    #[nla(unknown)]
+    pub unknown_attrs: Vec<::netlink::type_route::RtAttr>,
+}
+
+/// Create a new link.
+pub fn newlink_request(flags: ::netlink::proto::NlmFlags, header: &IfinfoMsg, attrs: &LinkAttrs) -> ::netlink::proto::NetlinkMessage {
+    let mut msg = ::netlink::proto::NetlinkMessage::new(16u16, flags | ::netlink::proto::NlmFlags::REQUEST);
+    msg.add_data(header.to_bytes());
+    for attr in ::netlink::type_route::AttrSet::to_attrs(attrs) {
+        msg.add_data(::netlink::Serializable::to_bytes(&attr));
+    }
+    msg
+}
+
+/// Delete an existing link.
+pub fn dellink_request(flags: ::netlink::proto::NlmFlags, header: &IfinfoMsg, attrs: &LinkAttrs) -> ::netlink::proto::NetlinkMessage {
+    let mut msg = ::netlink::proto::NetlinkMessage::new(17u16, flags | ::netlink::proto::NlmFlags::REQUEST);
+    msg.add_data(header.to_bytes());
+    for attr in ::netlink::type_route::AttrSet::to_attrs(attrs) {
+        msg.add_data(::netlink::Serializable::to_bytes(&attr));
+    }
+    msg
+}
+
+/// Get / dump information about a link.
+pub fn getlink_request(flags: ::netlink::proto::NlmFlags, header: &IfinfoMsg, attrs: &LinkAttrs) -> ::netlink::proto::NetlinkMessage {
+    let mut msg = ::netlink::proto::NetlinkMessage::new(18u16, flags | ::netlink::proto::NlmFlags::REQUEST);
+    msg.add_data(header.to_bytes());
+    for attr in ::netlink::type_route::AttrSet::to_attrs(attrs) {
+        msg.add_data(::netlink::Serializable::to_bytes(&attr));
+    }
+    msg
+}
+
+/// Get / dump information about a link.
+pub fn getlink_dump_request(header: &IfinfoMsg, attrs: &LinkAttrs) -> ::netlink::proto::NetlinkMessage {
+    let mut msg = ::netlink::proto::NetlinkMessage::new(18u16, ::netlink::proto::NlmFlags::REQUEST | ::netlink::proto::NlmFlags::DUMP);
+    msg.add_data(header.to_bytes());
+    for attr in ::netlink::type_route::AttrSet::to_attrs(attrs) {
+        msg.add_data(::netlink::Serializable::to_bytes(&attr));
+    }
+    msg
+}
+
+/// Parses a reply to getlink.
+pub fn getlink_reply(msg: &::netlink::proto::NetlinkMessageRef) -> ::netlink::Result<(IfinfoMsg, LinkAttrs)> {
+    let data = msg.data;
+    let header = IfinfoMsg::from_bytes(data)?;
+    let data = &data[IfinfoMsg::SIZE..];
+    let attrs = <LinkAttrs as ::netlink::type_route::AttrSet>::from_bytes(data)?;
+    Ok((header, attrs))
+}
+
+/// Set information about a link.
+pub fn setlink_request(flags: ::netlink::proto::NlmFlags, header: &IfinfoMsg, attrs: &LinkAttrs) -> ::netlink::proto::NetlinkMessage {
+    let mut msg = ::netlink::proto::NetlinkMessage::new(19u16, flags | ::netlink::proto::NlmFlags::REQUEST);
+    msg.add_data(header.to_bytes());
+    for attr in ::netlink::type_route::AttrSet::to_attrs(attrs) {
+        msg.add_data(::netlink::Serializable::to_bytes(&attr));
+    }
+    msg
+}
+
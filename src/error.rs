@@ -0,0 +1,196 @@
+use crate::proto::{ErrorMessage, NetlinkMessage};
+use std::fmt;
+use std::io;
+
+pub type Result<T> = std::result::Result<T, NetlinkError>;
+
+/// NetlinkError is the error type returned by everything in this crate.
+///
+/// It converts into an io::Error, for callers that don't care about the
+/// details.
+#[derive(Debug)]
+pub enum NetlinkError {
+    /// The kernel rejected a request. The ErrorMessage has the errno as well
+    /// as any extended ack information.
+    Kernel(ErrorMessage),
+
+    /// A message or attribute was cut short. `offset` is the position in the
+    /// buffer being parsed, and `attr` the type of the attribute, if known.
+    Truncated {
+        what: &'static str,
+        offset: usize,
+        attr: Option<u16>,
+    },
+
+    /// A message or attribute had the wrong size or contents.
+    Malformed {
+        what: &'static str,
+        offset: usize,
+        attr: Option<u16>,
+    },
+
+    /// A datagram was larger than the receive buffer, so the end of it was
+    /// lost.
+    DatagramTruncated { size: usize, received: usize },
+
+    /// The socket's receive buffer overflowed (ENOBUFS), and the kernel
+    /// dropped messages. Anyone tracking state via notifications should
+    /// resynchronize with a dump.
+    Overrun,
+
+    /// The kernel's response didn't match our request, e.g. the sequence
+    /// number or port id were wrong.
+    Protocol(&'static str),
+
+    /// The requested object doesn't exist.
+    NotFound(&'static str),
+
+    /// The table changed while it was being dumped (NLM_F_DUMP_INTR), so the
+    /// result may be inconsistent. Holds the messages that were received;
+    /// dumping again usually gets a consistent result.
+    DumpInterrupted(Vec<NetlinkMessage>),
+
+    /// An error from the socket itself.
+    Io(io::Error),
+}
+
+impl NetlinkError {
+    /// The errno of this error, if it came from the kernel or a syscall.
+    /// Useful for matching e.g. libc::EEXIST or libc::ENODEV.
+    pub fn errno(&self) -> Option<i32> {
+        match self {
+            NetlinkError::Kernel(e) => Some(e.errno),
+            NetlinkError::Io(e) => e.raw_os_error(),
+            NetlinkError::Overrun => Some(libc::ENOBUFS),
+            _ => None,
+        }
+    }
+
+    /// The extended ack message from the kernel, if any.
+    pub fn kernel_msg(&self) -> Option<&str> {
+        match self {
+            NetlinkError::Kernel(e) => e.msg.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for NetlinkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetlinkError::Kernel(e) => write!(f, "{}", e),
+            NetlinkError::Truncated { what, offset, attr } => {
+                write!(f, "{} truncated at offset {}", what, offset)?;
+                if let Some(typ) = attr {
+                    write!(f, " (attribute type {})", typ)?;
+                }
+                Ok(())
+            }
+            NetlinkError::Malformed { what, offset, attr } => {
+                write!(f, "malformed {} at offset {}", what, offset)?;
+                if let Some(typ) = attr {
+                    write!(f, " (attribute type {})", typ)?;
+                }
+                Ok(())
+            }
+            NetlinkError::DatagramTruncated { size, received } => write!(
+                f,
+                "datagram of {} bytes truncated to {} bytes",
+                size, received
+            ),
+            NetlinkError::Overrun => write!(f, "receive buffer overrun, messages were lost"),
+            NetlinkError::Protocol(s) => write!(f, "protocol error: {}", s),
+            NetlinkError::NotFound(s) => write!(f, "{}", s),
+            NetlinkError::DumpInterrupted(_) => {
+                write!(
+                    f,
+                    "dump was interrupted by a change, result may be inconsistent"
+                )
+            }
+            NetlinkError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for NetlinkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NetlinkError::Kernel(e) => Some(e),
+            NetlinkError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for NetlinkError {
+    fn from(e: io::Error) -> Self {
+        NetlinkError::Io(e)
+    }
+}
+
+impl From<ErrorMessage> for NetlinkError {
+    fn from(e: ErrorMessage) -> Self {
+        NetlinkError::Kernel(e)
+    }
+}
+
+impl From<NetlinkError> for io::Error {
+    fn from(e: NetlinkError) -> Self {
+        let kind = match e {
+            NetlinkError::Io(e) => return e,
+            NetlinkError::Overrun => return io::Error::from_raw_os_error(libc::ENOBUFS),
+            NetlinkError::Kernel(ref k) => io::Error::from_raw_os_error(k.errno).kind(),
+            NetlinkError::Truncated { .. } => io::ErrorKind::UnexpectedEof,
+            NetlinkError::Malformed { .. } => io::ErrorKind::InvalidData,
+            NetlinkError::DatagramTruncated { .. } => io::ErrorKind::InvalidData,
+            NetlinkError::Protocol(_) => io::ErrorKind::InvalidData,
+            NetlinkError::NotFound(_) => io::ErrorKind::NotFound,
+            NetlinkError::DumpInterrupted(_) => io::ErrorKind::Interrupted,
+        };
+        io::Error::new(kind, e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NetlinkError;
+    use crate::proto::ErrorMessage;
+    use std::io;
+
+    #[test]
+    fn test_into_io_error() {
+        let e = NetlinkError::Kernel(ErrorMessage {
+            errno: libc::EEXIST,
+            msg: Some("File exists".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(e.errno(), Some(libc::EEXIST));
+        assert_eq!(e.kernel_msg(), Some("File exists"));
+
+        let ioe: io::Error = e.into();
+        assert_eq!(ioe.kind(), io::ErrorKind::AlreadyExists);
+
+        let e = NetlinkError::Truncated {
+            what: "rtattr",
+            offset: 12,
+            attr: Some(3),
+        };
+        assert_eq!(e.errno(), None);
+        assert_eq!(
+            e.to_string(),
+            "rtattr truncated at offset 12 (attribute type 3)"
+        );
+        let ioe: io::Error = e.into();
+        assert_eq!(ioe.kind(), io::ErrorKind::UnexpectedEof);
+
+        let ioe: io::Error = NetlinkError::Io(io::Error::from_raw_os_error(libc::ENODEV)).into();
+        assert_eq!(ioe.raw_os_error(), Some(libc::ENODEV));
+
+        assert_eq!(NetlinkError::Overrun.errno(), Some(libc::ENOBUFS));
+        let ioe: io::Error = NetlinkError::Overrun.into();
+        assert_eq!(ioe.raw_os_error(), Some(libc::ENOBUFS));
+
+        let ioe: io::Error = NetlinkError::DumpInterrupted(vec![]).into();
+        assert_eq!(ioe.kind(), io::ErrorKind::Interrupted);
+    }
+}
@@ -0,0 +1,470 @@
+use super::{link_dump, link_dump_request, LinkMsg};
+use crate::proto::conn::DumpMode;
+use crate::proto::transport::Transport;
+use crate::proto::{NetlinkMessage, NlmFlags};
+use crate::type_route::{
+    AttrSet, AttrValue, Be, IfInfoMsg, Ifla, IflaBr, IflaBrport, RtAttr, RtAttrRef, RtmType,
+};
+use crate::uapi;
+use crate::{NetlinkError, Result, Serializable};
+
+/// A bridge's settings, from IFLA_INFO_DATA.
+///
+/// Times are in hundredths of a second (clock_t), as the kernel reports
+/// them. The read-only fields are filled in when dumping and are never
+/// sent. When changing a bridge, set just the fields to change: the kernel
+/// rejects some settings it reports, such as vlan_filtering, if it was
+/// built without support for them.
+#[derive(AttrSet, Default, Debug, Clone, PartialEq)]
+pub struct Bridge {
+    #[nla(type = IflaBr::FORWARD_DELAY)]
+    pub forward_delay: Option<u32>,
+    #[nla(type = IflaBr::HELLO_TIME)]
+    pub hello_time: Option<u32>,
+    #[nla(type = IflaBr::MAX_AGE)]
+    pub max_age: Option<u32>,
+    #[nla(type = IflaBr::AGEING_TIME)]
+    pub ageing_time: Option<u32>,
+    /// 0 for no STP, 1 for the kernel's STP and 2 for a userspace one.
+    #[nla(type = IflaBr::STP_STATE)]
+    pub stp_state: Option<u32>,
+    #[nla(type = IflaBr::PRIORITY)]
+    pub priority: Option<u16>,
+    /// The group address used for STP, 01:80:c2:00:00:0X.
+    #[nla(type = IflaBr::GROUP_ADDR)]
+    pub group_addr: Option<[u8; 6]>,
+    /// Which link-local groups (01:80:c2:00:00:0X, bit X) are forwarded.
+    #[nla(type = IflaBr::GROUP_FWD_MASK)]
+    pub group_fwd_mask: Option<u16>,
+    /// Flushes the forwarding database when sent; never dumped.
+    #[nla(type = IflaBr::FDB_FLUSH)]
+    pub fdb_flush: Option<()>,
+
+    #[nla(type = IflaBr::VLAN_FILTERING)]
+    pub vlan_filtering: Option<bool>,
+    /// The VLAN ethertype, ETH_P_8021Q or ETH_P_8021AD.
+    #[nla(type = IflaBr::VLAN_PROTOCOL)]
+    pub vlan_protocol: Option<Be<u16>>,
+    /// The PVID given to new ports, or 0 for none.
+    #[nla(type = IflaBr::VLAN_DEFAULT_PVID)]
+    pub vlan_default_pvid: Option<u16>,
+    #[nla(type = IflaBr::VLAN_STATS_ENABLED)]
+    pub vlan_stats_enabled: Option<bool>,
+    #[nla(type = IflaBr::VLAN_STATS_PER_PORT)]
+    pub vlan_stats_per_port: Option<bool>,
+
+    /// 0 disables multicast router ports, 1 learns them from queries
+    /// (the default) and 2 makes every port one.
+    #[nla(type = IflaBr::MCAST_ROUTER)]
+    pub mcast_router: Option<u8>,
+    #[nla(type = IflaBr::MCAST_SNOOPING)]
+    pub mcast_snooping: Option<bool>,
+    #[nla(type = IflaBr::MCAST_QUERY_USE_IFADDR)]
+    pub mcast_query_use_ifaddr: Option<bool>,
+    #[nla(type = IflaBr::MCAST_QUERIER)]
+    pub mcast_querier: Option<bool>,
+    #[nla(type = IflaBr::MCAST_HASH_ELASTICITY)]
+    pub mcast_hash_elasticity: Option<u32>,
+    #[nla(type = IflaBr::MCAST_HASH_MAX)]
+    pub mcast_hash_max: Option<u32>,
+    #[nla(type = IflaBr::MCAST_LAST_MEMBER_CNT)]
+    pub mcast_last_member_cnt: Option<u32>,
+    #[nla(type = IflaBr::MCAST_STARTUP_QUERY_CNT)]
+    pub mcast_startup_query_cnt: Option<u32>,
+    #[nla(type = IflaBr::MCAST_LAST_MEMBER_INTVL)]
+    pub mcast_last_member_intvl: Option<u64>,
+    #[nla(type = IflaBr::MCAST_MEMBERSHIP_INTVL)]
+    pub mcast_membership_intvl: Option<u64>,
+    #[nla(type = IflaBr::MCAST_QUERIER_INTVL)]
+    pub mcast_querier_intvl: Option<u64>,
+    #[nla(type = IflaBr::MCAST_QUERY_INTVL)]
+    pub mcast_query_intvl: Option<u64>,
+    #[nla(type = IflaBr::MCAST_QUERY_RESPONSE_INTVL)]
+    pub mcast_query_response_intvl: Option<u64>,
+    #[nla(type = IflaBr::MCAST_STARTUP_QUERY_INTVL)]
+    pub mcast_startup_query_intvl: Option<u64>,
+    #[nla(type = IflaBr::MCAST_STATS_ENABLED)]
+    pub mcast_stats_enabled: Option<bool>,
+    #[nla(type = IflaBr::MCAST_IGMP_VERSION)]
+    pub mcast_igmp_version: Option<u8>,
+    #[nla(type = IflaBr::MCAST_MLD_VERSION)]
+    pub mcast_mld_version: Option<u8>,
+
+    /// Whether iptables, ip6tables and arptables see bridged traffic.
+    #[nla(type = IflaBr::NF_CALL_IPTABLES)]
+    pub nf_call_iptables: Option<bool>,
+    #[nla(type = IflaBr::NF_CALL_IP6TABLES)]
+    pub nf_call_ip6tables: Option<bool>,
+    #[nla(type = IflaBr::NF_CALL_ARPTABLES)]
+    pub nf_call_arptables: Option<bool>,
+
+    #[nla(type = IflaBr::MULTI_BOOLOPT)]
+    pub bool_opts: Option<BridgeBoolOpts>,
+
+    // STP state, as reported by the kernel.
+    #[nla(type = IflaBr::ROOT_ID, read_only)]
+    pub root_id: Option<BridgeId>,
+    #[nla(type = IflaBr::BRIDGE_ID, read_only)]
+    pub bridge_id: Option<BridgeId>,
+    #[nla(type = IflaBr::ROOT_PORT, read_only)]
+    pub root_port: Option<u16>,
+    #[nla(type = IflaBr::ROOT_PATH_COST, read_only)]
+    pub root_path_cost: Option<u32>,
+    #[nla(type = IflaBr::TOPOLOGY_CHANGE, read_only)]
+    pub topology_change: Option<bool>,
+    #[nla(type = IflaBr::TOPOLOGY_CHANGE_DETECTED, read_only)]
+    pub topology_change_detected: Option<bool>,
+    #[nla(type = IflaBr::HELLO_TIMER, read_only)]
+    pub hello_timer: Option<u64>,
+    #[nla(type = IflaBr::TCN_TIMER, read_only)]
+    pub tcn_timer: Option<u64>,
+    #[nla(type = IflaBr::TOPOLOGY_CHANGE_TIMER, read_only)]
+    pub topology_change_timer: Option<u64>,
+    #[nla(type = IflaBr::GC_TIMER, read_only)]
+    pub gc_timer: Option<u64>,
+}
+
+/// An STP bridge id (struct ifla_bridge_id): a priority and a MAC address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BridgeId {
+    pub priority: u16,
+    pub addr: [u8; 6],
+}
+
+impl AttrValue for BridgeId {
+    fn from_attr(attr: &RtAttrRef) -> Result<Self> {
+        if attr.data.len() != 8 {
+            return Err(NetlinkError::Malformed {
+                what: "bridge id attribute",
+                offset: 0,
+                attr: Some(attr.get_typ()),
+            });
+        }
+        let mut addr = [0; 6];
+        addr.copy_from_slice(&attr.data[2..8]);
+        Ok(BridgeId {
+            priority: u16::from_be_bytes([attr.data[0], attr.data[1]]),
+            addr,
+        })
+    }
+
+    fn to_attr(&self, typ: u16) -> RtAttr {
+        let mut data = self.priority.to_be_bytes().to_vec();
+        data.extend_from_slice(&self.addr);
+        RtAttr::new(typ, data)
+    }
+}
+
+/// The bridge's boolean options (struct br_boolopt_multi), one bit per
+/// BR_BOOLOPT_*. Only the bits in `mask` are changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BridgeBoolOpts {
+    pub value: u32,
+    pub mask: u32,
+}
+
+impl BridgeBoolOpts {
+    /// Don't learn from link-local frames.
+    pub const NO_LL_LEARN: u32 = 1 << 0;
+    /// Snoop multicast per VLAN.
+    pub const MCAST_VLAN_SNOOPING: u32 = 1 << 1;
+    /// Multiple spanning trees.
+    pub const MST_ENABLE: u32 = 1 << 2;
+
+    /// Sets (or clears) `opts` and marks them in the mask.
+    pub fn set(&mut self, opts: u32, on: bool) {
+        if on {
+            self.value |= opts;
+        } else {
+            self.value &= !opts;
+        }
+        self.mask |= opts;
+    }
+}
+
+impl AttrValue for BridgeBoolOpts {
+    fn from_attr(attr: &RtAttrRef) -> Result<Self> {
+        if attr.data.len() != 8 {
+            return Err(NetlinkError::Malformed {
+                what: "bridge boolopt attribute",
+                offset: 0,
+                attr: Some(attr.get_typ()),
+            });
+        }
+        Ok(BridgeBoolOpts {
+            value: u32::from_ne_bytes([attr.data[0], attr.data[1], attr.data[2], attr.data[3]]),
+            mask: u32::from_ne_bytes([attr.data[4], attr.data[5], attr.data[6], attr.data[7]]),
+        })
+    }
+
+    fn to_attr(&self, typ: u16) -> RtAttr {
+        let mut data = self.value.to_ne_bytes().to_vec();
+        data.extend_from_slice(&self.mask.to_ne_bytes());
+        RtAttr::new(typ, data)
+    }
+}
+
+typed_consts! {
+    /// The STP state of a bridge port (or of a VLAN on one).
+    pub struct BrState(u8): "BR_STATE_" {
+        DISABLED = uapi::BR_STATE_DISABLED;
+        LISTENING = uapi::BR_STATE_LISTENING;
+        LEARNING = uapi::BR_STATE_LEARNING;
+        FORWARDING = uapi::BR_STATE_FORWARDING;
+        BLOCKING = uapi::BR_STATE_BLOCKING;
+    }
+}
+
+impl AttrValue for BrState {
+    fn from_attr(attr: &RtAttrRef) -> Result<Self> {
+        Ok(BrState(attr.as_u8()?))
+    }
+
+    fn to_attr(&self, typ: u16) -> RtAttr {
+        RtAttr::new_u8(typ, self.0)
+    }
+}
+
+/// A bridge port's settings, as found in IFLA_INFO_SLAVE_DATA or
+/// IFLA_PROTINFO.
+///
+/// As with Bridge, the read-only fields are only filled in when dumping.
+#[derive(AttrSet, Default, Debug, Clone, PartialEq)]
+pub struct BridgePort {
+    /// The STP state. The kernel refuses to set it while running STP itself,
+    /// and the port has to be up.
+    #[nla(type = IflaBrport::STATE)]
+    pub state: Option<BrState>,
+    #[nla(type = IflaBrport::PRIORITY)]
+    pub priority: Option<u16>,
+    #[nla(type = IflaBrport::COST)]
+    pub cost: Option<u32>,
+    /// Sends frames back out the port they came in on (BRIDGE_MODE_HAIRPIN).
+    #[nla(type = IflaBrport::MODE)]
+    pub hairpin: Option<bool>,
+    /// Disables the port if it receives an STP BPDU.
+    #[nla(type = IflaBrport::GUARD)]
+    pub guard: Option<bool>,
+    /// Keeps the port from becoming the root port (root block).
+    #[nla(type = IflaBrport::PROTECT)]
+    pub protect: Option<bool>,
+    #[nla(type = IflaBrport::FAST_LEAVE)]
+    pub fast_leave: Option<bool>,
+    /// Learns source addresses into the forwarding database.
+    #[nla(type = IflaBrport::LEARNING)]
+    pub learning: Option<bool>,
+    /// Floods unicast frames for unknown destinations to the port.
+    #[nla(type = IflaBrport::UNICAST_FLOOD)]
+    pub unicast_flood: Option<bool>,
+    #[nla(type = IflaBrport::MCAST_FLOOD)]
+    pub mcast_flood: Option<bool>,
+    #[nla(type = IflaBrport::BCAST_FLOOD)]
+    pub bcast_flood: Option<bool>,
+    #[nla(type = IflaBrport::MCAST_TO_UCAST)]
+    pub mcast_to_ucast: Option<bool>,
+    #[nla(type = IflaBrport::PROXYARP)]
+    pub proxyarp: Option<bool>,
+    /// As Bridge::mcast_router, for the port.
+    #[nla(type = IflaBrport::MULTICAST_ROUTER)]
+    pub multicast_router: Option<u8>,
+    #[nla(type = IflaBrport::GROUP_FWD_MASK)]
+    pub group_fwd_mask: Option<u16>,
+    /// Answers ARP and ND for the port from the neighbour table.
+    #[nla(type = IflaBrport::NEIGH_SUPPRESS)]
+    pub neigh_suppress: Option<bool>,
+    /// Isolated ports can only talk to ports that aren't.
+    #[nla(type = IflaBrport::ISOLATED)]
+    pub isolated: Option<bool>,
+    #[nla(type = IflaBrport::VLAN_TUNNEL)]
+    pub vlan_tunnel: Option<bool>,
+    /// The index of the port to send traffic to if this one goes down, or
+    /// 0 to clear it.
+    #[nla(type = IflaBrport::BACKUP_PORT)]
+    pub backup_port: Option<u32>,
+    /// Flushes the port's forwarding database entries when sent.
+    #[nla(type = IflaBrport::FLUSH)]
+    pub flush: Option<()>,
+
+    #[nla(type = IflaBrport::ROOT_ID, read_only)]
+    pub root_id: Option<BridgeId>,
+    #[nla(type = IflaBrport::BRIDGE_ID, read_only)]
+    pub bridge_id: Option<BridgeId>,
+    #[nla(type = IflaBrport::DESIGNATED_PORT, read_only)]
+    pub designated_port: Option<u16>,
+    #[nla(type = IflaBrport::DESIGNATED_COST, read_only)]
+    pub designated_cost: Option<u16>,
+    #[nla(type = IflaBrport::ID, read_only)]
+    pub id: Option<u16>,
+    #[nla(type = IflaBrport::NO, read_only)]
+    pub no: Option<u16>,
+    #[nla(type = IflaBrport::TOPOLOGY_CHANGE_ACK, read_only)]
+    pub topology_change_ack: Option<bool>,
+    #[nla(type = IflaBrport::CONFIG_PENDING, read_only)]
+    pub config_pending: Option<bool>,
+    #[nla(type = IflaBrport::MESSAGE_AGE_TIMER, read_only)]
+    pub message_age_timer: Option<u64>,
+    #[nla(type = IflaBrport::FORWARD_DELAY_TIMER, read_only)]
+    pub forward_delay_timer: Option<u64>,
+    #[nla(type = IflaBrport::HOLD_TIMER, read_only)]
+    pub hold_timer: Option<u64>,
+}
+
+/// Lists the ports of all bridges, with an AF_BRIDGE dump. Each link has
+/// its settings in `bridge_port`.
+pub fn bridge_port_list<T: Transport + ?Sized>(sock: &mut T) -> Result<Vec<LinkMsg>> {
+    let mut req = link_dump_request(uapi::AF_BRIDGE as u8);
+    link_dump(sock, &mut req, DumpMode::default())
+}
+
+/// Changes a bridge port's settings with an AF_BRIDGE RTM_SETLINK, as
+/// `bridge link set` does. Only the fields that are set are changed.
+pub fn bridge_port_set<T: Transport + ?Sized>(
+    sock: &mut T,
+    idx: i32,
+    port: &BridgePort,
+) -> Result<()> {
+    // Must be flagged NLA_F_NESTED, or the kernel takes it for the old
+    // single-byte port state.
+    let attr = RtAttr::new_nested(Ifla::PROTINFO, port.to_attrs());
+    let mut req = af_bridge_request(RtmType::SETLINK, idx, attr);
+    sock.exec(&mut req, None)?;
+    Ok(())
+}
+
+/// Builds an acked AF_BRIDGE link request, which the kernel hands to the
+/// bridge rather than handling itself.
+pub(super) fn af_bridge_request(typ: RtmType, idx: i32, attr: RtAttr) -> NetlinkMessage {
+    let mut req = NetlinkMessage::new(typ, NlmFlags::REQUEST | NlmFlags::ACK);
+    let msg = IfInfoMsg {
+        family: uapi::AF_BRIDGE as u8,
+        index: idx,
+        ..Default::default()
+    };
+    req.add_data(msg.to_bytes());
+    req.add_data(attr.to_bytes());
+    req
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bridge_port_list, bridge_port_set, BrState, Bridge, BridgeBoolOpts, BridgeId, BridgePort,
+    };
+    use crate::hl::iface::LinkMsg;
+    use crate::proto::{MockTransport, NetlinkMessage, NlmFlags};
+    use crate::type_route::{
+        AttrSet, Be, IfInfoMsg, Ifla, IflaBr, IflaBrport, IflaInfo, RtAttr, RtmType,
+    };
+    use crate::{uapi, Serializable};
+    use std::ffi::CString;
+
+    #[test]
+    fn test_bridge_roundtrip() {
+        let mut bool_opts = BridgeBoolOpts::default();
+        bool_opts.set(BridgeBoolOpts::NO_LL_LEARN, true);
+        let br = Bridge {
+            forward_delay: Some(1500),
+            stp_state: Some(1),
+            priority: Some(4096),
+            vlan_protocol: Some(Be(0x88a8)),
+            mcast_snooping: Some(false),
+            mcast_query_intvl: Some(12500),
+            nf_call_iptables: Some(true),
+            bool_opts: Some(bool_opts),
+            ..Default::default()
+        };
+        assert_eq!(Bridge::from_bytes(&br.to_bytes()).unwrap(), br);
+
+        // network byte order, as the kernel wants
+        let proto = br
+            .to_attrs()
+            .into_iter()
+            .find(|a| a.get_typ() == IflaBr::VLAN_PROTOCOL)
+            .unwrap();
+        assert_eq!(proto.data, vec![0x88, 0xa8]);
+    }
+
+    #[test]
+    fn test_bridge_read_only() {
+        let id = BridgeId {
+            priority: 0x8000,
+            addr: [2, 0, 0, 0, 0, 1],
+        };
+        let data = RtAttr::new_nested(
+            IflaInfo::DATA,
+            vec![
+                RtAttr::new(IflaBr::ROOT_ID, vec![0x80, 0, 2, 0, 0, 0, 0, 1]),
+                RtAttr::new_u16(IflaBr::ROOT_PORT, 0),
+                RtAttr::new_u64(IflaBr::GC_TIMER, 1234),
+                RtAttr::new_u32(IflaBr::HELLO_TIME, 200),
+            ],
+        );
+
+        let br = Bridge::from_attrs(data.nested()).unwrap();
+        assert_eq!(br.root_id, Some(id));
+        assert_eq!(br.root_port, Some(0));
+        assert_eq!(br.gc_timer, Some(1234));
+        assert_eq!(br.hello_time, Some(200));
+
+        // only the settings are sent back
+        let typs: Vec<u16> = br.to_attrs().iter().map(|a| a.get_typ()).collect();
+        assert_eq!(typs, vec![u16::from(IflaBr::HELLO_TIME)]);
+    }
+
+    #[test]
+    fn test_bridge_port() {
+        let port = BridgePort {
+            state: Some(BrState::FORWARDING),
+            learning: Some(false),
+            isolated: Some(true),
+            backup_port: Some(5),
+            ..Default::default()
+        };
+
+        // A port of an AF_BRIDGE dump has its settings in IFLA_PROTINFO.
+        let mut dumped = NetlinkMessage::new(RtmType::NEWLINK, NlmFlags::empty());
+        let info = IfInfoMsg {
+            family: uapi::AF_BRIDGE as u8,
+            index: 4,
+            ..Default::default()
+        };
+        dumped.add_data(info.to_bytes());
+        dumped.add_data(RtAttr::new_nested(Ifla::PROTINFO, port.to_attrs()).to_bytes());
+
+        let mut mock = MockTransport::new();
+        mock.expect(RtmType::GETLINK, vec![dumped])
+            .expect(RtmType::SETLINK, vec![]);
+        let ports = bridge_port_list(&mut mock).unwrap();
+        assert_eq!(ports.len(), 1);
+        assert_eq!(ports[0].bridge_port.as_ref(), Some(&port));
+
+        bridge_port_set(&mut mock, 4, &port).unwrap();
+        mock.assert_done();
+        let sent = &mock.sent()[1];
+        assert_eq!(sent.data[0], uapi::AF_BRIDGE as u8);
+        let parsed = LinkMsg::from_message(sent).unwrap();
+        assert_eq!((parsed.index, parsed.bridge_port), (4, Some(port)));
+
+        // Other families' IFLA_PROTINFO isn't a bridge port.
+        let mut msg = NetlinkMessage::new(RtmType::NEWLINK, NlmFlags::empty());
+        msg.add_data(IfInfoMsg::default().to_bytes());
+        msg.add_data(RtAttr::new_nested(Ifla::PROTINFO, vec![]).to_bytes());
+        assert!(LinkMsg::from_message(&msg).unwrap().bridge_port.is_none());
+
+        // An AF_UNSPEC dump has it in IFLA_INFO_SLAVE_DATA.
+        let mut msg = NetlinkMessage::new(RtmType::NEWLINK, NlmFlags::empty());
+        msg.add_data(IfInfoMsg::default().to_bytes());
+        let info = vec![
+            RtAttr::new_str(IflaInfo::KIND, "veth"),
+            RtAttr::new_str(IflaInfo::SLAVE_KIND, "bridge"),
+            RtAttr::new_nested(
+                IflaInfo::SLAVE_DATA,
+                vec![RtAttr::new_u32(IflaBrport::COST, 100)],
+            ),
+        ];
+        msg.add_data(RtAttr::new_nested(Ifla::LINKINFO, info).to_bytes());
+        let link = LinkMsg::from_message(&msg).unwrap();
+        assert_eq!(link.slave_kind, Some(CString::new("bridge").unwrap()));
+        assert_eq!(link.bridge_port.unwrap().cost, Some(100));
+    }
+}
@@ -0,0 +1,452 @@
+use super::bridge::af_bridge_request;
+use super::{link_dump, link_dump_request, BrState, LinkMsg};
+use crate::proto::conn::DumpMode;
+use crate::proto::transport::{dump_with, Transport};
+use crate::proto::{NetlinkMessage, NetlinkMessageRef, NlmFlags};
+use crate::type_route::{
+    AttrSet, AttrValue, BridgeVlandb, BridgeVlandbDump, BridgeVlandbEntry, BridgeVlandbGopts,
+    BridgeVlandbStats, Ifla, IflaBridge, RtAttr, RtAttrRef, RtmType,
+};
+use crate::uapi;
+use crate::{NetlinkError, Result, Serializable};
+use bitflags::bitflags;
+
+bitflags! {
+    /// The flags of a bridge VLAN (BRIDGE_VLAN_INFO_*).
+    #[derive(Default)]
+    pub struct BridgeVlanFlags: u16 {
+        /// In legacy requests, the VLAN is the bridge's own rather than the
+        /// port's.
+        const MASTER = uapi::BRIDGE_VLAN_INFO_MASTER as u16;
+        /// Untagged frames arriving on the port go in this VLAN.
+        const PVID = uapi::BRIDGE_VLAN_INFO_PVID as u16;
+        /// Frames in this VLAN leave the port untagged.
+        const UNTAGGED = uapi::BRIDGE_VLAN_INFO_UNTAGGED as u16;
+        /// Marks the ends of a range in legacy requests; BridgeVlan takes
+        /// care of these.
+        const RANGE_BEGIN = uapi::BRIDGE_VLAN_INFO_RANGE_BEGIN as u16;
+        const RANGE_END = uapi::BRIDGE_VLAN_INFO_RANGE_END as u16;
+        /// The bridge itself is in the VLAN, not just its ports.
+        const BRENTRY = uapi::BRIDGE_VLAN_INFO_BRENTRY as u16;
+        /// With RTM_NEWVLAN, only changes the VLAN's options, without
+        /// adding it.
+        const ONLY_OPTS = uapi::BRIDGE_VLAN_INFO_ONLY_OPTS as u16;
+    }
+}
+
+bitflags! {
+    /// Which device a legacy VLAN request is for (IFLA_BRIDGE_FLAGS). If
+    /// neither is set, it's the port's bridge.
+    pub struct BridgeFlags: u16 {
+        /// The bridge the port is in.
+        const MASTER = uapi::BRIDGE_FLAGS_MASTER as u16;
+        /// The device itself, e.g. a bridge, for its own VLANs.
+        const SELF = uapi::BRIDGE_FLAGS_SELF as u16;
+    }
+}
+
+bitflags! {
+    /// What an RTM_GETVLAN dump reports (BRIDGE_VLANDB_DUMPF_*).
+    pub struct VlandbDumpFlags: u32 {
+        /// Each VLAN's counters, in BridgeVlan::stats.
+        const STATS = uapi::BRIDGE_VLANDB_DUMPF_STATS;
+        /// The bridges' global VLAN options instead of the port VLANs.
+        const GLOBAL = uapi::BRIDGE_VLANDB_DUMPF_GLOBAL;
+    }
+}
+
+/// A VLAN id and its flags (struct bridge_vlan_info).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BridgeVlanInfo {
+    pub flags: BridgeVlanFlags,
+    pub vid: u16,
+}
+
+impl AttrValue for BridgeVlanInfo {
+    fn from_attr(attr: &RtAttrRef) -> Result<Self> {
+        if attr.data.len() < 4 {
+            return Err(NetlinkError::Malformed {
+                what: "bridge vlan info attribute",
+                offset: 0,
+                attr: Some(attr.get_typ()),
+            });
+        }
+        let flags = u16::from_ne_bytes([attr.data[0], attr.data[1]]);
+        Ok(BridgeVlanInfo {
+            flags: BridgeVlanFlags::from_bits_truncate(flags),
+            vid: u16::from_ne_bytes([attr.data[2], attr.data[3]]),
+        })
+    }
+
+    fn to_attr(&self, typ: u16) -> RtAttr {
+        let mut data = self.flags.bits().to_ne_bytes().to_vec();
+        data.extend_from_slice(&self.vid.to_ne_bytes());
+        RtAttr::new(typ, data)
+    }
+}
+
+/// A VLAN, or a range of them, on a bridge port or on the bridge itself.
+/// This is a BRIDGE_VLANDB_ENTRY; the legacy requests only use the id,
+/// range and flags.
+#[derive(AttrSet, Default, Debug, Clone, PartialEq)]
+pub struct BridgeVlan {
+    #[nla(type = BridgeVlandbEntry::INFO)]
+    pub info: BridgeVlanInfo,
+    /// The last VLAN of a range starting at info.vid.
+    #[nla(type = BridgeVlandbEntry::RANGE)]
+    pub range_end: Option<u16>,
+    /// The VLAN's STP state on the port.
+    #[nla(type = BridgeVlandbEntry::STATE)]
+    pub state: Option<BrState>,
+    /// As BridgePort::multicast_router, for the VLAN.
+    #[nla(type = BridgeVlandbEntry::MCAST_ROUTER)]
+    pub mcast_router: Option<u8>,
+    #[nla(type = BridgeVlandbEntry::STATS, nested, read_only)]
+    pub stats: Option<BridgeVlanStats>,
+}
+
+impl BridgeVlan {
+    /// A single VLAN, e.g. `BridgeVlan::new(1, BridgeVlanFlags::PVID |
+    /// BridgeVlanFlags::UNTAGGED)`.
+    pub fn new(vid: u16, flags: BridgeVlanFlags) -> BridgeVlan {
+        BridgeVlan {
+            info: BridgeVlanInfo { flags, vid },
+            ..Default::default()
+        }
+    }
+
+    /// The VLANs from `vid` to `end`, inclusive.
+    pub fn range(vid: u16, end: u16, flags: BridgeVlanFlags) -> BridgeVlan {
+        BridgeVlan {
+            range_end: Some(end),
+            ..BridgeVlan::new(vid, flags)
+        }
+    }
+}
+
+/// A VLAN's counters, from BRIDGE_VLANDB_ENTRY_STATS.
+#[derive(AttrSet, Default, Debug, Clone, PartialEq)]
+pub struct BridgeVlanStats {
+    #[nla(type = BridgeVlandbStats::RX_BYTES)]
+    pub rx_bytes: Option<u64>,
+    #[nla(type = BridgeVlandbStats::RX_PACKETS)]
+    pub rx_packets: Option<u64>,
+    #[nla(type = BridgeVlandbStats::TX_BYTES)]
+    pub tx_bytes: Option<u64>,
+    #[nla(type = BridgeVlandbStats::TX_PACKETS)]
+    pub tx_packets: Option<u64>,
+}
+
+/// A bridge's options for a VLAN, or a range of them, from
+/// BRIDGE_VLANDB_GLOBAL_OPTIONS. These mirror the bridge-wide multicast
+/// options in Bridge, and need multicast VLAN snooping on.
+#[derive(AttrSet, Default, Debug, Clone, PartialEq)]
+pub struct BridgeVlanGlobalOpts {
+    #[nla(type = BridgeVlandbGopts::ID)]
+    pub vid: u16,
+    /// The last VLAN of a range starting at vid.
+    #[nla(type = BridgeVlandbGopts::RANGE)]
+    pub range_end: Option<u16>,
+    #[nla(type = BridgeVlandbGopts::MCAST_SNOOPING)]
+    pub mcast_snooping: Option<bool>,
+    #[nla(type = BridgeVlandbGopts::MCAST_IGMP_VERSION)]
+    pub mcast_igmp_version: Option<u8>,
+    #[nla(type = BridgeVlandbGopts::MCAST_MLD_VERSION)]
+    pub mcast_mld_version: Option<u8>,
+    #[nla(type = BridgeVlandbGopts::MCAST_LAST_MEMBER_CNT)]
+    pub mcast_last_member_cnt: Option<u32>,
+    #[nla(type = BridgeVlandbGopts::MCAST_STARTUP_QUERY_CNT)]
+    pub mcast_startup_query_cnt: Option<u32>,
+    #[nla(type = BridgeVlandbGopts::MCAST_LAST_MEMBER_INTVL)]
+    pub mcast_last_member_intvl: Option<u64>,
+    #[nla(type = BridgeVlandbGopts::MCAST_MEMBERSHIP_INTVL)]
+    pub mcast_membership_intvl: Option<u64>,
+    #[nla(type = BridgeVlandbGopts::MCAST_QUERIER_INTVL)]
+    pub mcast_querier_intvl: Option<u64>,
+    #[nla(type = BridgeVlandbGopts::MCAST_QUERY_INTVL)]
+    pub mcast_query_intvl: Option<u64>,
+    #[nla(type = BridgeVlandbGopts::MCAST_QUERY_RESPONSE_INTVL)]
+    pub mcast_query_response_intvl: Option<u64>,
+    #[nla(type = BridgeVlandbGopts::MCAST_STARTUP_QUERY_INTVL)]
+    pub mcast_startup_query_intvl: Option<u64>,
+    #[nla(type = BridgeVlandbGopts::MCAST_QUERIER)]
+    pub mcast_querier: Option<bool>,
+    /// The multiple spanning tree instance the VLAN belongs to.
+    #[nla(type = BridgeVlandbGopts::MSTI)]
+    pub msti: Option<u16>,
+}
+
+/// Parses the IFLA_BRIDGE_VLAN_INFO attributes of an AF_BRIDGE
+/// IFLA_AF_SPEC, where a range is sent as its first and last VLAN.
+pub(super) fn vlans_from_af_spec<'a, I>(attrs: I) -> Result<Vec<BridgeVlan>>
+where
+    I: Iterator<Item = Result<RtAttrRef<'a>>>,
+{
+    let mut out = vec![];
+    let mut begin: Option<BridgeVlanInfo> = None;
+    for attr in attrs {
+        let attr = attr?;
+        if attr.get_typ() != IflaBridge::VLAN_INFO {
+            continue;
+        }
+        let mut info = BridgeVlanInfo::from_attr(&attr)?;
+        if info.flags.contains(BridgeVlanFlags::RANGE_BEGIN) {
+            info.flags.remove(BridgeVlanFlags::RANGE_BEGIN);
+            begin = Some(info);
+        } else if info.flags.contains(BridgeVlanFlags::RANGE_END) {
+            let first = begin.take().ok_or(NetlinkError::Malformed {
+                what: "vlan range without a beginning",
+                offset: 0,
+                attr: Some(attr.get_typ()),
+            })?;
+            out.push(BridgeVlan {
+                info: first,
+                range_end: Some(info.vid),
+                ..Default::default()
+            });
+        } else {
+            out.push(BridgeVlan {
+                info,
+                ..Default::default()
+            });
+        }
+    }
+    Ok(out)
+}
+
+/// Serializes VLANs as IFLA_BRIDGE_VLAN_INFO attributes, splitting ranges
+/// into their first and last VLAN.
+fn vlans_to_af_spec(vlans: &[BridgeVlan]) -> Vec<RtAttr> {
+    let mut out = vec![];
+    for vlan in vlans.iter() {
+        let info = vlan.info;
+        match vlan.range_end {
+            Some(end) if end != info.vid => {
+                let begin = BridgeVlanInfo {
+                    flags: info.flags | BridgeVlanFlags::RANGE_BEGIN,
+                    ..info
+                };
+                let end = BridgeVlanInfo {
+                    flags: info.flags | BridgeVlanFlags::RANGE_END,
+                    vid: end,
+                };
+                out.push(begin.to_attr(IflaBridge::VLAN_INFO.into()));
+                out.push(end.to_attr(IflaBridge::VLAN_INFO.into()));
+            }
+            _ => out.push(info.to_attr(IflaBridge::VLAN_INFO.into())),
+        }
+    }
+    out
+}
+
+/// Lists the VLANs of every bridge port, and of the bridges themselves,
+/// with an AF_BRIDGE dump. Each link has its VLANs in `bridge_vlans`, with
+/// runs of VLANs that have the same flags as ranges.
+pub fn bridge_vlan_list<T: Transport + ?Sized>(sock: &mut T) -> Result<Vec<LinkMsg>> {
+    let mut req = link_dump_request(uapi::AF_BRIDGE as u8);
+    let mask = uapi::RTEXT_FILTER_BRVLAN_COMPRESSED;
+    req.add_data(RtAttr::new_u32(Ifla::EXT_MASK, mask).to_bytes());
+    link_dump(sock, &mut req, DumpMode::default())
+}
+
+/// Adds VLANs to a bridge port with an AF_BRIDGE RTM_SETLINK, as `bridge
+/// vlan add` does. Adding one that is already there changes its flags.
+/// The bridge needs vlan_filtering on for them to have any effect.
+pub fn bridge_vlan_add<T: Transport + ?Sized>(
+    sock: &mut T,
+    idx: i32,
+    flags: BridgeFlags,
+    vlans: &[BridgeVlan],
+) -> Result<()> {
+    bridge_vlan_change(sock, RtmType::SETLINK, idx, flags, vlans)
+}
+
+/// Removes VLANs from a bridge port with an AF_BRIDGE RTM_DELLINK.
+pub fn bridge_vlan_del<T: Transport + ?Sized>(
+    sock: &mut T,
+    idx: i32,
+    flags: BridgeFlags,
+    vlans: &[BridgeVlan],
+) -> Result<()> {
+    bridge_vlan_change(sock, RtmType::DELLINK, idx, flags, vlans)
+}
+
+fn bridge_vlan_change<T: Transport + ?Sized>(
+    sock: &mut T,
+    typ: RtmType,
+    idx: i32,
+    flags: BridgeFlags,
+    vlans: &[BridgeVlan],
+) -> Result<()> {
+    let mut spec = vec![];
+    if !flags.is_empty() {
+        spec.push(RtAttr::new_u16(IflaBridge::FLAGS, flags.bits()));
+    }
+    spec.extend(vlans_to_af_spec(vlans));
+    let mut req = af_bridge_request(typ, idx, RtAttr::new_nested(Ifla::AF_SPEC, spec));
+    sock.exec(&mut req, None)?;
+    Ok(())
+}
+
+/// BridgeVlanMsg is an RTM_NEWVLAN message: the VLANs of a port (or
+/// bridge), or a bridge's global VLAN options.
+#[derive(AttrSet, Default, Debug, Clone, PartialEq)]
+pub struct BridgeVlanMsg {
+    /// The port or bridge, from struct br_vlan_msg.
+    pub index: i32,
+    #[nla(type = BridgeVlandb::ENTRY, nested, repeated)]
+    pub vlans: Vec<BridgeVlan>,
+    #[nla(type = BridgeVlandb::GLOBAL_OPTIONS, nested, repeated)]
+    pub global_options: Vec<BridgeVlanGlobalOpts>,
+}
+
+/// The size of struct br_vlan_msg: the family, padding and an ifindex.
+const BR_VLAN_MSG_SIZE: usize = 8;
+
+fn br_vlan_msg(idx: i32) -> Vec<u8> {
+    let mut out = vec![uapi::AF_BRIDGE as u8, 0, 0, 0];
+    out.extend_from_slice(&idx.to_ne_bytes());
+    out
+}
+
+impl BridgeVlanMsg {
+    pub fn from_message(nlmsg: &NetlinkMessage) -> Result<BridgeVlanMsg> {
+        BridgeVlanMsg::from_ref(&NetlinkMessageRef::from(nlmsg))
+    }
+
+    pub fn from_ref(nlmsg: &NetlinkMessageRef) -> Result<BridgeVlanMsg> {
+        if nlmsg.data.len() < BR_VLAN_MSG_SIZE {
+            return Err(NetlinkError::Truncated {
+                what: "br_vlan_msg",
+                offset: 0,
+                attr: None,
+            });
+        }
+        let d = nlmsg.data;
+        let mut out = BridgeVlanMsg::from_bytes(&d[BR_VLAN_MSG_SIZE..])?;
+        out.index = i32::from_ne_bytes([d[4], d[5], d[6], d[7]]);
+        Ok(out)
+    }
+
+    fn to_request(&self, typ: RtmType) -> NetlinkMessage {
+        let mut req = NetlinkMessage::new(typ, NlmFlags::REQUEST | NlmFlags::ACK);
+        req.add_data(br_vlan_msg(self.index));
+        for attr in self.to_attrs().iter() {
+            req.add_data(attr.to_bytes());
+        }
+        req
+    }
+}
+
+/// Lists port VLANs with an RTM_GETVLAN dump, or with
+/// VlandbDumpFlags::GLOBAL, the bridges' global VLAN options. `idx` limits
+/// it to one port or bridge; 0 means all of them. An interrupted dump is
+/// handled according to `mode`, as with link_list_with_mode.
+pub fn bridge_vlandb_list<T: Transport + ?Sized>(
+    sock: &mut T,
+    idx: i32,
+    flags: VlandbDumpFlags,
+    mode: DumpMode,
+) -> Result<Vec<BridgeVlanMsg>> {
+    let mut req = NetlinkMessage::new(RtmType::GETVLAN, NlmFlags::DUMP | NlmFlags::REQUEST);
+    req.add_data(br_vlan_msg(idx));
+    if !flags.is_empty() {
+        req.add_data(RtAttr::new_u32(BridgeVlandbDump::FLAGS, flags.bits()).to_bytes());
+    }
+
+    dump_with(sock, &mut req, mode, |nlmsg| {
+        if nlmsg.header.typ != RtmType::NEWVLAN {
+            return Ok(None);
+        }
+        BridgeVlanMsg::from_ref(&nlmsg).map(Some)
+    })
+}
+
+/// Adds VLANs to a port, or changes their options, such as their STP
+/// state, with RTM_NEWVLAN. With BridgeVlanFlags::ONLY_OPTS, existing
+/// VLANs are changed without being added again. A bridge's global VLAN
+/// options are set the same way, with the bridge's index.
+pub fn bridge_vlandb_set<T: Transport + ?Sized>(sock: &mut T, msg: &BridgeVlanMsg) -> Result<()> {
+    let mut req = msg.to_request(RtmType::NEWVLAN);
+    sock.exec(&mut req, None)?;
+    Ok(())
+}
+
+/// Removes VLANs from a port with RTM_DELVLAN.
+pub fn bridge_vlandb_del<T: Transport + ?Sized>(sock: &mut T, msg: &BridgeVlanMsg) -> Result<()> {
+    let mut req = msg.to_request(RtmType::DELVLAN);
+    sock.exec(&mut req, None)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bridge_vlan_add, bridge_vlan_del, bridge_vlan_list, bridge_vlandb_list, bridge_vlandb_set,
+        BridgeFlags, BridgeVlan, BridgeVlanFlags, BridgeVlanMsg, VlandbDumpFlags,
+    };
+    use crate::hl::iface::{BrState, LinkMsg};
+    use crate::proto::conn::DumpMode;
+    use crate::proto::MockTransport;
+    use crate::type_route::{IfInfoMsg, IflaBridge, RtAttr, RtAttrRef, RtmType};
+
+    #[test]
+    fn test_bridge_vlans() {
+        let vlans = vec![
+            BridgeVlan::new(1, BridgeVlanFlags::PVID | BridgeVlanFlags::UNTAGGED),
+            BridgeVlan::range(10, 20, BridgeVlanFlags::empty()),
+        ];
+
+        // The legacy requests send ranges as their first and last VLAN.
+        let mut mock = MockTransport::new();
+        mock.expect(RtmType::SETLINK, vec![])
+            .expect(RtmType::DELLINK, vec![]);
+        bridge_vlan_add(&mut mock, 4, BridgeFlags::empty(), &vlans).unwrap();
+        bridge_vlan_del(&mut mock, 4, BridgeFlags::SELF, &vlans[1..]).unwrap();
+        mock.assert_done();
+
+        let mut added = mock.sent()[0].clone();
+        let spec: Vec<RtAttr> = RtAttrRef::iter(&added.data[IfInfoMsg::size()..])
+            .next()
+            .unwrap()
+            .unwrap()
+            .nested()
+            .map(|a| a.unwrap().to_rtattr())
+            .collect();
+        assert_eq!(spec.len(), 3);
+        assert!(spec.iter().all(|a| a.get_typ() == IflaBridge::VLAN_INFO));
+        let deleted = LinkMsg::from_message(&mock.sent()[1]).unwrap();
+        assert_eq!(deleted.bridge_vlans, &vlans[1..]);
+
+        // A dump is answered the same way.
+        added.header.typ = RtmType::NEWLINK.into();
+        let mut mock = MockTransport::new();
+        mock.expect(RtmType::GETLINK, vec![added]);
+        let links = bridge_vlan_list(&mut mock).unwrap();
+        mock.assert_done();
+        assert_eq!((links[0].index, &links[0].bridge_vlans), (4, &vlans));
+
+        // RTM_NEWVLAN has the range and per-VLAN options as attributes.
+        let msg = BridgeVlanMsg {
+            index: 4,
+            vlans: vec![BridgeVlan {
+                state: Some(BrState::BLOCKING),
+                ..BridgeVlan::range(10, 20, BridgeVlanFlags::ONLY_OPTS)
+            }],
+            ..Default::default()
+        };
+        let mut mock = MockTransport::new();
+        mock.expect(RtmType::NEWVLAN, vec![]);
+        bridge_vlandb_set(&mut mock, &msg).unwrap();
+        let mut newvlan = mock.sent()[0].clone();
+        assert_eq!(BridgeVlanMsg::from_message(&newvlan).unwrap(), msg);
+
+        newvlan.header.typ = RtmType::NEWVLAN.into();
+        let mut mock = MockTransport::new();
+        mock.expect(RtmType::GETVLAN, vec![newvlan]);
+        let dumped =
+            bridge_vlandb_list(&mut mock, 0, VlandbDumpFlags::STATS, DumpMode::default()).unwrap();
+        mock.assert_done();
+        assert_eq!(dumped, vec![msg]);
+    }
+}
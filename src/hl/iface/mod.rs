@@ -6,16 +6,29 @@
 /// the kernel will "always" send them when retrieving a link.
 ///
 /// Likewise, when creating or updating a link, most fields are optional.
+mod bridge;
+pub use self::bridge::{
+    bridge_port_list, bridge_port_set, BrState, Bridge, BridgeBoolOpts, BridgeId, BridgePort,
+};
+mod bridge_vlan;
+pub use self::bridge_vlan::{
+    bridge_vlan_add, bridge_vlan_del, bridge_vlan_list, bridge_vlandb_del, bridge_vlandb_list,
+    bridge_vlandb_set, BridgeFlags, BridgeVlan, BridgeVlanFlags, BridgeVlanGlobalOpts,
+    BridgeVlanInfo, BridgeVlanMsg, BridgeVlanStats, VlandbDumpFlags,
+};
 mod ifflags;
 pub use self::ifflags::IfFlags;
-use crate::proto::conn::NetlinkSocket;
-use crate::proto::NetlinkMessage;
-use crate::type_route::{IfInfoMsg, RtAttr};
+use crate::proto::conn::DumpMode;
+use crate::proto::transport::{dump_with, Responses, Transport};
+use crate::proto::{NetlinkMessage, NetlinkMessageRef, NlmFlags};
+use crate::type_route::{
+    AttrSet, IfInfoMsg, Ifla, IflaInfo, IflaVlan, RtAttr, RtAttrRef, RtmType, VethInfo,
+};
 use crate::uapi;
-use crate::Serializable;
+use crate::{NetlinkError, Result, Serializable};
 use std::default::Default;
 use std::ffi::CString;
-use std::io::{Error, ErrorKind, Result};
+use std::os::unix::io::RawFd;
 
 // First attempt: everything is a Maybe
 
@@ -24,10 +37,13 @@ use std::io::{Error, ErrorKind, Result};
 ///
 /// All fieds are optional except index, flags, and flags_change, because the
 /// kernel doesn't actually require them
+///
+/// When sent to the kernel, only the flags in flags_change are changed; see
+/// `set_flags`. If flags_change is empty, flags replaces all of the link's
+/// flags, unless it is empty too.
 #[derive(Default, Debug)]
 pub struct LinkMsg {
     pub index: i32,
-    // TODO: implement a flags change type
     pub flags: IfFlags,
     pub flags_change: IfFlags,
 
@@ -40,9 +56,48 @@ pub struct LinkMsg {
     pub promisc: Option<i32>,
     pub kind: Option<CString>,
     pub master_index: Option<u32>,
+    /// The namespace parent_index is in, as a netns id relative to the
+    /// namespace the link was dumped from (IFLA_LINK_NETNSID). Not sent.
+    pub link_netnsid: Option<i32>,
+    /// Moves the link to (or creates it in) another namespace. Not set
+    /// when dumping.
+    pub netns: Option<LinkNetNs>,
     pub specific: LinkType,
+
+    /// The kind of the link's master, if it has one (IFLA_INFO_SLAVE_KIND).
+    pub slave_kind: Option<CString>,
+    /// The link's settings as a bridge port, from IFLA_INFO_SLAVE_DATA or,
+    /// in AF_BRIDGE messages, IFLA_PROTINFO. Not sent by link_add or
+    /// link_set; see `bridge_port_set`.
+    pub bridge_port: Option<BridgePort>,
+    /// The link's VLANs, from the IFLA_AF_SPEC of AF_BRIDGE messages; see
+    /// `bridge_vlan_list`. Not sent.
+    pub bridge_vlans: Vec<BridgeVlan>,
+}
+
+/// LinkNetNs is the namespace to put a link in, as sent in IFLA_NET_NS_FD or
+/// IFLA_NET_NS_PID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkNetNs {
+    /// An open namespace file, e.g. /var/run/netns/foo.
+    Fd(RawFd),
+
+    /// The namespace of a process.
+    Pid(libc::pid_t),
 }
 
+impl LinkNetNs {
+    fn to_attr(self) -> RtAttr {
+        match self {
+            LinkNetNs::Fd(fd) => RtAttr::new_u32(Ifla::NET_NS_FD, fd as u32),
+            LinkNetNs::Pid(pid) => RtAttr::new_u32(Ifla::NET_NS_PID, pid as u32),
+        }
+    }
+}
+
+// Bridge is much bigger than the rest, but links aren't kept around in
+// large enough numbers for that to matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum LinkType {
     Unknown,
@@ -61,36 +116,27 @@ impl Default for LinkType {
 
 impl LinkType {
     pub fn from_attrs(kind: &CString, rt_attrs: &Vec<RtAttr>) -> Result<LinkType> {
-        let kind = kind.to_str().unwrap();
-        let out = match kind {
-            "bridge" => {
-                let mut b: Bridge = Default::default();
-                for rt_attr in rt_attrs.iter() {
-                    match rt_attr.get_typ() as u32 {
-                        uapi::IFLA_BR_VLAN_FILTERING => {
-                            b.vlan_filtering = Some(rt_attr.as_bool().unwrap())
-                        }
-                        _ => {}
-                    }
-                }
-                LinkType::Bridge(b)
+        let kind = match kind.to_str() {
+            Ok(kind) => kind,
+            Err(_) => {
+                return Err(NetlinkError::Malformed {
+                    what: "link kind",
+                    offset: 0,
+                    attr: Some(IflaInfo::KIND.into()),
+                })
             }
+        };
+        let attrs = rt_attrs.iter().map(|a| Ok(RtAttrRef::from(a)));
+        let out = match kind {
+            "bridge" => LinkType::Bridge(Bridge::from_attrs(attrs)?),
             "dummy" => LinkType::Dummy {},
-            // TODO ifb
-
-            // veth fields are create-only, oddly enough
-            "veth" => LinkType::Veth(Default::default()),
-
-            "vlan" => {
-                let mut v: Vlan = Default::default();
-                for rt_attr in rt_attrs.iter() {
-                    match rt_attr.get_typ() as u32 {
-                        uapi::IFLA_VLAN_ID => v.vlan_id = Some(rt_attr.as_u16().unwrap()),
-                        _ => {}
-                    };
-                }
-                LinkType::Vlan(v)
-            }
+            "ifb" => LinkType::Ifb,
+
+            // veth fields are create-only, oddly enough; the kernel only
+            // sends the peer's index, in IFLA_LINK
+            "veth" => LinkType::Veth(Veth::from_attrs(attrs)?),
+
+            "vlan" => LinkType::Vlan(Vlan::from_attrs(attrs)?),
 
             // unrecognized link type
             _ => LinkType::Unknown,
@@ -98,33 +144,123 @@ impl LinkType {
 
         Ok(out)
     }
+
+    /// The IFLA_INFO_KIND of this type of link, if it is a known one.
+    pub fn kind(&self) -> Option<&'static str> {
+        match self {
+            LinkType::Unknown => None,
+            LinkType::Bridge(_) => Some("bridge"),
+            LinkType::Dummy => Some("dummy"),
+            LinkType::Ifb => Some("ifb"),
+            LinkType::Veth(_) => Some("veth"),
+            LinkType::Vlan(_) => Some("vlan"),
+        }
+    }
+
+    /// Serializes the kind-specific settings, which go in IFLA_INFO_DATA.
+    pub fn to_attrs(&self) -> Vec<RtAttr> {
+        match self {
+            LinkType::Bridge(br) => br.to_attrs(),
+            LinkType::Veth(veth) => veth.to_attrs(),
+            LinkType::Vlan(vlan) => vlan.to_attrs(),
+            _ => vec![],
+        }
+    }
 }
 
-#[derive(Default, Debug)]
+#[derive(AttrSet, Default, Debug)]
 pub struct Vlan {
-    vlan_id: Option<u16>,
+    #[nla(type = IflaVlan::ID)]
+    pub vlan_id: Option<u16>,
 }
 
+/// A veth pair. The peer is created along with the link, and the peer_*
+/// settings are only used then; if they aren't set, the kernel picks them.
 #[derive(Default, Debug)]
 pub struct Veth {
-    // supported on create only
-    peer_name: Option<i32>,
+    pub peer_name: Option<CString>,
+    pub peer_hardware_addr: Option<Vec<u8>>,
+    pub peer_mtu: Option<u32>,
+    /// Creates the peer in another namespace.
+    pub peer_netns: Option<LinkNetNs>,
+
+    /// The peer's index, from IFLA_LINK when dumping. If peer_netnsid is
+    /// set, the index is in that namespace.
+    pub peer_index: Option<u32>,
+    /// The peer's namespace, if it's in another one, from
+    /// IFLA_LINK_NETNSID.
+    pub peer_netnsid: Option<i32>,
 }
 
-#[derive(Default, Debug)]
-pub struct Bridge {
-    vlan_filtering: Option<bool>,
+impl Veth {
+    /// Parses VETH_INFO_PEER, which holds an ifinfomsg followed by the
+    /// peer's link attributes. The kernel only accepts it, so this is just
+    /// for reading back requests.
+    fn from_attrs<'a, I>(attrs: I) -> Result<Veth>
+    where
+        I: Iterator<Item = Result<RtAttrRef<'a>>>,
+    {
+        let mut out = Veth::default();
+        for attr in attrs {
+            let attr = attr?;
+            if attr.get_typ() != VethInfo::PEER {
+                continue;
+            }
+            let info = IfInfoMsg::from_bytes(attr.data)?;
+            let peer =
+                LinkMsg::from_attr_refs(&info, RtAttrRef::iter(&attr.data[IfInfoMsg::size()..]))?;
+            out.peer_name = peer.name;
+            out.peer_hardware_addr = peer.hadrware_addr;
+            out.peer_mtu = peer.mtu;
+            out.peer_netns = peer.netns;
+        }
+        Ok(out)
+    }
+
+    /// Serializes VETH_INFO_PEER, if any of the peer's settings are set.
+    pub fn to_attrs(&self) -> Vec<RtAttr> {
+        let peer = LinkMsg {
+            name: self.peer_name.clone(),
+            hadrware_addr: self.peer_hardware_addr.clone(),
+            mtu: self.peer_mtu,
+            netns: self.peer_netns,
+            ..Default::default()
+        };
+        let attrs = peer.base_attrs();
+        if attrs.is_empty() {
+            return vec![];
+        }
+
+        // Not flagged NLA_F_NESTED, since it starts with a struct.
+        let mut out = RtAttr::new(VethInfo::PEER, peer.to_ifinfo().to_bytes());
+        for attr in attrs.iter() {
+            out.add_data(attr);
+        }
+        vec![out]
+    }
 }
 
 impl LinkMsg {
     pub fn from_message(nlmsg: &NetlinkMessage) -> Result<LinkMsg> {
+        LinkMsg::from_ref(&NetlinkMessageRef::from(nlmsg))
+    }
+
+    /// Parses a link straight out of the buffer the message is in.
+    pub fn from_ref(nlmsg: &NetlinkMessageRef) -> Result<LinkMsg> {
         // peel off ifinfo message
-        let ifinfo = IfInfoMsg::from_bytes(&nlmsg.data)?;
-        let attrs = RtAttr::from_bytes(&nlmsg.data[IfInfoMsg::size()..])?;
-        let link = LinkMsg::from_attrs(&ifinfo, &attrs)?;
-        Ok(link)
+        let ifinfo = IfInfoMsg::from_bytes(nlmsg.data)?;
+        let attrs = RtAttrRef::iter(&nlmsg.data[IfInfoMsg::size()..]);
+        LinkMsg::from_attr_refs(&ifinfo, attrs)
     }
+
     pub fn from_attrs(info: &IfInfoMsg, rt_attrs: &Vec<RtAttr>) -> Result<LinkMsg> {
+        LinkMsg::from_attr_refs(info, rt_attrs.iter().map(|a| Ok(RtAttrRef::from(a))))
+    }
+
+    fn from_attr_refs<'a, I>(info: &IfInfoMsg, rt_attrs: I) -> Result<LinkMsg>
+    where
+        I: Iterator<Item = Result<RtAttrRef<'a>>>,
+    {
         let mut out = LinkMsg {
             index: info.index,
             flags: IfFlags::from_bits_truncate(info.flags),
@@ -132,67 +268,252 @@ impl LinkMsg {
             ..Default::default()
         };
 
-        // todo: get rid of all of these unwraps
-        // need to plumb through the result
-        for rt_attr in rt_attrs.iter() {
-            match rt_attr.get_typ() as u32 {
-                uapi::IFLA_MTU => out.mtu = Some(rt_attr.as_u32().unwrap()),
-                uapi::IFLA_IFNAME => out.name = Some(rt_attr.to_cstring().unwrap()),
-                uapi::IFLA_TXQLEN => out.tx_q_len = Some(rt_attr.as_u32().unwrap()),
+        let mut info_data = vec![];
+        let mut slave_data = vec![];
+        for rt_attr in rt_attrs {
+            let rt_attr = rt_attr?;
+            match Ifla(rt_attr.get_typ()) {
+                Ifla::MTU => out.mtu = Some(rt_attr.as_u32()?),
+                Ifla::IFNAME => out.name = Some(rt_attr.to_cstring()?),
+                Ifla::TXQLEN => out.tx_q_len = Some(rt_attr.as_u32()?),
                 // TODO: filter all-zero hwaddrs
-                uapi::IFLA_ADDRESS => out.hadrware_addr = Some(rt_attr.data.to_owned()),
-                uapi::IFLA_LINK => out.parent_index = Some(rt_attr.as_u32().unwrap()),
-                uapi::IFLA_MASTER => out.master_index = Some(rt_attr.as_u32().unwrap()),
-                uapi::IFLA_IFALIAS => out.alias = Some(rt_attr.to_cstring().unwrap()),
+                Ifla::ADDRESS => out.hadrware_addr = Some(rt_attr.data.to_owned()),
+                Ifla::LINK => out.parent_index = Some(rt_attr.as_u32()?),
+                Ifla::MASTER => out.master_index = Some(rt_attr.as_u32()?),
+                Ifla::LINK_NETNSID => out.link_netnsid = Some(rt_attr.as_i32()?),
+                Ifla::NET_NS_FD => out.netns = Some(LinkNetNs::Fd(rt_attr.as_u32()? as RawFd)),
+                Ifla::NET_NS_PID => {
+                    out.netns = Some(LinkNetNs::Pid(rt_attr.as_u32()? as libc::pid_t))
+                }
+                Ifla::IFALIAS => out.alias = Some(rt_attr.to_cstring()?),
                 // LINKINFO is just a nested list of more attributes
-                uapi::IFLA_LINKINFO => {
-                    let info_attrs = RtAttr::from_bytes(&rt_attr.data)?;
-                    for info_attr in info_attrs.iter() {
-                        match info_attr.get_typ() as u32 {
-                            uapi::IFLA_INFO_KIND => {
-                                out.kind = Some(info_attr.to_cstring().unwrap())
+                Ifla::LINKINFO => {
+                    for info_attr in rt_attr.nested() {
+                        let info_attr = info_attr?;
+                        match IflaInfo(info_attr.get_typ()) {
+                            IflaInfo::KIND => out.kind = Some(info_attr.to_cstring()?),
+                            // kind-specific data, which is another array of
+                            // rtattrs. It can only be parsed once the kind is
+                            // known.
+                            IflaInfo::DATA => {
+                                for data_attr in info_attr.nested() {
+                                    info_data.push(data_attr?.to_rtattr());
+                                }
+                            }
+                            // likewise for the settings as a port of its
+                            // master
+                            IflaInfo::SLAVE_KIND => out.slave_kind = Some(info_attr.to_cstring()?),
+                            IflaInfo::SLAVE_DATA => {
+                                for data_attr in info_attr.nested() {
+                                    slave_data.push(data_attr?.to_rtattr());
+                                }
                             }
-                            // TODO: kind-specific data, which is another array
-                            // of rtattrs
                             _ => {}
                         }
                     }
                 }
+                // AF_BRIDGE messages hold the port settings here instead.
+                // Other families use it for their own things, or (old
+                // kernels) for just the port state, unnested.
+                Ifla::PROTINFO if info.family == uapi::AF_BRIDGE as u8 && rt_attr.is_nested() => {
+                    out.bridge_port = Some(BridgePort::from_attrs(rt_attr.nested())?)
+                }
+                Ifla::AF_SPEC if info.family == uapi::AF_BRIDGE as u8 => {
+                    out.bridge_vlans = bridge_vlan::vlans_from_af_spec(rt_attr.nested())?
+                }
                 _ => {}
             }
         }
 
+        if let Some(kind) = &out.kind {
+            out.specific = LinkType::from_attrs(kind, &info_data)?;
+        }
+        if out.slave_kind.as_ref().map(|k| k.to_bytes()) == Some(&b"bridge"[..]) {
+            let attrs = slave_data.iter().map(|a| Ok(RtAttrRef::from(a)));
+            out.bridge_port = Some(BridgePort::from_attrs(attrs)?);
+        }
+        if let LinkType::Veth(veth) = &mut out.specific {
+            veth.peer_index = out.parent_index;
+            veth.peer_netnsid = out.link_netnsid;
+        }
+
         Ok(out)
     }
+
+    /// Sets (or clears) `flags`, e.g. IfFlags::UP, and marks them in
+    /// flags_change so that only they are changed.
+    pub fn set_flags(&mut self, flags: IfFlags, on: bool) {
+        self.flags.set(flags, on);
+        self.flags_change |= flags;
+    }
+
+    /// Serializes the link's attributes, including IFLA_LINKINFO if the
+    /// kind of link is known.
+    pub fn to_attrs(&self) -> Vec<RtAttr> {
+        let mut out = self.base_attrs();
+        if let Some(info) = self.link_info() {
+            out.push(info);
+        }
+        out
+    }
+
+    /// All the attributes but IFLA_LINKINFO.
+    fn base_attrs(&self) -> Vec<RtAttr> {
+        let mut out = vec![];
+        if let Some(name) = &self.name {
+            out.push(RtAttr::new_cstr(Ifla::IFNAME, name));
+        }
+        if let Some(mtu) = self.mtu {
+            out.push(RtAttr::new_u32(Ifla::MTU, mtu));
+        }
+        if let Some(tx_q_len) = self.tx_q_len {
+            out.push(RtAttr::new_u32(Ifla::TXQLEN, tx_q_len));
+        }
+        if let Some(addr) = &self.hadrware_addr {
+            out.push(RtAttr::new(Ifla::ADDRESS, addr.clone()));
+        }
+        if let Some(parent) = self.parent_index {
+            out.push(RtAttr::new_u32(Ifla::LINK, parent));
+        }
+        if let Some(master) = self.master_index {
+            out.push(RtAttr::new_u32(Ifla::MASTER, master));
+        }
+        if let Some(alias) = &self.alias {
+            out.push(RtAttr::new_cstr(Ifla::IFALIAS, alias));
+        }
+        if let Some(netns) = self.netns {
+            out.push(netns.to_attr());
+        }
+        out
+    }
+
+    /// IFLA_LINKINFO, holding the kind and any kind-specific settings. The
+    /// kind comes from `specific`, or failing that `kind`.
+    fn link_info(&self) -> Option<RtAttr> {
+        let kind = match (self.specific.kind(), &self.kind) {
+            (Some(kind), _) => RtAttr::new_str(IflaInfo::KIND, kind),
+            (None, Some(kind)) => RtAttr::new_cstr(IflaInfo::KIND, kind),
+            (None, None) => return None,
+        };
+        let mut info = vec![kind];
+        let data = self.specific.to_attrs();
+        if !data.is_empty() {
+            info.push(RtAttr::new_nested(IflaInfo::DATA, data));
+        }
+        Some(RtAttr::new_nested(Ifla::LINKINFO, info))
+    }
+
+    fn to_ifinfo(&self) -> IfInfoMsg {
+        IfInfoMsg {
+            family: uapi::AF_UNSPEC as u8,
+            index: self.index,
+            flags: self.flags.bits(),
+            change: self.flags_change.bits(),
+            ..Default::default()
+        }
+    }
+
+    /// Builds a request about this link, acked by the kernel.
+    fn to_request(&self, typ: RtmType, flags: NlmFlags, attrs: Vec<RtAttr>) -> NetlinkMessage {
+        let mut req = NetlinkMessage::new(typ, flags | NlmFlags::REQUEST | NlmFlags::ACK);
+        req.add_data(self.to_ifinfo().to_bytes());
+        for attr in attrs.iter() {
+            req.add_data(attr.to_bytes());
+        }
+        req
+    }
 }
 
-pub fn link_list(sock: &mut NetlinkSocket) -> Result<Vec<LinkMsg>> {
-    let mut req = NetlinkMessage::new(
-        uapi::RTM_GETLINK as u16,
-        (uapi::NLM_F_DUMP | uapi::NLM_F_REQUEST) as u16,
-    );
+fn link_dump_request(family: u8) -> NetlinkMessage {
+    let mut req = NetlinkMessage::new(RtmType::GETLINK, NlmFlags::DUMP | NlmFlags::REQUEST);
     let msg = IfInfoMsg {
-        family: uapi::AF_UNSPEC as u8,
+        family,
         ..Default::default()
     };
     req.add_data(msg.to_bytes());
+    req
+}
 
-    let resp = sock.exec(&mut req, Some(uapi::RTM_NEWLINK as u16))?;
+/// Lists all links. If the dump is interrupted by a change, it is retried;
+/// see `DumpMode`. Links are decoded straight out of the receive buffer.
+pub fn link_list<T: Transport + ?Sized>(sock: &mut T) -> Result<Vec<LinkMsg>> {
+    link_list_with_mode(sock, DumpMode::default())
+}
 
-    let mut out = vec![];
-    for nlmsg in resp {
-        let link = LinkMsg::from_message(&nlmsg)?;
-        out.push(link);
-    }
+/// Lists all links, like `link_list`, handling an interrupted dump
+/// according to `mode`.
+pub fn link_list_with_mode<T: Transport + ?Sized>(
+    sock: &mut T,
+    mode: DumpMode,
+) -> Result<Vec<LinkMsg>> {
+    link_dump(sock, &mut link_dump_request(uapi::AF_UNSPEC as u8), mode)
+}
 
-    Ok(out)
+/// Runs a link dump, decoding the RTM_NEWLINK responses.
+fn link_dump<T: Transport + ?Sized>(
+    sock: &mut T,
+    req: &mut NetlinkMessage,
+    mode: DumpMode,
+) -> Result<Vec<LinkMsg>> {
+    dump_with(sock, req, mode, |nlmsg| {
+        if nlmsg.header.typ != RtmType::NEWLINK {
+            return Ok(None);
+        }
+        LinkMsg::from_ref(&nlmsg).map(Some)
+    })
 }
 
-pub fn link_get_by_index(sock: &mut NetlinkSocket, idx: i32) -> Result<LinkMsg> {
-    let mut req = NetlinkMessage::new(
-        uapi::RTM_GETLINK as u16,
-        (uapi::NLM_F_ACK | uapi::NLM_F_REQUEST) as u16,
-    );
+/// Lists all links, decoding each one as it arrives rather than collecting
+/// them first. Stopping early is fine; the rest of the dump is discarded
+/// when the iterator is dropped.
+///
+/// An interrupted dump can't be retried here, since the links have already
+/// been handed out. Instead the iterator ends with
+/// NetlinkError::DumpInterrupted (with no messages in it).
+pub fn link_iter<T: Transport + ?Sized>(sock: &mut T) -> Result<Links<'_, T>> {
+    let mut req = link_dump_request(uapi::AF_UNSPEC as u8);
+    Ok(Links {
+        resps: sock.request(&mut req)?,
+        done: false,
+    })
+}
+
+/// Links is an iterator over a link dump, returned by `link_iter`.
+pub struct Links<'a, T: Transport + ?Sized> {
+    resps: Responses<'a, T>,
+    done: bool,
+}
+
+impl<'a, T: Transport + ?Sized> Iterator for Links<'a, T> {
+    type Item = Result<LinkMsg>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            match self.resps.next() {
+                Some(Ok(nlmsg)) => {
+                    if nlmsg.header.typ != RtmType::NEWLINK {
+                        continue;
+                    }
+                    return Some(LinkMsg::from_message(&nlmsg));
+                }
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    self.done = true;
+                    if self.resps.interrupted() {
+                        return Some(Err(NetlinkError::DumpInterrupted(vec![])));
+                    }
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+pub fn link_get_by_index<T: Transport + ?Sized>(sock: &mut T, idx: i32) -> Result<LinkMsg> {
+    let mut req = NetlinkMessage::new(RtmType::GETLINK, NlmFlags::ACK | NlmFlags::REQUEST);
     let msg = IfInfoMsg {
         family: uapi::AF_UNSPEC as u8,
         index: idx,
@@ -200,10 +521,212 @@ pub fn link_get_by_index(sock: &mut NetlinkSocket, idx: i32) -> Result<LinkMsg>
     };
     req.add_data(msg.to_bytes());
 
-    let resp = sock.exec(&mut req, Some(uapi::RTM_NEWLINK as u16))?;
+    let resp = sock.exec(&mut req, Some(RtmType::NEWLINK.into()))?;
     match resp.len() {
-        0 => Err(Error::new(ErrorKind::NotFound, "link not found")),
+        0 => Err(NetlinkError::NotFound("link not found")),
         1 => LinkMsg::from_message(&resp[0]),
-        _ => Err(Error::new(ErrorKind::Other, "too many links returned")),
+        _ => Err(NetlinkError::Protocol("too many links returned")),
+    }
+}
+
+/// Creates a link. The kind of link comes from `link.specific`, or
+/// `link.kind` for kinds without a LinkType of their own. Fails with EEXIST
+/// if the name is taken.
+pub fn link_add<T: Transport + ?Sized>(sock: &mut T, link: &LinkMsg) -> Result<()> {
+    let flags = NlmFlags::CREATE | NlmFlags::EXCL;
+    let mut req = link.to_request(RtmType::NEWLINK, flags, link.to_attrs());
+    sock.exec(&mut req, None)?;
+    Ok(())
+}
+
+/// Changes a link, found by index or, if the index is 0, by name. Only the
+/// fields that are set are changed.
+///
+/// RTM_SETLINK can't change kind-specific settings, so if `link.specific`
+/// has any, this sends an RTM_NEWLINK without NLM_F_CREATE instead, which
+/// the kernel treats as a change to an existing link (as `ip link set`
+/// does).
+pub fn link_set<T: Transport + ?Sized>(sock: &mut T, link: &LinkMsg) -> Result<()> {
+    let mut req = if link.specific.to_attrs().is_empty() {
+        link.to_request(RtmType::SETLINK, NlmFlags::empty(), link.base_attrs())
+    } else {
+        link.to_request(RtmType::NEWLINK, NlmFlags::empty(), link.to_attrs())
+    };
+    sock.exec(&mut req, None)?;
+    Ok(())
+}
+
+/// Brings a link up or down.
+pub fn link_set_up<T: Transport + ?Sized>(sock: &mut T, idx: i32, up: bool) -> Result<()> {
+    let mut link = LinkMsg {
+        index: idx,
+        ..Default::default()
+    };
+    link.set_flags(IfFlags::UP, up);
+    link_set(sock, &link)
+}
+
+/// Deletes a link, found by index or, if the index is 0, by name.
+pub fn link_del<T: Transport + ?Sized>(sock: &mut T, link: &LinkMsg) -> Result<()> {
+    let mut attrs = vec![];
+    if let Some(name) = &link.name {
+        attrs.push(RtAttr::new_cstr(Ifla::IFNAME, name));
+    }
+    let del = LinkMsg {
+        index: link.index,
+        ..Default::default()
+    };
+    let mut req = del.to_request(RtmType::DELLINK, NlmFlags::empty(), attrs);
+    sock.exec(&mut req, None)?;
+    Ok(())
+}
+
+/// An RTM_NEWLINK for a link called `name`, as the kernel would send it,
+/// for the tests here and elsewhere.
+#[cfg(test)]
+pub(crate) fn test_link(index: i32, name: &str, flags: NlmFlags) -> NetlinkMessage {
+    let mut msg = NetlinkMessage::new(RtmType::NEWLINK, flags);
+    let info = IfInfoMsg {
+        index,
+        ..Default::default()
+    };
+    msg.add_data(info.to_bytes());
+    msg.add_data(RtAttr::new_str(Ifla::IFNAME, name).to_bytes());
+    msg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        link_add, link_del, link_list, link_list_with_mode, link_set, link_set_up, test_link,
+        Bridge, IfFlags, LinkMsg, LinkNetNs, LinkType, Veth,
+    };
+    use crate::proto::conn::DumpMode;
+    use crate::proto::{MockTransport, NetlinkMessage, NlmFlags};
+    use crate::type_route::{IfInfoMsg, Ifla, IflaInfo, RtAttr, RtmType};
+    use crate::{NetlinkError, Serializable};
+    use std::ffi::CString;
+
+    #[test]
+    fn test_link_list_interrupted() {
+        let intr = || vec![test_link(1, "lo", NlmFlags::DUMP_INTR)];
+        let whole = vec![
+            test_link(1, "lo", NlmFlags::empty()),
+            test_link(2, "eth0", NlmFlags::empty()),
+        ];
+
+        // retried by default
+        let mut mock = MockTransport::new();
+        mock.expect(RtmType::GETLINK, intr())
+            .expect(RtmType::GETLINK, whole);
+        assert_eq!(link_list(&mut mock).unwrap().len(), 2);
+        mock.assert_done();
+
+        mock.expect(RtmType::GETLINK, intr());
+        match link_list_with_mode(&mut mock, DumpMode::Partial) {
+            Err(NetlinkError::DumpInterrupted(msgs)) => assert_eq!(msgs.len(), 1),
+            other => panic!("unexpected result {:?}", other),
+        }
+        mock.assert_done();
+
+        mock.expect(RtmType::GETLINK, intr())
+            .expect(RtmType::GETLINK, intr());
+        let res = link_list_with_mode(&mut mock, DumpMode::Retry(1));
+        assert!(matches!(res, Err(NetlinkError::DumpInterrupted(_))));
+        mock.assert_done();
+    }
+
+    #[test]
+    fn test_link_add_set_del() {
+        let mut mock = MockTransport::new();
+        mock.expect(RtmType::NEWLINK, vec![])
+            .expect(RtmType::SETLINK, vec![])
+            .expect(RtmType::NEWLINK, vec![])
+            .expect(RtmType::DELLINK, vec![]);
+
+        let mut link = LinkMsg {
+            name: Some(CString::new("br0").unwrap()),
+            mtu: Some(9000),
+            specific: LinkType::Bridge(Bridge {
+                vlan_filtering: Some(true),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        link.set_flags(IfFlags::UP, true);
+        link_add(&mut mock, &link).unwrap();
+        link_set_up(&mut mock, 7, false).unwrap();
+        link_set(&mut mock, &link).unwrap();
+        link_del(&mut mock, &link).unwrap();
+        mock.assert_done();
+
+        let sent = mock.sent();
+        let flags = sent[0].header.nlm_flags();
+        assert!(
+            flags.contains(NlmFlags::REQUEST | NlmFlags::ACK | NlmFlags::CREATE | NlmFlags::EXCL)
+        );
+        let parsed = LinkMsg::from_message(&sent[0]).unwrap();
+        assert_eq!(parsed.name, link.name);
+        assert_eq!(parsed.mtu, Some(9000));
+        assert_eq!(parsed.flags, IfFlags::UP);
+        assert_eq!(parsed.kind, Some(CString::new("bridge").unwrap()));
+        match parsed.specific {
+            LinkType::Bridge(br) => assert_eq!(br.vlan_filtering, Some(true)),
+            other => panic!("unexpected link type {:?}", other),
+        }
+
+        // down: UP is changed, and cleared
+        let info = IfInfoMsg::from_bytes(&sent[1].data).unwrap();
+        assert_eq!(
+            (info.index, info.flags, info.change),
+            (7, 0, IfFlags::UP.bits())
+        );
+
+        // kind-specific changes go in an RTM_NEWLINK without NLM_F_CREATE
+        assert!(!sent[2].header.nlm_flags().contains(NlmFlags::CREATE));
+    }
+
+    #[test]
+    fn test_veth() {
+        let mut mock = MockTransport::new();
+        mock.expect(RtmType::NEWLINK, vec![]);
+
+        let link = LinkMsg {
+            name: Some(CString::new("veth0").unwrap()),
+            specific: LinkType::Veth(Veth {
+                peer_name: Some(CString::new("eth0").unwrap()),
+                peer_hardware_addr: Some(vec![2, 0, 0, 0, 0, 1]),
+                peer_mtu: Some(1450),
+                peer_netns: Some(LinkNetNs::Pid(1234)),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        link_add(&mut mock, &link).unwrap();
+
+        let parsed = LinkMsg::from_message(&mock.sent()[0]).unwrap();
+        match (parsed.specific, link.specific) {
+            (LinkType::Veth(got), LinkType::Veth(want)) => {
+                assert_eq!(got.peer_name, want.peer_name);
+                assert_eq!(got.peer_hardware_addr, want.peer_hardware_addr);
+                assert_eq!(got.peer_mtu, want.peer_mtu);
+                assert_eq!(got.peer_netns, want.peer_netns);
+            }
+            other => panic!("unexpected link types {:?}", other),
+        }
+
+        // When dumped, the peer is found through IFLA_LINK.
+        let mut msg = NetlinkMessage::new(RtmType::NEWLINK, NlmFlags::empty());
+        msg.add_data(IfInfoMsg::default().to_bytes());
+        msg.add_data(RtAttr::new_u32(Ifla::LINK, 9).to_bytes());
+        msg.add_data(RtAttr::new_i32(Ifla::LINK_NETNSID, 2).to_bytes());
+        let info = vec![RtAttr::new_str(IflaInfo::KIND, "veth")];
+        msg.add_data(RtAttr::new_nested(Ifla::LINKINFO, info).to_bytes());
+        match LinkMsg::from_message(&msg).unwrap().specific {
+            LinkType::Veth(veth) => {
+                assert_eq!((veth.peer_index, veth.peer_netnsid), (Some(9), Some(2)))
+            }
+            other => panic!("unexpected link type {:?}", other),
+        }
     }
 }
@@ -1,9 +1,17 @@
 #![allow(dead_code)]
+// Lets code generated by netlink-derive refer to ::netlink from in here.
+extern crate self as netlink;
+
+#[macro_use]
+mod macros;
+mod error;
 pub mod hl;
 pub mod proto;
 pub mod type_route;
 pub mod uapi;
 
+pub use self::error::{NetlinkError, Result};
+
 pub trait Serializable {
     fn to_bytes(&self) -> Vec<u8>;
 }
@@ -38,4 +46,13 @@ pub mod util {
         ((len) + RTA_ALIGNTO - 1) & !(RTA_ALIGNTO - 1)
     }
 
+    /// Reads a native-endian u16 at idx. Panics if b is too short.
+    pub fn u16_at(b: &[u8], idx: usize) -> u16 {
+        u16::from_ne_bytes([b[idx], b[idx + 1]])
+    }
+
+    /// Reads a native-endian u32 at idx. Panics if b is too short.
+    pub fn u32_at(b: &[u8], idx: usize) -> u32 {
+        u32::from_ne_bytes([b[idx], b[idx + 1], b[idx + 2], b[idx + 3]])
+    }
 }
@@ -0,0 +1,65 @@
+/// Declares a newtype over an integer, with a constant for each value the
+/// kernel defines. Debug prints the kernel's name for known values, so a
+/// dumped message reads `IFLA_MTU` rather than `4`.
+///
+/// The newtype converts to and from the integer and compares equal to it,
+/// so it can be used wherever the raw value was.
+macro_rules! typed_consts {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident($t:ty): $prefix:literal {
+            $($(#[$cmeta:meta])* $c:ident = $v:expr;)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+        pub struct $name(pub $t);
+
+        impl $name {
+            $($(#[$cmeta])* pub const $c: $name = $name($v as $t);)*
+
+            /// Returns the kernel's name for this value, if it has one.
+            pub fn name(&self) -> Option<&'static str> {
+                $(
+                    if self.0 == $v as $t {
+                        return Some(concat!($prefix, stringify!($c)));
+                    }
+                )*
+                None
+            }
+        }
+
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                match self.name() {
+                    Some(name) => f.write_str(name),
+                    None => write!(f, "{}({})", stringify!($name), self.0),
+                }
+            }
+        }
+
+        impl From<$t> for $name {
+            fn from(v: $t) -> Self {
+                $name(v)
+            }
+        }
+
+        impl From<$name> for $t {
+            fn from(v: $name) -> Self {
+                v.0
+            }
+        }
+
+        impl PartialEq<$t> for $name {
+            fn eq(&self, other: &$t) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl PartialEq<$name> for $t {
+            fn eq(&self, other: &$name) -> bool {
+                *self == other.0
+            }
+        }
+    };
+}
@@ -0,0 +1,186 @@
+use super::{NetlinkHeader, NetlinkMessage, NlmFlags};
+use crate::type_route::{RtAttr, RtAttrRef};
+use crate::uapi;
+use crate::{NetlinkError, Result};
+use std::fmt;
+use std::io::Error;
+
+// NLMSGERR_ATTR_MISS_TYPE only appeared in the 6.0 headers, so spell it out
+// rather than depend on the build host having them.
+const NLMSGERR_ATTR_MISS_TYPE: u32 = 5;
+
+/// ErrorMessage is the payload of an NLMSG_ERROR message. An errno of 0 means
+/// the request was acknowledged successfully.
+///
+/// If the socket has NETLINK_EXT_ACK enabled, the kernel may also explain
+/// what went wrong, and which attribute in the request it didn't like.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorMessage {
+    /// The (positive) errno returned by the kernel
+    pub errno: i32,
+
+    /// The sequence number of the request this responds to
+    pub seq: u32,
+
+    /// NLMSGERR_ATTR_MSG: a human-readable error message
+    pub msg: Option<String>,
+
+    /// NLMSGERR_ATTR_OFFS: the offset, from the start of the request, of
+    /// the attribute that caused the error
+    pub offset: Option<u32>,
+
+    /// The type of the attribute at `offset`, resolved against the request
+    pub attr: Option<u16>,
+
+    /// NLMSGERR_ATTR_MISS_TYPE: the type of a required attribute that
+    /// was missing from the request
+    pub miss_type: Option<u32>,
+}
+
+impl ErrorMessage {
+    pub fn from_message(nlmsg: &NetlinkMessage) -> Result<ErrorMessage> {
+        let data = &nlmsg.data;
+        if data.len() < 4 + NetlinkHeader::size() {
+            return Err(NetlinkError::Truncated {
+                what: "error message",
+                offset: 0,
+                attr: None,
+            });
+        }
+
+        let mut errno: [u8; 4] = [0; 4];
+        errno.copy_from_slice(&data[0..4]);
+        let request = NetlinkHeader::from_bytes(&data[4..])?;
+
+        let mut out = ErrorMessage {
+            errno: -i32::from_ne_bytes(errno),
+            seq: request.seq,
+            ..Default::default()
+        };
+
+        if !nlmsg.header.nlm_flags().contains(NlmFlags::ACK_TLVS) {
+            return Ok(out);
+        }
+
+        // The kernel echoes back the whole request, unless capped.
+        let mut idx = 4 + NetlinkHeader::size();
+        if !nlmsg.header.nlm_flags().contains(NlmFlags::CAPPED) {
+            idx = crate::util::align(4 + request.len as usize);
+        }
+        if idx >= data.len() {
+            return Ok(out);
+        }
+
+        for attr in RtAttr::from_bytes(&data[idx..])? {
+            match attr.get_typ() as u32 {
+                uapi::NLMSGERR_ATTR_MSG => {
+                    let msg = attr.to_cstring()?;
+                    out.msg = Some(msg.to_string_lossy().into_owned());
+                }
+                uapi::NLMSGERR_ATTR_OFFS => out.offset = Some(attr.as_u32()?),
+                NLMSGERR_ATTR_MISS_TYPE => out.miss_type = Some(attr.as_u32()?),
+                _ => {}
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Is this a successful acknowledgement rather than an error?
+    pub fn is_ack(&self) -> bool {
+        self.errno == 0
+    }
+
+    /// Looks up the type of the attribute pointed to by `offset` in the
+    /// serialized request that caused this error.
+    pub fn resolve_attr(&mut self, request: &[u8]) {
+        if let Some(off) = self.offset {
+            let off = off as usize;
+            if off >= NetlinkHeader::size() && off + 4 <= request.len() {
+                if let Ok(attr) = RtAttrRef::one_from_bytes(request, off) {
+                    self.attr = Some(attr.get_typ());
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ErrorMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Error::from_raw_os_error(self.errno))?;
+        if let Some(msg) = &self.msg {
+            write!(f, ": {}", msg)?;
+        }
+        match (self.attr, self.offset) {
+            (Some(attr), Some(off)) => {
+                write!(f, " (offending attribute type {} at offset {})", attr, off)?
+            }
+            (None, Some(off)) => write!(f, " (offending attribute at offset {})", off)?,
+            _ => {}
+        }
+        if let Some(typ) = self.miss_type {
+            write!(f, " (missing attribute type {})", typ)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ErrorMessage {}
+
+#[cfg(test)]
+mod tests {
+    use super::ErrorMessage;
+    use crate::proto::{NetlinkHeader, NetlinkMessage, NlmFlags, NlmsgType};
+    use crate::type_route::RtAttr;
+    use crate::uapi;
+    use crate::Serializable;
+
+    #[test]
+    fn test_ext_ack() {
+        let mut req = NetlinkMessage::new(16u16, 5u16);
+        req.header.seq = 7;
+        req.add_data(vec![0; 16]);
+        req.add_data(RtAttr::new(3u16, vec![b'x', 0, 0, 0]).to_bytes());
+        let req_bytes = req.to_bytes();
+
+        let mut resp = NetlinkMessage::new(NlmsgType::ERROR, NlmFlags::CAPPED | NlmFlags::ACK_TLVS);
+        let mut d = (-22i32).to_ne_bytes().to_vec();
+        d.extend(&req_bytes[0..NetlinkHeader::size()]);
+        d.extend(RtAttr::new(uapi::NLMSGERR_ATTR_MSG as u16, b"bad name\0".to_vec()).to_bytes());
+        d.extend(vec![0; 3]);
+        d.extend(
+            RtAttr::new(
+                uapi::NLMSGERR_ATTR_OFFS as u16,
+                32u32.to_ne_bytes().to_vec(),
+            )
+            .to_bytes(),
+        );
+        resp.add_data(d);
+
+        let mut e = ErrorMessage::from_message(&resp).unwrap();
+        assert_eq!(e.errno, 22);
+        assert_eq!(e.seq, 7);
+        assert_eq!(e.msg, Some("bad name".to_string()));
+        assert_eq!(e.offset, Some(32));
+        assert!(!e.is_ack());
+
+        e.resolve_attr(&req_bytes);
+        assert_eq!(e.attr, Some(3));
+        assert_eq!(
+            e.to_string(),
+            "Invalid argument (os error 22): bad name (offending attribute type 3 at offset 32)"
+        );
+    }
+
+    #[test]
+    fn test_plain_ack() {
+        let mut resp = NetlinkMessage::new(NlmsgType::ERROR, NlmFlags::empty());
+        let mut d = vec![0; 4];
+        d.extend(NetlinkMessage::new(16u16, 5u16).header.to_bytes());
+        resp.add_data(d);
+
+        let e = ErrorMessage::from_message(&resp).unwrap();
+        assert!(e.is_ack());
+        assert_eq!(e.msg, None);
+    }
+}
@@ -0,0 +1,515 @@
+use super::conn::{
+    check_response, complete, is_interrupted, is_last, DumpMode, NetlinkSocket, Response,
+};
+use super::{NetNs, NetlinkHeader, NetlinkMessage, NlmFlags};
+use crate::uapi;
+use crate::{NetlinkError, Result, Serializable};
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::unix::AsyncFd;
+use tokio::sync::{mpsc, Mutex as AsyncMutex, OwnedMutexGuard};
+use tokio::task::JoinHandle;
+
+/// AsyncNetlinkSocket is a netlink socket for use with tokio.
+///
+/// It is cheap to clone, and all clones share the same underlying socket.
+/// Any number of requests may be in flight at once; a background task reads
+/// from the socket and routes each response to its request by sequence
+/// number. This means the socket must be created from within a runtime.
+///
+/// The kernel only runs one dump per socket at a time, so dump requests are
+/// queued behind each other. Other requests are not affected.
+#[derive(Clone)]
+pub struct AsyncNetlinkSocket {
+    inner: Arc<Inner>,
+    reader: Arc<Reader>,
+}
+
+struct Inner {
+    sock: AsyncFd<NetlinkSocket>,
+    pid: u32,
+    next_seq: AtomicU32,
+
+    /// Requests that are waiting for responses, by sequence number.
+    pending: Mutex<HashMap<u32, Waiter>>,
+
+    /// Where to send unsolicited messages, if anyone is listening.
+    events: Mutex<Option<mpsc::UnboundedSender<Result<NetlinkMessage>>>>,
+
+    /// Held for the duration of a dump.
+    dump_lock: Arc<AsyncMutex<()>>,
+}
+
+/// Whoever is waiting for the responses with a given sequence number.
+enum Waiter {
+    /// A request whose Responses is still alive.
+    Active(mpsc::UnboundedSender<Result<NetlinkMessage>>),
+
+    /// A dump whose Responses was dropped before the kernel finished
+    /// sending it. Holds the dump lock until the rest has been read, since
+    /// the kernel won't start another dump on this socket before then.
+    Draining(OwnedMutexGuard<()>),
+}
+
+/// Stops the background reader once the last handle is dropped.
+struct Reader(JoinHandle<()>);
+
+impl Drop for Reader {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl AsyncNetlinkSocket {
+    pub fn new(proto: i32) -> Result<AsyncNetlinkSocket> {
+        AsyncNetlinkSocket::with_socket(NetlinkSocket::new(proto)?)
+    }
+
+    /// Opens a socket in another network namespace, see
+    /// `NetlinkSocket::new_in_netns`.
+    pub fn new_in_netns(proto: i32, ns: &NetNs) -> Result<AsyncNetlinkSocket> {
+        AsyncNetlinkSocket::with_socket(NetlinkSocket::new_in_netns(proto, ns)?)
+    }
+
+    fn with_socket(sock: NetlinkSocket) -> Result<AsyncNetlinkSocket> {
+        sock.set_nonblocking(true)?;
+        let pid = sock.pid()?;
+
+        let inner = Arc::new(Inner {
+            sock: AsyncFd::new(sock)?,
+            pid,
+            // kernel notifications have sequence number 0
+            next_seq: AtomicU32::new(1),
+            pending: Mutex::new(HashMap::new()),
+            events: Mutex::new(None),
+            dump_lock: Arc::new(AsyncMutex::new(())),
+        });
+        let reader = tokio::spawn(read_loop(inner.clone()));
+
+        Ok(AsyncNetlinkSocket {
+            inner,
+            reader: Arc::new(Reader(reader)),
+        })
+    }
+
+    /// Joins a multicast group, e.g. uapi::RTNLGRP_LINK. Notifications sent
+    /// to the group are then available via `events()`.
+    pub fn add_membership(&self, group: u32) -> Result<()> {
+        self.inner.sock.get_ref().setsockopt_u32(
+            libc::SOL_NETLINK,
+            uapi::NETLINK_ADD_MEMBERSHIP as i32,
+            group,
+        )
+    }
+
+    /// Leaves a multicast group previously joined with `add_membership`.
+    pub fn drop_membership(&self, group: u32) -> Result<()> {
+        self.inner.sock.get_ref().setsockopt_u32(
+            libc::SOL_NETLINK,
+            uapi::NETLINK_DROP_MEMBERSHIP as i32,
+            group,
+        )
+    }
+
+    /// The underlying socket, e.g. for setting socket options. It must stay
+    /// in non-blocking mode.
+    pub fn socket(&self) -> &NetlinkSocket {
+        self.inner.sock.get_ref()
+    }
+
+    /// Returns a receiver for unsolicited messages, i.e. notifications for
+    /// the multicast groups this socket has joined. Only one receiver is
+    /// active at a time; calling this again disconnects the previous one.
+    /// Unsolicited messages received while nobody is listening are dropped.
+    /// If notifications were lost, the receiver gets NetlinkError::Overrun.
+    pub fn events(&self) -> Events {
+        let (tx, rx) = mpsc::unbounded_channel();
+        *self.inner.events.lock().unwrap() = Some(tx);
+        Events { rx }
+    }
+
+    /// Sends a request, returning a handle on which its responses arrive.
+    pub async fn request(&self, request: &mut NetlinkMessage) -> Result<Responses> {
+        let flags = request.header.nlm_flags();
+        let dump_guard = if flags.contains(NlmFlags::DUMP) {
+            Some(self.inner.dump_lock.clone().lock_owned().await)
+        } else {
+            None
+        };
+
+        let seq = self.inner.next_seq.fetch_add(1, Ordering::Relaxed);
+        request.header.seq = seq;
+
+        // Register before sending, so we can't miss the response. If sending
+        // fails, dropping the Responses unregisters it again.
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.inner
+            .pending
+            .lock()
+            .unwrap()
+            .insert(seq, Waiter::Active(tx));
+        let mut resps = Responses {
+            inner: self.inner.clone(),
+            seq,
+            rx,
+            request: request.to_bytes(),
+            ack: flags.contains(NlmFlags::ACK),
+            done: false,
+            finished: false,
+            interrupted: false,
+            dump_guard,
+        };
+
+        loop {
+            let mut guard = self.inner.sock.writable().await?;
+            match guard.try_io(|s| s.get_ref().send(&resps.request).map_err(io::Error::from)) {
+                Ok(Ok(_)) => return Ok(resps),
+                Ok(Err(e)) => {
+                    // Nothing was sent, so there is nothing to drain.
+                    resps.finished = true;
+                    return Err(e.into());
+                }
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    /// Sends a request and collects all of its responses, like
+    /// `NetlinkSocket::exec`.
+    pub async fn exec(
+        &self,
+        request: &mut NetlinkMessage,
+        resp_typ: Option<u16>,
+    ) -> Result<Vec<NetlinkMessage>> {
+        let mut resps = self.request(request).await?;
+        let mut out = vec![];
+        while let Some(resp) = resps.next().await {
+            let resp = resp?;
+            if resp_typ.is_none() || resp_typ == Some(resp.header.typ) {
+                out.push(resp);
+            }
+        }
+        complete(out, resps.interrupted())
+    }
+
+    /// Executes a dump request, like `NetlinkSocket::exec_dump`.
+    pub async fn exec_dump(
+        &self,
+        request: &mut NetlinkMessage,
+        resp_typ: Option<u16>,
+        mode: DumpMode,
+    ) -> Result<Vec<NetlinkMessage>> {
+        let mut retries = match mode {
+            DumpMode::Retry(n) => n,
+            DumpMode::Partial => 0,
+        };
+        loop {
+            match self.exec(request, resp_typ).await {
+                Err(NetlinkError::DumpInterrupted(_)) if retries > 0 => retries -= 1,
+                res => return res,
+            }
+        }
+    }
+}
+
+/// The background task that reads every datagram from the socket and
+/// hands the messages out.
+async fn read_loop(inner: Arc<Inner>) {
+    let mut buf = vec![];
+    loop {
+        let mut guard = match inner.sock.readable().await {
+            Ok(guard) => guard,
+            Err(_) => break,
+        };
+        let res = guard.try_io(|s| match s.get_ref().recv_into(&mut buf) {
+            Err(NetlinkError::Io(e)) if e.kind() == io::ErrorKind::WouldBlock => Err(e),
+            res => Ok(res),
+        });
+        let msgs = match res {
+            Err(_) | Ok(Err(_)) => continue, // would block
+            Ok(Ok(Ok(msgs))) => msgs,
+            // Lost some notifications; tell whoever reads events.
+            Ok(Ok(Err(NetlinkError::Overrun))) => {
+                if let Some(tx) = inner.events.lock().unwrap().as_ref() {
+                    let _ = tx.send(Err(NetlinkError::Overrun));
+                }
+                continue;
+            }
+            Ok(Ok(Err(NetlinkError::Io(_)))) => break,
+            // A datagram we couldn't parse; the socket is still usable.
+            Ok(Ok(Err(e))) => {
+                inner.fail(&buf, e);
+                continue;
+            }
+        };
+
+        for msg in msgs {
+            inner.dispatch(msg);
+        }
+    }
+
+    // Nobody will ever answer outstanding requests; wake them up.
+    inner.pending.lock().unwrap().clear();
+}
+
+impl Inner {
+    fn dispatch(&self, msg: NetlinkMessage) {
+        // Responses to our requests carry our port id. If nobody is waiting
+        // any more, the request was abandoned.
+        if msg.header.pid == self.pid {
+            let mut pending = self.pending.lock().unwrap();
+            match pending.get(&msg.header.seq) {
+                Some(Waiter::Active(tx)) => {
+                    let _ = tx.send(Ok(msg));
+                }
+                Some(Waiter::Draining(_)) if is_last(&msg.header) => {
+                    pending.remove(&msg.header.seq);
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(tx) = self.events.lock().unwrap().as_ref() {
+            let _ = tx.send(Ok(msg));
+        }
+    }
+
+    /// Reports a datagram that couldn't be parsed. The header at the start
+    /// of it usually survives, and says which request it belonged to;
+    /// otherwise it was presumably a notification.
+    fn fail(&self, datagram: &[u8], err: NetlinkError) {
+        if let Ok(header) = NetlinkHeader::from_bytes(datagram) {
+            if header.pid == self.pid {
+                if let Some(Waiter::Active(tx)) = self.pending.lock().unwrap().get(&header.seq) {
+                    let _ = tx.send(Err(err));
+                }
+                return;
+            }
+        }
+
+        if let Some(tx) = self.events.lock().unwrap().as_ref() {
+            let _ = tx.send(Err(err));
+        }
+    }
+}
+
+/// Responses is the stream of responses to a single request, returned by
+/// `AsyncNetlinkSocket::request`.
+pub struct Responses {
+    inner: Arc<Inner>,
+    seq: u32,
+    rx: mpsc::UnboundedReceiver<Result<NetlinkMessage>>,
+    request: Vec<u8>,
+    ack: bool,
+    /// No more responses will be returned.
+    done: bool,
+    /// The kernel has sent everything it is going to send.
+    finished: bool,
+    interrupted: bool,
+    dump_guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Responses {
+    /// Waits for the next response. Returns None once the request is
+    /// complete, or after an error.
+    pub async fn next(&mut self) -> Option<Result<NetlinkMessage>> {
+        let resp = self.next_inner().await;
+        if self.finished {
+            // let the next dump start
+            self.dump_guard = None;
+        }
+        resp
+    }
+
+    async fn next_inner(&mut self) -> Option<Result<NetlinkMessage>> {
+        if self.done {
+            return None;
+        }
+
+        let resp = match self.rx.recv().await {
+            Some(Ok(resp)) => resp,
+            // The kernel may still be sending the rest of the dump.
+            Some(Err(e)) => {
+                self.done = true;
+                return Some(Err(e));
+            }
+            None => {
+                self.done = true;
+                self.finished = true;
+                return Some(Err(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "netlink socket closed",
+                )
+                .into()));
+            }
+        };
+
+        self.interrupted |= is_interrupted(&resp.header);
+        match check_response(resp, &self.request, self.ack) {
+            Ok(Response::Done) => {
+                self.done = true;
+                self.finished = true;
+                None
+            }
+            Ok(Response::Data(resp, last)) => {
+                self.done = last;
+                self.finished = last;
+                Some(Ok(resp))
+            }
+            Err(e) => {
+                self.done = true;
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+
+    /// The sequence number assigned to the request.
+    pub fn seq(&self) -> u32 {
+        self.seq
+    }
+
+    /// Whether the kernel flagged the dump as interrupted (NLM_F_DUMP_INTR),
+    /// meaning the responses may be inconsistent. Only final once `next()`
+    /// has returned None.
+    pub fn interrupted(&self) -> bool {
+        self.interrupted
+    }
+}
+
+impl Drop for Responses {
+    fn drop(&mut self) {
+        let mut pending = self.inner.pending.lock().unwrap();
+
+        // The reader may already have passed on the end of the dump. It
+        // can't pass on anything more while we hold the lock.
+        while let Ok(resp) = self.rx.try_recv() {
+            if let Ok(resp) = resp {
+                self.finished |= is_last(&resp.header);
+            }
+        }
+
+        match self.dump_guard.take() {
+            // Abandoned half way through a dump: the kernel carries on
+            // sending it, so the next dump has to wait until it's done.
+            Some(guard) if !self.finished => {
+                pending.insert(self.seq, Waiter::Draining(guard));
+            }
+            _ => {
+                pending.remove(&self.seq);
+            }
+        }
+    }
+}
+
+/// Events receives the unsolicited messages for an AsyncNetlinkSocket. It is
+/// returned by `AsyncNetlinkSocket::events()`.
+pub struct Events {
+    rx: mpsc::UnboundedReceiver<Result<NetlinkMessage>>,
+}
+
+impl Events {
+    /// Waits for the next notification. Returns None if the socket has been
+    /// closed, or another receiver has replaced this one.
+    pub async fn next(&mut self) -> Option<Result<NetlinkMessage>> {
+        self.rx.recv().await
+    }
+}
+
+#[cfg(all(test, feature = "async"))]
+mod tests {
+    use super::AsyncNetlinkSocket;
+    use crate::hl::iface::test_link;
+    use crate::proto::conn::FakeKernel;
+    use crate::proto::{NetlinkMessage, NlmFlags};
+    use crate::type_route::{IfInfoMsg, RtmType};
+    use crate::{uapi, NetlinkError, Serializable};
+    use std::time::Duration;
+
+    fn sockets() -> (AsyncNetlinkSocket, FakeKernel) {
+        let sock = AsyncNetlinkSocket::new(uapi::NETLINK_ROUTE as i32).unwrap();
+        let kernel = FakeKernel::new(sock.socket().pid().unwrap());
+        (sock, kernel)
+    }
+
+    fn index(msg: &NetlinkMessage) -> i32 {
+        IfInfoMsg::from_bytes(&msg.data).unwrap().index
+    }
+
+    #[tokio::test]
+    async fn test_concurrent() {
+        let (sock, kernel) = sockets();
+        let mut a = sock
+            .request(&mut FakeKernel::request(NlmFlags::empty()))
+            .await
+            .unwrap();
+        let mut b = sock
+            .request(&mut FakeKernel::request(NlmFlags::empty()))
+            .await
+            .unwrap();
+
+        kernel.send(b.seq(), vec![test_link(2, "b", NlmFlags::empty())]);
+        kernel.send(a.seq(), vec![test_link(1, "a", NlmFlags::empty())]);
+
+        assert_eq!(index(&a.next().await.unwrap().unwrap()), 1);
+        assert_eq!(index(&b.next().await.unwrap().unwrap()), 2);
+        assert!(a.next().await.is_none());
+        assert!(b.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_dump() {
+        let (sock, kernel) = sockets();
+        let mut first = sock
+            .request(&mut FakeKernel::request(NlmFlags::DUMP))
+            .await
+            .unwrap();
+        let seq = first.seq();
+        kernel.send(seq, vec![test_link(1, "lo", NlmFlags::MULTI)]);
+        assert_eq!(index(&first.next().await.unwrap().unwrap()), 1);
+        drop(first);
+
+        // A real dump, which has to wait for the kernel to finish this one.
+        let s = sock.clone();
+        let second = tokio::spawn(async move {
+            let mut req = NetlinkMessage::new(RtmType::GETLINK, NlmFlags::DUMP | NlmFlags::REQUEST);
+            req.add_data(IfInfoMsg::default().to_bytes());
+            s.exec(&mut req, Some(RtmType::NEWLINK.into())).await
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!second.is_finished());
+
+        let rest = vec![test_link(2, "eth0", NlmFlags::MULTI), FakeKernel::done()];
+        kernel.send(seq, rest);
+        assert!(!second.await.unwrap().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_error_routing() {
+        let (sock, kernel) = sockets();
+        let mut a = sock
+            .request(&mut FakeKernel::request(NlmFlags::empty()))
+            .await
+            .unwrap();
+        let mut b = sock
+            .request(&mut FakeKernel::request(NlmFlags::empty()))
+            .await
+            .unwrap();
+
+        // b's response claims to be longer than it is
+        let mut bad = kernel.datagram(b.seq(), vec![test_link(2, "b", NlmFlags::empty())]);
+        bad[0] = 64;
+        kernel.send_raw(&bad);
+        kernel.send(a.seq(), vec![test_link(1, "a", NlmFlags::empty())]);
+
+        assert_eq!(index(&a.next().await.unwrap().unwrap()), 1);
+        match tokio::time::timeout(Duration::from_secs(1), b.next()).await {
+            Ok(Some(Err(NetlinkError::Truncated { .. }))) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(b.next().await.is_none());
+    }
+}
@@ -0,0 +1,158 @@
+use super::conn::{check_response, NetlinkSocket, Response};
+use super::{NetlinkMessage, NlmFlags};
+use crate::{Result, Serializable};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+
+/// Roughly how much receive buffer a single ack takes up, including the
+/// kernel's overhead. Used to decide how many requests can be in flight
+/// before the acks overflow the receive buffer and are dropped.
+const ACK_TRUESIZE: usize = 1024;
+
+/// Batch is a list of requests to be sent together, with as few syscalls as
+/// possible. See `NetlinkSocket::exec_batch`.
+///
+/// Batches are meant for requests that change things, such as adding
+/// routes. Dump requests don't belong in a batch, since the kernel only
+/// runs one dump per socket at a time.
+#[derive(Debug, Default)]
+pub struct Batch {
+    msgs: Vec<NetlinkMessage>,
+}
+
+impl Batch {
+    pub fn new() -> Batch {
+        Default::default()
+    }
+
+    pub fn add(&mut self, msg: NetlinkMessage) {
+        self.msgs.push(msg);
+    }
+
+    pub fn len(&self) -> usize {
+        self.msgs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.msgs.is_empty()
+    }
+
+    /// The requests in the batch. Once the batch has been executed, they
+    /// have their sequence numbers assigned.
+    pub fn messages(&self) -> &[NetlinkMessage] {
+        &self.msgs
+    }
+
+    /// Splits the batch into chunks of at most `max_bytes` bytes and
+    /// `max_msgs` messages. A single message larger than `max_bytes` gets
+    /// a chunk of its own.
+    fn split(&self, max_bytes: usize, max_msgs: usize) -> Vec<Range<usize>> {
+        let mut out = vec![];
+        let mut start = 0;
+        let mut bytes = 0;
+        for (i, msg) in self.msgs.iter().enumerate() {
+            let len = msg.header.len as usize;
+            if i > start && (bytes + len > max_bytes || i - start >= max_msgs) {
+                out.push(start..i);
+                start = i;
+                bytes = 0;
+            }
+            bytes += len;
+        }
+        if start < self.msgs.len() {
+            out.push(start..self.msgs.len());
+        }
+        out
+    }
+}
+
+impl NetlinkSocket {
+    /// Sends every request in the batch and waits for all of their
+    /// responses. NLM_F_ACK is set on each request, so that each gets one.
+    ///
+    /// The result has an entry for every request, keyed by its sequence
+    /// number: either the messages the kernel returned (if any), or the
+    /// error for that request. An error for one request doesn't affect the
+    /// others. Only a failure of the socket itself fails the whole batch.
+    pub fn exec_batch(
+        &mut self,
+        batch: &mut Batch,
+    ) -> Result<BTreeMap<u32, Result<Vec<NetlinkMessage>>>> {
+        let pid = self.pid()?;
+
+        // Each chunk must fit in a single datagram, and all of its acks
+        // must fit in the receive buffer.
+        let max_bytes = self.send_buffer_size()? / 2;
+        let max_msgs = std::cmp::max(1, self.recv_buffer_size()? / ACK_TRUESIZE);
+
+        for msg in batch.msgs.iter_mut() {
+            msg.header.flags |= NlmFlags::ACK.bits();
+            msg.header.seq = self.next_seq();
+        }
+
+        let mut out = BTreeMap::new();
+        for range in batch.split(max_bytes, max_msgs) {
+            // The serialized requests, by sequence number, along with any
+            // responses received so far.
+            let mut pending: HashMap<u32, (Vec<u8>, Vec<NetlinkMessage>)> = HashMap::new();
+            let mut buf = vec![];
+            for msg in batch.msgs[range].iter() {
+                let b = msg.to_bytes();
+                buf.extend(b.iter());
+                pending.insert(msg.header.seq, (b, vec![]));
+            }
+            self.send(&buf)?;
+
+            while !pending.is_empty() {
+                for resp in self.recv_responses()? {
+                    let seq = resp.header.seq;
+                    if resp.header.pid != pid || !pending.contains_key(&seq) {
+                        self.queue_event(resp);
+                        continue;
+                    }
+
+                    let (req, resps) = pending.get_mut(&seq).unwrap();
+                    match check_response(resp, req, true) {
+                        Ok(Response::Data(resp, _)) => resps.push(resp),
+                        Ok(Response::Done) => {
+                            let (_, resps) = pending.remove(&seq).unwrap();
+                            out.insert(seq, Ok(resps));
+                        }
+                        Err(e) => {
+                            pending.remove(&seq);
+                            out.insert(seq, Err(e));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Batch;
+    use crate::proto::NetlinkMessage;
+
+    #[test]
+    fn test_split() {
+        let mut b = Batch::new();
+        for i in 0..5 {
+            let mut m = NetlinkMessage::new(16u16, 0u16);
+            m.add_data(vec![0; 4 * i]);
+            b.add(m);
+        }
+        // lengths are 16, 20, 24, 28, 32
+
+        assert_eq!(b.split(1000, 1000), vec![0..5]);
+        assert_eq!(b.split(1000, 2), vec![0..2, 2..4, 4..5]);
+        assert_eq!(b.split(50, 1000), vec![0..2, 2..3, 3..4, 4..5]);
+
+        // oversized messages still get sent
+        assert_eq!(b.split(10, 1000), vec![0..1, 1..2, 2..3, 3..4, 4..5]);
+
+        assert_eq!(Batch::new().split(10, 10), vec![]);
+    }
+}
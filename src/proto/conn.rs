@@ -1,25 +1,73 @@
-use crate::Serializable;
-use byteorder::{NativeEndian, ReadBytesExt};
+use super::pcap::PcapWriter;
+use super::transport::{Responses, Transport};
+use super::{NlmFlags, NlmsgType};
+use crate::uapi;
+use crate::{NetlinkError, Result, Serializable};
 use libc;
-use std::io::{Cursor, Error, ErrorKind, Result};
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind, Write};
 use std::mem;
-use std::os::unix::io::RawFd;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::Mutex;
+
+/// How many times `DumpMode::default()` retries an interrupted dump.
+pub const DEFAULT_DUMP_RETRIES: usize = 5;
+
+/// What to do when the kernel flags a dump as interrupted (NLM_F_DUMP_INTR),
+/// meaning the table changed while it was being dumped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpMode {
+    /// Dump again, up to this many more times. If the dump is still
+    /// interrupted, fail with NetlinkError::DumpInterrupted.
+    Retry(usize),
+
+    /// Fail with NetlinkError::DumpInterrupted, which holds the possibly
+    /// inconsistent result.
+    Partial,
+}
 
-const RECEIVE_BUFFER_SIZE: usize = 65536;
+impl Default for DumpMode {
+    fn default() -> Self {
+        DumpMode::Retry(DEFAULT_DUMP_RETRIES)
+    }
+}
 
 #[derive(Debug)]
 pub struct NetlinkSocket {
     proto: i32,
     next_seq: u32,
     fd: RawFd,
+
+    /// The multicast groups this socket has joined.
+    groups: Vec<u32>,
+
+    /// Unsolicited (multicast) messages that arrived while we were waiting
+    /// for a response, to be handed out by `events()`.
+    events: VecDeque<super::NetlinkMessage>,
+
+    /// Set if notifications were lost while we were waiting for a response.
+    overrun: bool,
+
+    /// The receive buffer, reused for every datagram. It grows to fit the
+    /// largest one seen so far.
+    buf: Vec<u8>,
+
+    /// If set, every datagram sent or received is copied here.
+    recorder: Mutex<Option<PcapWriter<Box<dyn Write + Send>>>>,
 }
 
 impl NetlinkSocket {
     pub fn new(proto: i32) -> Result<NetlinkSocket> {
         let mut s = NetlinkSocket {
-            next_seq: 0,
+            // kernel notifications have sequence number 0
+            next_seq: 1,
             proto: proto,
             fd: 0,
+            groups: vec![],
+            events: VecDeque::new(),
+            overrun: false,
+            buf: vec![],
+            recorder: Mutex::new(None),
         };
 
         return s.bind().and(Ok(s));
@@ -34,9 +82,9 @@ impl NetlinkSocket {
 
     /// The so-called port id, assigned by the kernel when the socket is opened.
     /// Has nothing to do with the process id.
-    fn pid(&self) -> Result<u32> {
+    pub(crate) fn pid(&self) -> Result<u32> {
         if self.fd <= 0 {
-            return Err(Error::new(ErrorKind::NotConnected, "not connected"));
+            return Err(Error::new(ErrorKind::NotConnected, "not connected").into());
         }
 
         let mut saddr = self.sockaddr();
@@ -47,7 +95,7 @@ impl NetlinkSocket {
 
         // res err
         if res < 0 {
-            return Err(Error::last_os_error());
+            return Err(Error::last_os_error().into());
         }
 
         Ok(saddr.nl_pid)
@@ -63,7 +111,7 @@ impl NetlinkSocket {
         };
 
         if sock < 0 {
-            return Err(Error::last_os_error());
+            return Err(Error::last_os_error().into());
         }
         self.fd = sock;
 
@@ -77,18 +125,103 @@ impl NetlinkSocket {
             )
         };
         if res < 0 {
-            return Err(Error::last_os_error());
+            return Err(Error::last_os_error().into());
         }
+
+        // Ask the kernel to explain its errors. Older kernels don't support
+        // this, which is fine.
+        let _ = self.setsockopt_u32(libc::SOL_NETLINK, uapi::NETLINK_EXT_ACK as i32, 1);
         return Ok(());
     }
 
-    fn send(&mut self, buf: &mut [u8]) -> Result<()> {
+    /// Joins a multicast group, e.g. uapi::RTNLGRP_LINK. Notifications sent
+    /// to the group are then available via `events()`.
+    pub fn add_membership(&mut self, group: u32) -> Result<()> {
+        self.setsockopt_u32(
+            libc::SOL_NETLINK,
+            uapi::NETLINK_ADD_MEMBERSHIP as i32,
+            group,
+        )?;
+        if !self.groups.contains(&group) {
+            self.groups.push(group);
+        }
+        Ok(())
+    }
+
+    /// Leaves a multicast group previously joined with `add_membership`.
+    pub fn drop_membership(&mut self, group: u32) -> Result<()> {
+        self.setsockopt_u32(
+            libc::SOL_NETLINK,
+            uapi::NETLINK_DROP_MEMBERSHIP as i32,
+            group,
+        )?;
+        self.groups.retain(|g| *g != group);
+        Ok(())
+    }
+
+    /// The multicast groups currently joined.
+    pub fn groups(&self) -> &[u32] {
+        &self.groups
+    }
+
+    /// Returns a blocking iterator over unsolicited messages, i.e.
+    /// notifications for the multicast groups this socket has joined.
+    pub fn events(&mut self) -> Events<'_> {
+        Events { sock: self }
+    }
+
+    /// Records every datagram sent or received from now on to `w`, as a pcap
+    /// capture with link type LINKTYPE_NETLINK. Wireshark can open it, and
+    /// PcapReplay can play it back. Replaces any previous recording.
+    ///
+    /// Failures to write the capture are ignored, so that they don't affect
+    /// the socket; if that matters, have `w` keep track of them.
+    pub fn record<W: Write + Send + 'static>(&self, w: W) -> Result<()> {
+        let writer = PcapWriter::new(Box::new(w) as Box<dyn Write + Send>)?;
+        *self.recorder.lock().unwrap() = Some(writer);
+        Ok(())
+    }
+
+    /// Stops recording, flushing the capture.
+    pub fn stop_recording(&self) -> Result<()> {
+        match self.recorder.lock().unwrap().take() {
+            Some(mut writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn tee(&self, outgoing: bool, data: &[u8]) {
+        if let Some(writer) = self.recorder.lock().unwrap().as_mut() {
+            let _ = writer.write_datagram(self.proto as u16, outgoing, data);
+        }
+    }
+
+    /// Puts the socket in to (or out of) non-blocking mode. In non-blocking
+    /// mode, receiving returns ErrorKind::WouldBlock if there is nothing
+    /// to read.
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<()> {
+        let flags = unsafe { libc::fcntl(self.fd, libc::F_GETFL) };
+        if flags < 0 {
+            return Err(Error::last_os_error().into());
+        }
+        let flags = if nonblocking {
+            flags | libc::O_NONBLOCK
+        } else {
+            flags & !libc::O_NONBLOCK
+        };
+        if unsafe { libc::fcntl(self.fd, libc::F_SETFL, flags) } < 0 {
+            return Err(Error::last_os_error().into());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn send(&self, buf: &[u8]) -> Result<()> {
         let mut saddr = self.sockaddr();
         let len = buf.len();
         let res = unsafe {
             libc::sendto(
                 self.fd,
-                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.as_ptr() as *const libc::c_void,
                 len,
                 0, // flags
                 mem::transmute(&mut saddr),
@@ -97,128 +230,391 @@ impl NetlinkSocket {
         };
 
         if res < 0 {
-            return Err(Error::last_os_error());
+            return Err(Error::last_os_error().into());
         }
+        self.tee(true, buf);
         Ok(())
     }
 
     fn recv(&mut self) -> Result<Vec<super::NetlinkMessage>> {
+        let mut buf = mem::take(&mut self.buf);
+        let res = self.recv_into(&mut buf);
+        self.buf = buf;
+        res
+    }
+
+    /// Receives a single datagram, using (and growing if necessary) `buf`.
+    pub(crate) fn recv_into(&self, buf: &mut Vec<u8>) -> Result<Vec<super::NetlinkMessage>> {
+        let len = self.recv_datagram(buf)?;
+        super::NetlinkMessage::from_bytes(&buf[..len])
+    }
+
+    /// Receives a single datagram into `buf`, returning its length.
+    fn recv_datagram(&self, buf: &mut Vec<u8>) -> Result<usize> {
         if self.fd <= 0 {
-            return Err(Error::new(ErrorKind::NotConnected, "not connected"));
+            return Err(Error::new(ErrorKind::NotConnected, "not connected").into());
         }
 
-        let mut buf: Vec<u8> = Vec::new();
-        // TODO: peek instead of giant buffer
-        buf.resize(RECEIVE_BUFFER_SIZE, 0);
+        // With MSG_TRUNC, the kernel returns the real size of the datagram,
+        // even if it doesn't fit in the buffer. Peek first to size it.
+        let size = self.recv_raw(buf, libc::MSG_PEEK | libc::MSG_TRUNC)?;
+        if buf.len() < size {
+            buf.resize(size, 0);
+        }
 
+        let res = self.recv_raw(buf, libc::MSG_TRUNC)?;
+        if res > buf.len() {
+            return Err(NetlinkError::DatagramTruncated {
+                size: res,
+                received: buf.len(),
+            });
+        }
+        self.tee(false, &buf[..res]);
+
+        if res < super::NetlinkHeader::size() {
+            return Err(NetlinkError::Truncated {
+                what: "netlink message",
+                offset: 0,
+                attr: None,
+            });
+        }
+
+        Ok(res)
+    }
+
+    fn recv_raw(&self, buf: &mut [u8], flags: i32) -> Result<usize> {
         let len = buf.len();
-        let res = unsafe {
-            libc::recv(
-                self.fd,
-                buf.as_mut_slice().as_mut_ptr() as *mut libc::c_void,
-                len,
-                0, //flags
-            )
-        };
+        let res = unsafe { libc::recv(self.fd, buf.as_mut_ptr() as *mut libc::c_void, len, flags) };
         if res < 0 {
-            return Err(Error::last_os_error());
+            let err = Error::last_os_error();
+            if err.raw_os_error() == Some(libc::ENOBUFS) {
+                return Err(NetlinkError::Overrun);
+            }
+            return Err(err.into());
         }
-        let res: usize = res as usize;
+        Ok(res as usize)
+    }
 
-        // the slice is (probably) too big; resize to the returned length.
-        if res < super::NetlinkHeader::size() {
-            return Err(Error::new(
-                ErrorKind::UnexpectedEof,
-                "netlink message too short",
-            ));
+    /// Allocates a sequence number for a request. 0 is skipped, so that
+    /// responses can't be mistaken for notifications.
+    pub(crate) fn next_seq(&mut self) -> u32 {
+        let seq = self.next_seq;
+        self.next_seq = match self.next_seq.wrapping_add(1) {
+            0 => 1,
+            next => next,
+        };
+        seq
+    }
+
+    /// Receives the next datagram while waiting for responses. If
+    /// notifications are lost, that is recorded for `events()` rather than
+    /// failing the request.
+    pub(crate) fn recv_responses(&mut self) -> Result<Vec<super::NetlinkMessage>> {
+        loop {
+            match self.recv() {
+                Err(NetlinkError::Overrun) if !self.groups.is_empty() => self.overrun = true,
+                res => return res,
+            }
         }
-        buf.truncate(res);
+    }
+
+    /// Reads the responses to the serialized `request`, handing data
+    /// messages to `f` without copying them out of `buf`. See
+    /// `Transport::request_with`.
+    fn responses_with(
+        &mut self,
+        buf: &mut Vec<u8>,
+        request: &[u8],
+        seq: u32,
+        ack: bool,
+        f: &mut dyn FnMut(super::NetlinkMessageRef<'_>) -> Result<()>,
+    ) -> Result<()> {
+        let pid = self.pid()?;
+        let mut res = Ok(());
+        let mut interrupted = false;
+        let mut done = false;
+        while !done {
+            let len = match self.recv_datagram(buf) {
+                Err(NetlinkError::Overrun) if !self.groups.is_empty() => {
+                    self.overrun = true;
+                    continue;
+                }
+                len => len?,
+            };
+
+            for resp in super::NetlinkMessageRef::iter(&buf[..len]) {
+                let resp = resp?;
+
+                // Anything after the end of our responses is someone else's,
+                // as is anything with the wrong seq no or port id.
+                let wrong_seq = resp.header.seq != seq;
+                let wrong_pid = resp.header.pid != pid;
+                if done || wrong_seq || wrong_pid {
+                    if self.unsolicited(resp.to_message()) || done {
+                        continue;
+                    }
+                    if wrong_seq {
+                        return Err(NetlinkError::Protocol("Incorrect seq number"));
+                    }
+                    return Err(NetlinkError::Protocol("Got incorrect responding port ID."));
+                }
+
+                interrupted |= is_interrupted(&resp.header);
+                let typ = resp.header.typ;
+                if typ == NlmsgType::ERROR || typ == NlmsgType::DONE {
+                    // These are rare enough that the copy doesn't matter.
+                    check_response(resp.to_message(), request, ack)?;
+                    done = true;
+                    continue;
+                }
 
-        let msgs = super::NetlinkMessage::from_bytes(&buf)?;
+                done = !resp.header.nlm_flags().contains(NlmFlags::MULTI) && !ack;
+                if res.is_ok() {
+                    res = f(resp);
+                }
+            }
+        }
+
+        res?;
+        complete(vec![], interrupted).map(|_| ())
+    }
 
-        return Ok(msgs);
+    /// Keeps a message that isn't a response for `events()`, if we've joined
+    /// any groups. Otherwise it is dropped.
+    pub(crate) fn queue_event(&mut self, msg: super::NetlinkMessage) {
+        if !self.groups.is_empty() {
+            self.events.push_back(msg);
+        }
+    }
+
+    /// Sends a request, returning a blocking iterator over its responses.
+    /// See `Transport::request`.
+    pub fn request(
+        &mut self,
+        request: &mut super::NetlinkMessage,
+    ) -> Result<Responses<'_, NetlinkSocket>> {
+        Transport::request(self, request)
     }
 
+    /// Sends a request and collects all of its responses. See
+    /// `Transport::exec`.
     pub fn exec(
         &mut self,
         request: &mut super::NetlinkMessage,
         resp_typ: Option<u16>,
     ) -> Result<Vec<super::NetlinkMessage>> {
-        // TODO: make this atomic and don't take a mut self.
-        request.header.seq = self.next_seq;
-        self.next_seq += 1;
+        Transport::exec(self, request, resp_typ)
+    }
 
-        // send the message
-        let mut b = request.to_bytes();
-        self.send(&mut b)?;
+    /// Executes a dump request. See `Transport::exec_dump`.
+    pub fn exec_dump(
+        &mut self,
+        request: &mut super::NetlinkMessage,
+        resp_typ: Option<u16>,
+        mode: DumpMode,
+    ) -> Result<Vec<super::NetlinkMessage>> {
+        Transport::exec_dump(self, request, resp_typ, mode)
+    }
+}
 
-        // Loop received messages
-        let pid = self.pid()?;
+impl Transport for NetlinkSocket {
+    fn send(&mut self, buf: &[u8]) -> Result<()> {
+        NetlinkSocket::send(self, buf)
+    }
 
-        let mut out: Vec<super::NetlinkMessage> = vec![];
-        loop {
-            let mut resps = self.recv()?;
-            for resp in resps.drain(0..) {
-                // Validate response:
-
-                // seq no matches
-                if resp.header.seq != request.header.seq {
-                    // We don't currently support shared sockets
-                    return Err(Error::new(ErrorKind::InvalidData, "Incorrect seq number"));
-                }
+    fn recv(&mut self) -> Result<Vec<super::NetlinkMessage>> {
+        self.recv_responses()
+    }
 
-                // port id matches
-                if resp.header.pid != pid {
-                    return Err(Error::new(
-                        ErrorKind::Other,
-                        "Got incorrect responding port ID.",
-                    ));
-                }
+    fn pid(&self) -> Result<u32> {
+        NetlinkSocket::pid(self)
+    }
 
-                // Did the kernel return an error?
-                // The errno is just the next 4 bytes
-                if (resp.header.typ as i32) == libc::NLMSG_ERROR {
-                    if resp.data.len() < 4 {
-                        return Err(Error::new(
-                            ErrorKind::UnexpectedEof,
-                            "Error message too short",
-                        ));
-                    }
-                    // TODO: rust 1.32 has proper byte order stuff, without
-                    // needing a separate crate.
-                    let mut rdr = Cursor::new(resp.data);
-                    let errno: u32 = rdr.read_u32::<NativeEndian>().unwrap();
-                    if errno == 0 {
-                        return Ok(out);
-                    }
-                    return Err(Error::from_raw_os_error(-(errno as i32)));
-                }
+    /// Responses are parsed in place in the receive buffer.
+    fn request_with(
+        &mut self,
+        request: &mut super::NetlinkMessage,
+        f: &mut dyn FnMut(super::NetlinkMessageRef<'_>) -> Result<()>,
+    ) -> Result<()> {
+        request.header.seq = self.next_seq();
+        let b = request.to_bytes();
+        self.send(&b)?;
+        let ack = request.header.nlm_flags().contains(NlmFlags::ACK);
+
+        let mut buf = mem::take(&mut self.buf);
+        let res = self.responses_with(&mut buf, &b, request.header.seq, ack, f);
+        self.buf = buf;
+        res
+    }
 
-                // have we reached the end?
-                if (resp.header.typ as i32) == libc::NLMSG_DONE {
-                    return Ok(out);
-                }
+    fn next_seq(&mut self) -> u32 {
+        NetlinkSocket::next_seq(self)
+    }
 
-                // If we know which type of message we want, skip those
-                // that don't match
-                match resp_typ {
-                    Some(typ) => {
-                        if resp.header.typ != typ {
-                            continue;
-                        }
-                    }
-                    None => {}
-                }
+    /// Notifications for the groups we've joined are kept for `events()`.
+    fn unsolicited(&mut self, msg: super::NetlinkMessage) -> bool {
+        if self.groups.is_empty() {
+            return false;
+        }
+        self.events.push_back(msg);
+        true
+    }
+}
+
+impl AsRawFd for NetlinkSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
 
-                // If we've gotten this far, the message is meant for us.
-                // Add it to the result
-                let respflags = resp.header.flags;
-                out.push(resp);
+/// What a single response message means for the request it answers.
+pub(crate) enum Response {
+    /// A message carrying data. The flag is set if no more are expected.
+    Data(super::NetlinkMessage, bool),
 
-                // If this isn't a mutipart message, we're done.
-                if (respflags as i32) & libc::NLM_F_MULTI == 0 {
-                    return Ok(out);
-                }
+    /// The request is complete.
+    Done,
+}
+
+/// Interprets a message received in response to the serialized `request`.
+/// Kernel errors are returned as NetlinkError::Kernel. If `ack` is set, the
+/// request asked for an acknowledgement, so a response isn't complete until
+/// that arrives.
+pub(crate) fn check_response(
+    resp: super::NetlinkMessage,
+    request: &[u8],
+    ack: bool,
+) -> Result<Response> {
+    // Did the kernel return an error?
+    if resp.header.typ == NlmsgType::ERROR {
+        let mut err = super::ErrorMessage::from_message(&resp)?;
+        if err.is_ack() {
+            return Ok(Response::Done);
+        }
+        err.resolve_attr(request);
+        return Err(NetlinkError::Kernel(err));
+    }
+
+    // have we reached the end?
+    if resp.header.typ == NlmsgType::DONE {
+        return Ok(Response::Done);
+    }
+
+    // If this isn't a mutipart message, we're done, unless we still need
+    // to wait for the ack.
+    let last = !resp.header.nlm_flags().contains(NlmFlags::MULTI) && !ack;
+    Ok(Response::Data(resp, last))
+}
+
+/// Whether the kernel flagged a dump response as inconsistent.
+pub(crate) fn is_interrupted(resp: &super::NetlinkHeader) -> bool {
+    resp.nlm_flags().contains(NlmFlags::DUMP_INTR)
+}
+
+/// Stands in for the kernel in tests: a second socket that sends
+/// responses straight to the socket under test. The real kernel ignores
+/// requests made with `FakeKernel::request`, so these are the only answers.
+#[cfg(test)]
+pub(crate) struct FakeKernel {
+    sock: NetlinkSocket,
+
+    /// The port id of the socket under test.
+    pid: u32,
+}
+
+#[cfg(test)]
+impl FakeKernel {
+    pub(crate) fn new(pid: u32) -> FakeKernel {
+        FakeKernel {
+            sock: NetlinkSocket::new(uapi::NETLINK_ROUTE as i32).unwrap(),
+            pid,
+        }
+    }
+
+    /// A request the kernel drops: NLMSG_NOOP, without NLM_F_ACK.
+    pub(crate) fn request(flags: NlmFlags) -> super::NetlinkMessage {
+        super::NetlinkMessage::new(NlmsgType::NOOP, NlmFlags::REQUEST | flags)
+    }
+
+    /// The NLMSG_DONE that ends a dump.
+    pub(crate) fn done() -> super::NetlinkMessage {
+        let mut msg = super::NetlinkMessage::new(NlmsgType::DONE, NlmFlags::MULTI);
+        msg.add_data(vec![0; 4]);
+        msg
+    }
+
+    /// Builds a datagram of responses to request `seq`.
+    pub(crate) fn datagram(&self, seq: u32, msgs: Vec<super::NetlinkMessage>) -> Vec<u8> {
+        let mut buf = vec![];
+        for mut msg in msgs {
+            msg.header.seq = seq;
+            msg.header.pid = self.pid;
+            buf.extend(msg.to_bytes());
+        }
+        buf
+    }
+
+    pub(crate) fn send(&self, seq: u32, msgs: Vec<super::NetlinkMessage>) {
+        self.send_raw(&self.datagram(seq, msgs));
+    }
+
+    pub(crate) fn send_raw(&self, buf: &[u8]) {
+        let mut saddr = self.sock.sockaddr();
+        saddr.nl_pid = self.pid;
+        let res = unsafe {
+            libc::sendto(
+                self.sock.fd,
+                buf.as_ptr() as *const libc::c_void,
+                buf.len(),
+                0, // flags
+                &saddr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as u32,
+            )
+        };
+        assert!(res >= 0, "{}", Error::last_os_error());
+    }
+}
+
+/// Whether the kernel has nothing more to send after this message.
+pub(crate) fn is_last(header: &super::NetlinkHeader) -> bool {
+    header.typ == NlmsgType::DONE
+        || header.typ == NlmsgType::ERROR
+        || !header.nlm_flags().contains(NlmFlags::MULTI)
+}
+
+/// The result of a request whose responses are `out`.
+pub(crate) fn complete(
+    out: Vec<super::NetlinkMessage>,
+    interrupted: bool,
+) -> Result<Vec<super::NetlinkMessage>> {
+    if interrupted {
+        return Err(NetlinkError::DumpInterrupted(out));
+    }
+    Ok(out)
+}
+
+/// Events is a blocking iterator over multicast notifications received by a
+/// NetlinkSocket. It is returned by `NetlinkSocket::events()`.
+pub struct Events<'a> {
+    sock: &'a mut NetlinkSocket,
+}
+
+impl<'a> Iterator for Events<'a> {
+    type Item = Result<super::NetlinkMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.sock.overrun {
+                self.sock.overrun = false;
+                return Some(Err(NetlinkError::Overrun));
+            }
+            if let Some(msg) = self.sock.events.pop_front() {
+                return Some(Ok(msg));
+            }
+
+            match self.sock.recv() {
+                Ok(msgs) => self.sock.events.extend(msgs),
+                Err(e) => return Some(Err(e)),
             }
         }
     }
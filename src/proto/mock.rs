@@ -0,0 +1,251 @@
+use super::transport::Transport;
+use super::{NetlinkHeader, NetlinkMessage, NlmFlags, NlmsgType};
+use crate::{Result, Serializable};
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind};
+
+/// MockTransport is an in-memory Transport for tests. It is scripted with the
+/// requests it should expect, in order, and what to respond to each:
+///
+/// ```ignore
+/// let mut mock = MockTransport::new();
+/// mock.expect(RtmType::GETLINK, vec![link]);
+/// let links = hl::iface::link_list(&mut mock)?;
+/// mock.assert_done();
+/// ```
+///
+/// Responses get the sequence number and port id of their request filled in.
+/// Like the kernel, the mock marks the responses to a dump NLM_F_MULTI and
+/// ends them with NLMSG_DONE, and acknowledges requests that set NLM_F_ACK.
+///
+/// Sending a request that doesn't match the next expectation panics.
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    next_seq: u32,
+    expected: VecDeque<Expectation>,
+    sent: Vec<NetlinkMessage>,
+    inbox: VecDeque<Vec<NetlinkMessage>>,
+}
+
+#[derive(Debug)]
+struct Expectation {
+    typ: u16,
+
+    /// If set, the request must match this exactly, apart from the
+    /// sequence number and port id.
+    request: Option<NetlinkMessage>,
+    reply: Reply,
+}
+
+#[derive(Debug)]
+enum Reply {
+    Messages(Vec<NetlinkMessage>),
+    Error(i32),
+}
+
+/// The port id of a MockTransport.
+const MOCK_PID: u32 = 4242;
+
+impl MockTransport {
+    pub fn new() -> MockTransport {
+        Default::default()
+    }
+
+    /// Expects a request of type `typ`, e.g. RTM_GETLINK, and answers it
+    /// with `responses`.
+    pub fn expect(&mut self, typ: impl Into<u16>, responses: Vec<NetlinkMessage>) -> &mut Self {
+        self.expected.push_back(Expectation {
+            typ: typ.into(),
+            request: None,
+            reply: Reply::Messages(responses),
+        });
+        self
+    }
+
+    /// Expects exactly `request`, and answers it with `responses`. The
+    /// sequence number and port id of the request aren't compared.
+    pub fn expect_request(
+        &mut self,
+        request: NetlinkMessage,
+        responses: Vec<NetlinkMessage>,
+    ) -> &mut Self {
+        self.expected.push_back(Expectation {
+            typ: request.header.typ,
+            request: Some(request),
+            reply: Reply::Messages(responses),
+        });
+        self
+    }
+
+    /// Expects a request of type `typ`, and fails it with the (positive)
+    /// `errno`, e.g. libc::EEXIST.
+    pub fn expect_error(&mut self, typ: impl Into<u16>, errno: i32) -> &mut Self {
+        self.expected.push_back(Expectation {
+            typ: typ.into(),
+            request: None,
+            reply: Reply::Error(errno),
+        });
+        self
+    }
+
+    /// Every request sent so far.
+    pub fn sent(&self) -> &[NetlinkMessage] {
+        &self.sent
+    }
+
+    /// Panics unless every expected request has been sent, and every
+    /// response received.
+    pub fn assert_done(&self) {
+        if let Some(e) = self.expected.front() {
+            panic!(
+                "mock: {} expected request(s) not sent, next is type {}",
+                self.expected.len(),
+                e.typ
+            );
+        }
+        if !self.inbox.is_empty() {
+            panic!("mock: {} response(s) not received", self.inbox.len());
+        }
+    }
+
+    fn answer(&mut self, request: NetlinkMessage) {
+        let exp = match self.expected.pop_front() {
+            Some(exp) => exp,
+            None => panic!("mock: unexpected request {:?}", request.header),
+        };
+        if exp.typ != request.header.typ {
+            panic!(
+                "mock: expected request of type {}, got {:?}",
+                exp.typ, request.header
+            );
+        }
+        if let Some(want) = exp.request {
+            if want.header.flags != request.header.flags || want.data != request.data {
+                panic!("mock: expected request {:?}, got {:?}", want, request);
+            }
+        }
+
+        let flags = request.header.nlm_flags();
+        let dump = flags.contains(NlmFlags::DUMP);
+        let mut out = vec![];
+        match exp.reply {
+            Reply::Messages(msgs) => {
+                for mut msg in msgs {
+                    if dump {
+                        msg.header.flags |= NlmFlags::MULTI.bits();
+                    }
+                    out.push(msg);
+                }
+                if dump {
+                    let mut done = NetlinkMessage::new(NlmsgType::DONE, NlmFlags::MULTI);
+                    done.add_data(vec![0; 4]);
+                    out.push(done);
+                } else if flags.contains(NlmFlags::ACK) {
+                    out.push(error_message(&request.header, 0));
+                }
+            }
+            Reply::Error(errno) => out.push(error_message(&request.header, errno)),
+        }
+
+        for msg in out.iter_mut() {
+            msg.header.seq = request.header.seq;
+            msg.header.pid = MOCK_PID;
+        }
+        if !out.is_empty() {
+            self.inbox.push_back(out);
+        }
+        self.sent.push(request);
+    }
+}
+
+/// An NLMSG_ERROR message answering the request with `header`. An errno of 0
+/// is an ack.
+fn error_message(header: &NetlinkHeader, errno: i32) -> NetlinkMessage {
+    let mut msg = NetlinkMessage::new(NlmsgType::ERROR, NlmFlags::empty());
+    let mut data = (-errno).to_ne_bytes().to_vec();
+    data.extend(header.to_bytes());
+    msg.add_data(data);
+    msg
+}
+
+impl Transport for MockTransport {
+    fn send(&mut self, buf: &[u8]) -> Result<()> {
+        for request in NetlinkMessage::from_bytes(buf)? {
+            self.answer(request);
+        }
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Result<Vec<NetlinkMessage>> {
+        match self.inbox.pop_front() {
+            Some(msgs) => Ok(msgs),
+            None => Err(Error::new(ErrorKind::WouldBlock, "mock: nothing to receive").into()),
+        }
+    }
+
+    fn pid(&self) -> Result<u32> {
+        Ok(MOCK_PID)
+    }
+
+    fn next_seq(&mut self) -> u32 {
+        self.next_seq += 1;
+        self.next_seq
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockTransport;
+    use crate::hl::iface::{self, test_link};
+    use crate::proto::{NlmFlags, Transport};
+    use crate::type_route::RtmType;
+
+    #[test]
+    fn test_link_list() {
+        let mut mock = MockTransport::new();
+        mock.expect(
+            RtmType::GETLINK,
+            vec![
+                test_link(1, "lo", NlmFlags::empty()),
+                test_link(2, "eth0", NlmFlags::empty()),
+            ],
+        );
+
+        let links = iface::link_list(&mut mock).unwrap();
+        mock.assert_done();
+        let names: Vec<_> = links
+            .iter()
+            .map(|l| l.name.clone().unwrap().into_string().unwrap())
+            .collect();
+        assert_eq!(names, vec!["lo", "eth0"]);
+
+        let sent = mock.sent();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].header.nlm_flags().contains(NlmFlags::DUMP));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut mock = MockTransport::new();
+        mock.expect_error(RtmType::GETLINK, libc::ENODEV)
+            .expect(RtmType::GETLINK, vec![]);
+
+        let err = iface::link_get_by_index(&mut mock, 7).unwrap_err();
+        assert_eq!(err.errno(), Some(libc::ENODEV));
+
+        // an ack and no link
+        assert!(iface::link_get_by_index(&mut mock, 7).is_err());
+        mock.assert_done();
+
+        // no responses left
+        assert!(mock.recv().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "expected request of type")]
+    fn test_unexpected() {
+        let mut mock = MockTransport::new();
+        mock.expect(RtmType::NEWLINK, vec![]);
+        let _ = iface::link_list(&mut mock);
+    }
+}
@@ -5,7 +5,25 @@
 /// proto implements the netlink protocol and socket
 ///
 ///
-pub use self::packet::{NetlinkHeader, NetlinkMessage};
+pub use self::ack::ErrorMessage;
+pub use self::batch::Batch;
+pub use self::mock::MockTransport;
+pub use self::netns::NetNs;
+pub use self::packet::{NetlinkHeader, NetlinkMessage, NetlinkMessageRef, NetlinkMessages};
+pub use self::pcap::{Datagram, PcapReader, PcapReplay, PcapWriter};
+pub use self::transport::Transport;
+pub use self::types::{NlmFlags, NlmsgType};
 
+mod ack;
+#[cfg(feature = "async")]
+pub mod async_conn;
+mod batch;
 pub mod conn;
+mod mock;
+mod netns;
 mod packet;
+mod pcap;
+pub mod shared;
+mod sockopt;
+pub mod transport;
+mod types;
@@ -0,0 +1,139 @@
+use super::conn::NetlinkSocket;
+use crate::Result;
+use std::ffi::CString;
+use std::io::{Error, ErrorKind};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::io::RawFd;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// NetNs identifies a network namespace.
+#[derive(Debug, Clone)]
+pub enum NetNs {
+    /// A namespace file, e.g. /var/run/netns/foo or /proc/1234/ns/net.
+    Path(PathBuf),
+
+    /// An open namespace file. It is not closed.
+    Fd(RawFd),
+
+    /// The namespace of a process.
+    Pid(libc::pid_t),
+}
+
+impl NetNs {
+    fn path(&self) -> Option<PathBuf> {
+        match self {
+            NetNs::Path(p) => Some(p.clone()),
+            NetNs::Fd(_) => None,
+            NetNs::Pid(pid) => Some(PathBuf::from(format!("/proc/{}/ns/net", pid))),
+        }
+    }
+}
+
+/// A file descriptor that is closed when dropped.
+struct Fd(RawFd);
+
+impl Fd {
+    fn open(path: &Path) -> Result<Fd> {
+        let path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "path contains NUL"))?;
+        let fd = unsafe { libc::open(path.as_ptr(), libc::O_RDONLY | libc::O_CLOEXEC) };
+        if fd < 0 {
+            return Err(Error::last_os_error().into());
+        }
+        Ok(Fd(fd))
+    }
+}
+
+impl Drop for Fd {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.0) };
+    }
+}
+
+fn setns(fd: RawFd) -> Result<()> {
+    if unsafe { libc::setns(fd, libc::CLONE_NEWNET) } < 0 {
+        return Err(Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+/// Runs `f` in the network namespace `ns`. Namespaces are per thread, so this
+/// happens on a helper thread; the calling thread is not affected.
+// io::Error::other needs Rust 1.74.
+#[allow(clippy::io_other_error)]
+fn in_netns<F, T>(ns: &NetNs, f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    let path = ns.path();
+    let fd = match ns {
+        NetNs::Fd(fd) => *fd,
+        _ => -1,
+    };
+
+    let helper = thread::spawn(move || {
+        let target = match path {
+            Some(path) => Some(Fd::open(&path)?),
+            None => None,
+        };
+        let orig = Fd::open(Path::new("/proc/thread-self/ns/net"))?;
+
+        setns(target.as_ref().map_or(fd, |t| t.0))?;
+        let res = f();
+        setns(orig.0)?;
+        res
+    });
+
+    helper
+        .join()
+        .unwrap_or_else(|_| Err(Error::new(ErrorKind::Other, "netns helper panicked").into()))
+}
+
+impl NetlinkSocket {
+    /// Opens a socket in another network namespace. The socket stays in that
+    /// namespace; everything done with it applies there.
+    ///
+    /// Entering a namespace requires CAP_SYS_ADMIN.
+    pub fn new_in_netns(proto: i32, ns: &NetNs) -> Result<NetlinkSocket> {
+        in_netns(ns, move || NetlinkSocket::new(proto))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NetNs;
+    use crate::proto::conn::NetlinkSocket;
+    use crate::uapi;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_netns_path() {
+        assert_eq!(
+            NetNs::Pid(1234).path(),
+            Some(PathBuf::from("/proc/1234/ns/net"))
+        );
+        assert_eq!(NetNs::Fd(3).path(), None);
+    }
+
+    #[test]
+    fn test_netns_errors() {
+        let proto = uapi::NETLINK_ROUTE as i32;
+
+        let ns = NetNs::Path(PathBuf::from("/var/run/netns/does-not-exist"));
+        let err = NetlinkSocket::new_in_netns(proto, &ns).err().unwrap();
+        assert_eq!(err.errno(), Some(libc::ENOENT));
+
+        // pid_max can't go this high, so no such process
+        let err = NetlinkSocket::new_in_netns(proto, &NetNs::Pid(i32::MAX))
+            .err()
+            .unwrap();
+        assert_eq!(err.errno(), Some(libc::ENOENT));
+
+        // not a namespace
+        let ns = NetNs::Path(PathBuf::from("/dev/null"));
+        let err = NetlinkSocket::new_in_netns(proto, &ns).err().unwrap();
+        assert_eq!(err.errno(), Some(libc::EINVAL));
+    }
+}
@@ -1,7 +1,6 @@
-use crate::Serializable;
-use std::io::{Error, ErrorKind, Result};
-use std::mem;
-use std::ptr;
+use super::NlmFlags;
+use crate::util::{u16_at, u32_at};
+use crate::{NetlinkError, Result, Serializable};
 
 /// The preamble packet sent with every netlink transaction
 #[repr(C)]
@@ -18,18 +17,29 @@ pub struct NetlinkHeader {
 impl NetlinkHeader {
     pub fn from_bytes(v: &[u8]) -> Result<NetlinkHeader> {
         if v.len() < NetlinkHeader::size() {
-            return Err(Error::new(ErrorKind::UnexpectedEof, "message too short"));
+            return Err(NetlinkError::Truncated {
+                what: "netlink header",
+                offset: 0,
+                attr: None,
+            });
         }
 
-        // Duplicate bytes, transmute to netlink header
-        let mem = v.to_owned();
-        let h: NetlinkHeader = unsafe { ptr::read(mem.as_ptr() as *mut NetlinkHeader) };
-
-        Ok(h)
+        Ok(NetlinkHeader {
+            len: u32_at(v, 0),
+            typ: u16_at(v, 4),
+            flags: u16_at(v, 6),
+            seq: u32_at(v, 8),
+            pid: u32_at(v, 12),
+        })
     }
 
     pub fn size() -> usize {
-        mem::size_of::<NetlinkHeader>()
+        0x10
+    }
+
+    /// Returns the flags as NlmFlags. Unknown bits are dropped.
+    pub fn nlm_flags(&self) -> NlmFlags {
+        NlmFlags::from_bits_truncate(self.flags)
     }
 }
 
@@ -43,10 +53,11 @@ impl crate::Serializable for NetlinkHeader {
         // We will append the rest of the message to this vector, so we might
         // as well allocate the whole thing now
         let mut out: Vec<u8> = Vec::with_capacity(self.len as usize);
-        unsafe {
-            ptr::copy_nonoverlapping(self, out.as_mut_ptr() as *mut NetlinkHeader, 1);
-            out.set_len(s);
-        }
+        out.extend_from_slice(&self.len.to_ne_bytes());
+        out.extend_from_slice(&self.typ.to_ne_bytes());
+        out.extend_from_slice(&self.flags.to_ne_bytes());
+        out.extend_from_slice(&self.seq.to_ne_bytes());
+        out.extend_from_slice(&self.pid.to_ne_bytes());
         return out;
     }
 }
@@ -69,12 +80,18 @@ pub struct NetlinkMessage {
 }
 
 impl NetlinkMessage {
-    pub fn new(typ: u16, flags: u16) -> NetlinkMessage {
+    /// Creates an empty message. The type is usually a family's typed
+    /// message type, such as `RtmType::GETLINK`, and the flags NlmFlags:
+    ///
+    /// ```ignore
+    /// NetlinkMessage::new(RtmType::GETLINK, NlmFlags::REQUEST | NlmFlags::DUMP)
+    /// ```
+    pub fn new(typ: impl Into<u16>, flags: impl Into<u16>) -> NetlinkMessage {
         NetlinkMessage {
             header: NetlinkHeader {
                 len: NetlinkHeader::size() as u32,
-                typ: typ,
-                flags: flags,
+                typ: typ.into(),
+                flags: flags.into(),
                 seq: 0, // set when sending
                 pid: 0, // set by kernel
             },
@@ -83,44 +100,13 @@ impl NetlinkMessage {
     }
 
     pub fn from_bytes(v: &[u8]) -> Result<Vec<NetlinkMessage>> {
-        let mut idx = 0;
-        let mut res = Vec::new();
-        let len = v.len();
-
-        while idx < len {
-            let msg = NetlinkMessage::one_from_bytes(v, idx)?;
-            idx += msg.header.len as usize;
-            idx = crate::util::align(idx);
-            res.push(msg);
-        }
-
-        Ok(res)
+        NetlinkMessageRef::iter(v)
+            .map(|msg| msg.map(|msg| msg.to_message()))
+            .collect()
     }
 
     pub fn one_from_bytes(v: &[u8], idx: usize) -> Result<NetlinkMessage> {
-        if v.len() < (idx + NetlinkHeader::size()) {
-            return Err(Error::new(
-                ErrorKind::UnexpectedEof,
-                "message too short for header",
-            ));
-        }
-
-        // read the header pointing at idx
-        let header = NetlinkHeader::from_bytes(&v[idx..idx + NetlinkHeader::size()])?;
-        let header_len = header.len as usize;
-        if v.len() < (idx + header_len) {
-            return Err(Error::new(
-                ErrorKind::UnexpectedEof,
-                "buffer too short for message",
-            ));
-        }
-
-        // the leftover data is [idx + header .. idx +  len]
-        let pkt = NetlinkMessage {
-            header: header,
-            data: v[idx + NetlinkHeader::size()..idx + header_len].to_owned(),
-        };
-        return Ok(pkt);
+        Ok(NetlinkMessageRef::one_from_bytes(v, idx)?.to_message())
     }
 
     /// Adds some raw data to the netlink message, and updates length.
@@ -148,22 +134,120 @@ impl Serializable for NetlinkMessage {
     }
 }
 
+/// NetlinkMessageRef is a netlink message borrowed from a buffer, usually
+/// the one it was received into. Parsing one doesn't copy the payload.
+#[derive(Debug, Clone)]
+pub struct NetlinkMessageRef<'a> {
+    pub header: NetlinkHeader,
+    pub data: &'a [u8], // the remaining data
+}
+
+impl<'a> NetlinkMessageRef<'a> {
+    /// Returns a lazy iterator over the messages in `v`. It ends after the
+    /// first error.
+    pub fn iter(v: &'a [u8]) -> NetlinkMessages<'a> {
+        NetlinkMessages { buf: v, idx: 0 }
+    }
+
+    pub fn one_from_bytes(v: &'a [u8], idx: usize) -> Result<NetlinkMessageRef<'a>> {
+        if v.len() < (idx + NetlinkHeader::size()) {
+            return Err(NetlinkError::Truncated {
+                what: "netlink header",
+                offset: idx,
+                attr: None,
+            });
+        }
+
+        // read the header pointing at idx
+        let header = NetlinkHeader::from_bytes(&v[idx..idx + NetlinkHeader::size()])?;
+        let header_len = header.len as usize;
+        if header_len < NetlinkHeader::size() {
+            return Err(NetlinkError::Malformed {
+                what: "netlink message length",
+                offset: idx,
+                attr: None,
+            });
+        }
+        if v.len() < (idx + header_len) {
+            return Err(NetlinkError::Truncated {
+                what: "netlink message",
+                offset: idx,
+                attr: None,
+            });
+        }
+
+        // the leftover data is [idx + header .. idx +  len]
+        Ok(NetlinkMessageRef {
+            header,
+            data: &v[idx + NetlinkHeader::size()..idx + header_len],
+        })
+    }
+
+    /// Copies the message out of the buffer.
+    pub fn to_message(&self) -> NetlinkMessage {
+        NetlinkMessage {
+            header: self.header.clone(),
+            data: self.data.to_owned(),
+        }
+    }
+}
+
+impl<'a> From<&'a NetlinkMessage> for NetlinkMessageRef<'a> {
+    fn from(msg: &'a NetlinkMessage) -> Self {
+        NetlinkMessageRef {
+            header: msg.header.clone(),
+            data: &msg.data,
+        }
+    }
+}
+
+/// NetlinkMessages is an iterator over the messages in a buffer, returned by
+/// `NetlinkMessageRef::iter`.
+#[derive(Debug, Clone)]
+pub struct NetlinkMessages<'a> {
+    buf: &'a [u8],
+    idx: usize,
+}
+
+impl<'a> Iterator for NetlinkMessages<'a> {
+    type Item = Result<NetlinkMessageRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.idx >= self.buf.len() {
+            return None;
+        }
+        match NetlinkMessageRef::one_from_bytes(self.buf, self.idx) {
+            Ok(msg) => {
+                self.idx = crate::util::align(self.idx + msg.header.len as usize);
+                Some(Ok(msg))
+            }
+            Err(e) => {
+                self.idx = self.buf.len();
+                Some(Err(e))
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::NetlinkHeader;
-    use super::NetlinkMessage;
-    use crate::Serializable;
+    use super::{NetlinkMessage, NetlinkMessageRef};
+    use crate::{NetlinkError, Serializable};
+
+    /// Builds the wire form of a header in host byte order.
+    fn header(len: u32, typ: u16, flags: u16, seq: u32, pid: u32) -> Vec<u8> {
+        let mut b = len.to_ne_bytes().to_vec();
+        b.extend(&typ.to_ne_bytes());
+        b.extend(&flags.to_ne_bytes());
+        b.extend(&seq.to_ne_bytes());
+        b.extend(&pid.to_ne_bytes());
+        b
+    }
 
     #[test]
     fn test_from_one() {
-        // TODO: big-endian machines
-        let mut b = vec![
-            0x10, 0, 0, 0, //len
-            2, 0, // type
-            3, 0, // flags
-            4, 0, 0, 0, // sequence
-            5, 0, 0, 0, // pid
-        ];
+        let mut b = header(0x10, 2, 3, 4, 5);
 
         let p = NetlinkMessage::one_from_bytes(&b, 0);
         assert!(p.is_ok());
@@ -182,7 +266,7 @@ mod tests {
 
         // Add a byte of data to b
         b.push(6);
-        b[0] = 0x11; // adjust length
+        b[0..4].copy_from_slice(&0x11u32.to_ne_bytes()); // adjust length
         let p = NetlinkMessage::one_from_bytes(&b, 0).unwrap();
         assert_eq!(p.data, vec![6]);
         assert_eq!(p.data, vec![6]);
@@ -190,19 +274,9 @@ mod tests {
 
     #[test]
     fn test_from_bytes() {
-        let b = vec![
-            0x14, 0, 0, 0, //len
-            2, 0, // type
-            3, 0, // flags
-            4, 0, 0, 0, // sequence
-            5, 0, 0, 0, // pid
-            6, 0, 0, 0, // extra data
-            0x10, 0, 0, 0, //len
-            22, 0, // type
-            33, 0, // flags
-            44, 0, 0, 0, // sequence
-            55, 0, 0, 0, // pid
-        ];
+        let mut b = header(0x14, 2, 3, 4, 5);
+        b.extend(&[6, 0, 0, 0]); // extra data
+        b.extend(header(0x10, 22, 33, 44, 55));
         let msgs = NetlinkMessage::from_bytes(&b);
         assert!(msgs.is_ok());
         let msgs = msgs.unwrap();
@@ -214,6 +288,32 @@ mod tests {
         assert_eq!(msgs[1].header.pid, 55);
     }
 
+    #[test]
+    fn test_iter() {
+        let mut b = header(0x14, 2, 3, 4, 5);
+        b.extend(&[6, 7, 0, 0]); // extra data
+        b.extend(header(0x12, 22, 33, 44, 55));
+        b.extend(&[8, 9, 0, 0]); // unaligned data, then padding
+        b.extend(header(0x20, 1, 1, 1, 1)); // runs past the end
+
+        let mut it = NetlinkMessageRef::iter(&b);
+        let m = it.next().unwrap().unwrap();
+        assert_eq!(m.header.seq, 4);
+        assert_eq!(m.data, &[6, 7, 0, 0]);
+        assert_eq!(m.data.as_ptr(), b[0x10..].as_ptr());
+
+        let m = it.next().unwrap().unwrap();
+        assert_eq!(m.header.pid, 55);
+        assert_eq!(m.data, &[8, 9]);
+        assert_eq!(m.to_message().data, vec![8, 9]);
+
+        match it.next() {
+            Some(Err(NetlinkError::Truncated { offset, .. })) => assert_eq!(offset, 0x28),
+            r => panic!("unexpected result {:?}", r),
+        }
+        assert!(it.next().is_none());
+    }
+
     #[test]
     fn test_header_serialize() {
         let h = NetlinkHeader {
@@ -227,40 +327,24 @@ mod tests {
         let b = h.to_bytes();
         assert_eq!(b.len(), NetlinkHeader::size());
         assert_eq!(b.capacity(), 0x20);
-        assert_eq!(
-            b,
-            vec![
-                0x20, 0, 0, 0, //len
-                2, 0, // type
-                99, 0, // flags
-                4, 0, 0, 0, // sequence
-                5, 0, 0, 0, // pid
-            ]
-        );
+        assert_eq!(b, header(0x20, 2, 99, 4, 5));
+
+        assert_eq!(NetlinkHeader::from_bytes(&b).unwrap(), h);
     }
 
     #[test]
     fn test_message_serialize() {
-        let mut m = NetlinkMessage::new(42, 33);
+        let mut m = NetlinkMessage::new(42u16, 33u16);
         m.add_data(vec![1, 2, 3, 4]);
 
-        let b = m.to_bytes();
-        assert_eq!(
-            b,
-            vec![
-                0x14, 0, 0, 0, //len
-                42, 0, //type
-                33, 0, //flags
-                0, 0, 0, 0, // sequence
-                0, 0, 0, 0, // pid
-                1, 2, 3, 4, // extra data
-            ]
-        );
+        let mut expected = header(0x14, 42, 33, 0, 0);
+        expected.extend(&[1, 2, 3, 4]); // extra data
+        assert_eq!(m.to_bytes(), expected);
     }
 
     #[test]
     fn test_add_data() {
-        let mut m = NetlinkMessage::new(99, 88);
+        let mut m = NetlinkMessage::new(99u16, 88u16);
         m.add_data(vec![1, 2, 3, 4]);
 
         assert_eq!(m.header.len, 0x14);
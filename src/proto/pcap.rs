@@ -0,0 +1,420 @@
+use super::transport::Transport;
+use super::NetlinkMessage;
+use crate::{NetlinkError, Result};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, ErrorKind, Read, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// LINKTYPE_NETLINK, as used by nlmon captures.
+const LINKTYPE_NETLINK: u32 = 253;
+
+/// ARPHRD_NETLINK, the hardware type in the cooked header.
+const ARPHRD_NETLINK: u16 = 824;
+
+/// Packet types in the cooked header, as in packet(7).
+const PACKET_HOST: u16 = 0;
+const PACKET_OUTGOING: u16 = 4;
+
+const PCAP_MAGIC: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NSEC: u32 = 0xa1b2_3c4d;
+const SNAPLEN: u32 = 1 << 18;
+
+const FILE_HEADER_LEN: usize = 24;
+const RECORD_HEADER_LEN: usize = 16;
+
+/// Every packet starts with a header like that of LINKTYPE_LINUX_SLL, with
+/// all fields big-endian.
+const COOKED_HEADER_LEN: usize = 16;
+
+/// Datagram is a single netlink datagram read from a capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Datagram {
+    /// Whether it was sent (rather than received) by the capturing socket.
+    pub outgoing: bool,
+
+    /// The netlink protocol, e.g. NETLINK_ROUTE.
+    pub proto: u16,
+
+    /// The messages, exactly as they were sent or received.
+    pub data: Vec<u8>,
+}
+
+/// PcapWriter writes netlink datagrams to a pcap file with the
+/// LINKTYPE_NETLINK link type, which Wireshark can open.
+pub struct PcapWriter<W: Write> {
+    w: W,
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Writes the pcap file header.
+    pub fn new(mut w: W) -> Result<PcapWriter<W>> {
+        let mut hdr = Vec::with_capacity(FILE_HEADER_LEN);
+        hdr.extend(&PCAP_MAGIC.to_ne_bytes());
+        hdr.extend(&2u16.to_ne_bytes()); // version 2.4
+        hdr.extend(&4u16.to_ne_bytes());
+        hdr.extend(&0i32.to_ne_bytes()); // timezone
+        hdr.extend(&0u32.to_ne_bytes()); // timestamp accuracy
+        hdr.extend(&SNAPLEN.to_ne_bytes());
+        hdr.extend(&LINKTYPE_NETLINK.to_ne_bytes());
+        w.write_all(&hdr)?;
+        Ok(PcapWriter { w })
+    }
+
+    /// Writes a datagram of netlink protocol `proto`, timestamped now.
+    pub fn write_datagram(&mut self, proto: u16, outgoing: bool, data: &[u8]) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let len = (COOKED_HEADER_LEN + data.len()) as u32;
+
+        let mut rec = Vec::with_capacity(RECORD_HEADER_LEN + len as usize);
+        rec.extend(&(now.as_secs() as u32).to_ne_bytes());
+        rec.extend(&now.subsec_micros().to_ne_bytes());
+        rec.extend(&len.to_ne_bytes()); // captured length
+        rec.extend(&len.to_ne_bytes()); // original length
+
+        let pkttype = if outgoing {
+            PACKET_OUTGOING
+        } else {
+            PACKET_HOST
+        };
+        rec.extend(&pkttype.to_be_bytes());
+        rec.extend(&ARPHRD_NETLINK.to_be_bytes());
+        rec.extend(&[0; 10]); // no link-layer address
+        rec.extend(&proto.to_be_bytes());
+        rec.extend(data);
+
+        self.w.write_all(&rec)?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        self.w.flush()?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.w
+    }
+}
+
+impl<W: Write> fmt::Debug for PcapWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PcapWriter").finish()
+    }
+}
+
+/// PcapReader reads the netlink datagrams from a LINKTYPE_NETLINK pcap file.
+pub struct PcapReader<R: Read> {
+    r: R,
+
+    /// Was the file written on a machine of the other endianness?
+    swapped: bool,
+    offset: usize,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Reads the pcap file header.
+    pub fn new(mut r: R) -> Result<PcapReader<R>> {
+        let mut hdr = [0; FILE_HEADER_LEN];
+        read_full(&mut r, &mut hdr, "pcap header", 0)?;
+
+        let magic = u32::from_ne_bytes([hdr[0], hdr[1], hdr[2], hdr[3]]);
+        let swapped = match magic {
+            PCAP_MAGIC | PCAP_MAGIC_NSEC => false,
+            _ if magic.swap_bytes() == PCAP_MAGIC || magic.swap_bytes() == PCAP_MAGIC_NSEC => true,
+            _ => return Err(malformed("pcap magic", 0)),
+        };
+
+        let pr = PcapReader {
+            r,
+            swapped,
+            offset: FILE_HEADER_LEN,
+        };
+        if pr.u32_at(&hdr, 20) != LINKTYPE_NETLINK {
+            return Err(malformed("pcap link type", 20));
+        }
+        Ok(pr)
+    }
+
+    fn u32_at(&self, b: &[u8], idx: usize) -> u32 {
+        let v = u32::from_ne_bytes([b[idx], b[idx + 1], b[idx + 2], b[idx + 3]]);
+        if self.swapped {
+            v.swap_bytes()
+        } else {
+            v
+        }
+    }
+
+    /// Reads the next datagram, or None at the end of the file.
+    pub fn next_datagram(&mut self) -> Result<Option<Datagram>> {
+        let mut hdr = [0; RECORD_HEADER_LEN];
+        match self.r.read(&mut hdr[..1])? {
+            0 => return Ok(None),
+            _ => read_full(&mut self.r, &mut hdr[1..], "pcap record", self.offset)?,
+        }
+
+        let len = self.u32_at(&hdr, 8) as usize;
+        let orig_len = self.u32_at(&hdr, 12) as usize;
+        let offset = self.offset + RECORD_HEADER_LEN;
+        if len < COOKED_HEADER_LEN || len > SNAPLEN as usize {
+            return Err(malformed("pcap record length", offset));
+        }
+
+        let mut pkt = vec![0; len];
+        read_full(&mut self.r, &mut pkt, "pcap record", offset)?;
+        self.offset = offset + len;
+
+        if len < orig_len {
+            return Err(NetlinkError::DatagramTruncated {
+                size: orig_len - COOKED_HEADER_LEN,
+                received: len - COOKED_HEADER_LEN,
+            });
+        }
+        if u16::from_be_bytes([pkt[2], pkt[3]]) != ARPHRD_NETLINK {
+            return Err(malformed("pcap cooked header", offset));
+        }
+
+        Ok(Some(Datagram {
+            outgoing: u16::from_be_bytes([pkt[0], pkt[1]]) == PACKET_OUTGOING,
+            proto: u16::from_be_bytes([pkt[14], pkt[15]]),
+            data: pkt.split_off(COOKED_HEADER_LEN),
+        }))
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<Datagram>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_datagram().transpose()
+    }
+}
+
+fn read_full<R: Read>(r: &mut R, buf: &mut [u8], what: &'static str, offset: usize) -> Result<()> {
+    r.read_exact(buf).map_err(|e| match e.kind() {
+        ErrorKind::UnexpectedEof => NetlinkError::Truncated {
+            what,
+            offset,
+            attr: None,
+        },
+        _ => e.into(),
+    })
+}
+
+fn malformed(what: &'static str, offset: usize) -> NetlinkError {
+    NetlinkError::Malformed {
+        what,
+        offset,
+        attr: None,
+    }
+}
+
+/// The port id of a PcapReplay.
+const REPLAY_PID: u32 = 4243;
+
+/// PcapReplay is a Transport that plays back a capture, e.g. one made with
+/// `NetlinkSocket::record`, so that `hl` functions can be run against it.
+///
+/// Each request sent is matched to the next request in the capture, and
+/// answered with the responses to that one. The request itself is not
+/// compared, so the code being replayed must make the same requests in the
+/// same order. Notifications in the capture are skipped.
+#[derive(Debug)]
+pub struct PcapReplay {
+    datagrams: VecDeque<Datagram>,
+    next_seq: u32,
+
+    /// Our sequence number for each recorded one.
+    seqs: HashMap<u32, u32>,
+}
+
+impl PcapReplay {
+    pub fn new<R: Read>(r: R) -> Result<PcapReplay> {
+        Ok(PcapReplay {
+            datagrams: PcapReader::new(r)?.collect::<Result<_>>()?,
+            next_seq: 0,
+            seqs: HashMap::new(),
+        })
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<PcapReplay> {
+        PcapReplay::new(BufReader::new(File::open(path)?))
+    }
+
+    /// Takes the next datagram that was sent, or received, by the capturing
+    /// socket.
+    fn take(&mut self, outgoing: bool) -> Result<Vec<NetlinkMessage>> {
+        match self.datagrams.iter().position(|d| d.outgoing == outgoing) {
+            Some(i) => {
+                let d = self.datagrams.remove(i).unwrap();
+                NetlinkMessage::from_bytes(&d.data)
+            }
+            None => Err(io::Error::new(ErrorKind::UnexpectedEof, "end of capture").into()),
+        }
+    }
+}
+
+impl Transport for PcapReplay {
+    fn send(&mut self, buf: &[u8]) -> Result<()> {
+        let ours = NetlinkMessage::from_bytes(buf)?;
+        let recorded = self.take(true)?;
+        if ours.len() != recorded.len() {
+            return Err(NetlinkError::Protocol("request doesn't match the capture"));
+        }
+        for (ours, recorded) in ours.iter().zip(recorded.iter()) {
+            // Notifications have sequence number 0, so a request recorded
+            // with it can't be told apart from them.
+            if recorded.header.seq != 0 {
+                self.seqs.insert(recorded.header.seq, ours.header.seq);
+            }
+        }
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Result<Vec<NetlinkMessage>> {
+        let mut msgs = self.take(false)?;
+        for msg in msgs.iter_mut().filter(|m| m.header.seq != 0) {
+            if let Some(seq) = self.seqs.get(&msg.header.seq) {
+                msg.header.seq = *seq;
+                msg.header.pid = REPLAY_PID;
+            }
+        }
+        Ok(msgs)
+    }
+
+    fn pid(&self) -> Result<u32> {
+        Ok(REPLAY_PID)
+    }
+
+    fn next_seq(&mut self) -> u32 {
+        self.next_seq += 1;
+        self.next_seq
+    }
+
+    fn unsolicited(&mut self, _msg: NetlinkMessage) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Datagram, PcapReader, PcapReplay, PcapWriter};
+    use crate::hl::iface::{self, test_link};
+    use crate::proto::conn::NetlinkSocket;
+    use crate::proto::{NetlinkMessage, NlmFlags, NlmsgType, Transport};
+    use crate::type_route::{IfInfoMsg, RtmType};
+    use crate::uapi;
+    use crate::Serializable;
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+
+    /// A capture that can still be read once the socket has it.
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn link(seq: u32, index: i32, name: &str) -> Vec<u8> {
+        let mut msg = test_link(index, name, NlmFlags::MULTI);
+        msg.header.seq = seq;
+        msg.header.pid = 99;
+        msg.to_bytes()
+    }
+
+    #[test]
+    fn test_replay() {
+        let proto = uapi::NETLINK_ROUTE as u16;
+        let mut w = PcapWriter::new(vec![]).unwrap();
+
+        let mut req = NetlinkMessage::new(RtmType::GETLINK, NlmFlags::DUMP | NlmFlags::REQUEST);
+        req.header.seq = 17;
+        req.add_data(IfInfoMsg::default().to_bytes());
+        w.write_datagram(proto, true, &req.to_bytes()).unwrap();
+
+        let mut dg = link(17, 1, "lo");
+        dg.extend(link(17, 2, "eth0"));
+        w.write_datagram(proto, false, &dg).unwrap();
+
+        let mut done = NetlinkMessage::new(NlmsgType::DONE, NlmFlags::MULTI);
+        done.header.seq = 17;
+        done.header.pid = 99;
+        done.add_data(vec![0; 4]);
+        w.write_datagram(proto, false, &done.to_bytes()).unwrap();
+        let capture = w.into_inner();
+
+        let dgs: Vec<Datagram> = PcapReader::new(&capture[..])
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(dgs.len(), 3);
+        assert!(dgs[0].outgoing);
+        assert!(!dgs[1].outgoing);
+        assert_eq!(dgs[1].proto, proto);
+        assert_eq!(dgs[1].data, dg);
+
+        let mut replay = PcapReplay::new(&capture[..]).unwrap();
+        let links = iface::link_list(&mut replay).unwrap();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[1].name.as_ref().unwrap().to_str().unwrap(), "eth0");
+
+        // the capture is used up
+        assert!(iface::link_list(&mut replay).is_err());
+
+        assert!(PcapReader::new(&capture[..10]).is_err());
+        assert!(PcapReader::new(&[0u8; 24][..]).is_err());
+    }
+
+    #[test]
+    fn test_replay_notification() {
+        let proto = uapi::NETLINK_ROUTE as u16;
+        let mut w = PcapWriter::new(vec![]).unwrap();
+
+        // a request that was sent without a sequence number, then a
+        // notification that arrived before its response
+        let req = NetlinkMessage::new(RtmType::GETLINK, NlmFlags::REQUEST);
+        w.write_datagram(proto, true, &req.to_bytes()).unwrap();
+        w.write_datagram(proto, false, &link(0, 3, "veth0"))
+            .unwrap();
+        let capture = w.into_inner();
+
+        let mut replay = PcapReplay::new(&capture[..]).unwrap();
+        let mut req = NetlinkMessage::new(RtmType::GETLINK, NlmFlags::REQUEST);
+        req.header.seq = replay.next_seq();
+        replay.send(&req.to_bytes()).unwrap();
+
+        let msgs = replay.recv().unwrap();
+        assert_eq!(msgs[0].header.seq, 0);
+        assert_eq!(msgs[0].header.pid, 99);
+    }
+
+    #[test]
+    fn test_replay_recorded() {
+        // The first request of a fresh socket must be replayable too.
+        let mut sock = NetlinkSocket::new(uapi::NETLINK_ROUTE as i32).unwrap();
+        let capture = SharedBuf::default();
+        sock.record(capture.clone()).unwrap();
+        let links = iface::link_list(&mut sock).unwrap();
+        let link = iface::link_get_by_index(&mut sock, links[0].index).unwrap();
+        sock.stop_recording().unwrap();
+
+        let capture = capture.0.lock().unwrap().clone();
+        let mut replay = PcapReplay::new(&capture[..]).unwrap();
+        let replayed = iface::link_list(&mut replay).unwrap();
+        assert_eq!(replayed.len(), links.len());
+        assert_eq!(replayed[0].name, links[0].name);
+        let replayed = iface::link_get_by_index(&mut replay, link.index).unwrap();
+        assert_eq!(replayed.name, link.name);
+    }
+}
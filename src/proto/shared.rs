@@ -0,0 +1,568 @@
+use super::conn::{
+    check_response, complete, is_interrupted, is_last, DumpMode, NetlinkSocket, Response,
+};
+use super::{NetNs, NetlinkHeader, NetlinkMessage, NlmFlags};
+use crate::uapi;
+use crate::{NetlinkError, Result, Serializable};
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+/// SharedNetlinkSocket is a blocking netlink socket that can be shared
+/// between threads.
+///
+/// It is cheap to clone, and all clones share the same underlying socket.
+/// Each request gets its own sequence number. There is no background thread:
+/// whichever waiting thread finds nobody else reading does the reading, and
+/// routes every response to the thread waiting for that sequence number.
+/// Unsolicited messages are kept in a separate queue, see `events()`.
+///
+/// The kernel only runs one dump per socket at a time, so dump requests are
+/// queued behind each other. Other requests are not affected.
+#[derive(Clone)]
+pub struct SharedNetlinkSocket {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    sock: NetlinkSocket,
+    pid: u32,
+    next_seq: AtomicU32,
+    state: Mutex<State>,
+
+    /// The receive buffer; only touched by the thread that is reading.
+    buf: Mutex<Vec<u8>>,
+
+    /// Signalled whenever new messages were routed, the reader finished,
+    /// or a dump finished.
+    cond: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    /// Is some thread currently blocked receiving?
+    reading: bool,
+
+    /// Is a dump in progress?
+    dumping: bool,
+
+    /// Have we joined any multicast groups?
+    listening: bool,
+
+    /// Were notifications lost since the last time events were read?
+    overrun: bool,
+
+    /// Outstanding requests, by sequence number.
+    pending: HashMap<u32, Waiter>,
+
+    /// Unsolicited messages not yet handed out.
+    events: VecDeque<Result<NetlinkMessage>>,
+}
+
+/// Whoever is waiting for the responses with a given sequence number.
+enum Waiter {
+    /// A request whose Responses is still alive, with the responses it
+    /// hasn't taken yet.
+    Active(VecDeque<Result<NetlinkMessage>>),
+
+    /// A dump whose Responses was dropped before the kernel finished
+    /// sending it. The dump stays in progress until the rest has been read,
+    /// since the kernel won't start another dump on this socket before then.
+    Draining,
+}
+
+impl SharedNetlinkSocket {
+    pub fn new(proto: i32) -> Result<SharedNetlinkSocket> {
+        SharedNetlinkSocket::with_socket(NetlinkSocket::new(proto)?)
+    }
+
+    /// Opens a socket in another network namespace, see
+    /// `NetlinkSocket::new_in_netns`.
+    pub fn new_in_netns(proto: i32, ns: &NetNs) -> Result<SharedNetlinkSocket> {
+        SharedNetlinkSocket::with_socket(NetlinkSocket::new_in_netns(proto, ns)?)
+    }
+
+    fn with_socket(sock: NetlinkSocket) -> Result<SharedNetlinkSocket> {
+        let pid = sock.pid()?;
+
+        Ok(SharedNetlinkSocket {
+            inner: Arc::new(Inner {
+                sock,
+                pid,
+                // kernel notifications have sequence number 0
+                next_seq: AtomicU32::new(1),
+                state: Mutex::new(Default::default()),
+                buf: Mutex::new(vec![]),
+                cond: Condvar::new(),
+            }),
+        })
+    }
+
+    /// Joins a multicast group, e.g. uapi::RTNLGRP_LINK. Notifications sent
+    /// to the group are then available via `events()`.
+    pub fn add_membership(&self, group: u32) -> Result<()> {
+        self.inner.sock.setsockopt_u32(
+            libc::SOL_NETLINK,
+            uapi::NETLINK_ADD_MEMBERSHIP as i32,
+            group,
+        )?;
+        self.inner.lock().listening = true;
+        Ok(())
+    }
+
+    /// Leaves a multicast group previously joined with `add_membership`.
+    pub fn drop_membership(&self, group: u32) -> Result<()> {
+        self.inner.sock.setsockopt_u32(
+            libc::SOL_NETLINK,
+            uapi::NETLINK_DROP_MEMBERSHIP as i32,
+            group,
+        )
+    }
+
+    /// The underlying socket, e.g. for setting socket options.
+    pub fn socket(&self) -> &NetlinkSocket {
+        &self.inner.sock
+    }
+
+    /// Returns a blocking iterator over unsolicited messages. If several
+    /// threads iterate at once, each message goes to only one of them.
+    /// If notifications were lost, the iterator yields NetlinkError::Overrun.
+    pub fn events(&self) -> Events {
+        Events {
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Sends a request, returning a blocking iterator over its responses.
+    pub fn request(&self, request: &mut NetlinkMessage) -> Result<Responses> {
+        let flags = request.header.nlm_flags();
+        let dump = flags.contains(NlmFlags::DUMP);
+
+        let seq = self.inner.next_seq.fetch_add(1, Ordering::Relaxed);
+        request.header.seq = seq;
+
+        if dump {
+            // Nobody else reads the rest of an abandoned dump, so we may
+            // have to before ours can start.
+            self.inner.wait(
+                |state| {
+                    state
+                        .pending
+                        .values()
+                        .any(|w| matches!(w, Waiter::Draining))
+                },
+                |state| {
+                    if state.dumping {
+                        return None;
+                    }
+                    state.dumping = true;
+                    Some(Ok(()))
+                },
+            )?;
+        }
+
+        // Register before sending, so we can't miss the response.
+        self.inner
+            .lock()
+            .pending
+            .insert(seq, Waiter::Active(VecDeque::new()));
+        let mut resps = Responses {
+            inner: self.inner.clone(),
+            seq,
+            request: request.to_bytes(),
+            ack: flags.contains(NlmFlags::ACK),
+            dump,
+            done: false,
+            interrupted: false,
+        };
+
+        if let Err(e) = self.inner.sock.send(&resps.request) {
+            // Nothing was sent, so there is nothing to drain.
+            resps.finish(true);
+            return Err(e);
+        }
+        Ok(resps)
+    }
+
+    /// Sends a request and collects all of its responses, like
+    /// `NetlinkSocket::exec`.
+    pub fn exec(
+        &self,
+        request: &mut NetlinkMessage,
+        resp_typ: Option<u16>,
+    ) -> Result<Vec<NetlinkMessage>> {
+        let mut out = vec![];
+        let mut resps = self.request(request)?;
+        for resp in resps.by_ref() {
+            let resp = resp?;
+            if resp_typ.is_none() || resp_typ == Some(resp.header.typ) {
+                out.push(resp);
+            }
+        }
+        complete(out, resps.interrupted())
+    }
+
+    /// Executes a dump request, like `NetlinkSocket::exec_dump`.
+    pub fn exec_dump(
+        &self,
+        request: &mut NetlinkMessage,
+        resp_typ: Option<u16>,
+        mode: DumpMode,
+    ) -> Result<Vec<NetlinkMessage>> {
+        let mut retries = match mode {
+            DumpMode::Retry(n) => n,
+            DumpMode::Partial => 0,
+        };
+        loop {
+            match self.exec(request, resp_typ) {
+                Err(NetlinkError::DumpInterrupted(_)) if retries > 0 => retries -= 1,
+                res => return res,
+            }
+        }
+    }
+}
+
+impl Inner {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    /// Blocks until `take` returns something. While waiting, reads from
+    /// the socket if `read` says to and no other thread is doing so.
+    fn wait<T, R, F>(&self, read: R, mut take: F) -> Result<T>
+    where
+        R: Fn(&State) -> bool,
+        F: FnMut(&mut State) -> Option<Result<T>>,
+    {
+        let mut state = self.lock();
+        loop {
+            if let Some(res) = take(&mut state) {
+                return res;
+            }
+
+            if state.reading || !read(&state) {
+                state = self.cond.wait(state).unwrap();
+                continue;
+            }
+
+            // Our turn to read.
+            state.reading = true;
+            drop(state);
+            let mut buf = self.buf.lock().unwrap();
+            let res = self.sock.recv_into(&mut buf);
+            state = self.lock();
+            state.reading = false;
+            self.cond.notify_all();
+
+            let msgs = match res {
+                Ok(msgs) => msgs,
+                // Lost some notifications; tell whoever reads events.
+                Err(NetlinkError::Overrun) if state.listening => {
+                    state.overrun = true;
+                    continue;
+                }
+                // Responses may have been lost, or the socket is broken;
+                // either way, every request has to hear about it.
+                Err(e @ NetlinkError::Overrun) | Err(e @ NetlinkError::Io(_)) => {
+                    state.fail_all(&e);
+                    return take(&mut state).unwrap_or(Err(e));
+                }
+                // A datagram we couldn't parse; the socket is still usable.
+                Err(e) => {
+                    state.fail(&buf, e, self.pid);
+                    continue;
+                }
+            };
+            drop(buf);
+            for msg in msgs {
+                state.route(msg, self.pid);
+            }
+        }
+    }
+}
+
+impl State {
+    fn route(&mut self, msg: NetlinkMessage, pid: u32) {
+        // Responses to our requests carry our port id. If nobody is waiting
+        // any more, the request was abandoned.
+        if msg.header.pid == pid {
+            match self.pending.get_mut(&msg.header.seq) {
+                Some(Waiter::Active(q)) => q.push_back(Ok(msg)),
+                Some(Waiter::Draining) if is_last(&msg.header) => {
+                    self.pending.remove(&msg.header.seq);
+                    self.dumping = false;
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if self.listening {
+            self.events.push_back(Ok(msg));
+        }
+    }
+
+    /// Reports a datagram that couldn't be parsed. The header at the start
+    /// of it usually survives, and says which request it belonged to;
+    /// otherwise it was presumably a notification.
+    fn fail(&mut self, datagram: &[u8], err: NetlinkError, pid: u32) {
+        if let Ok(header) = NetlinkHeader::from_bytes(datagram) {
+            if header.pid == pid {
+                if let Some(Waiter::Active(q)) = self.pending.get_mut(&header.seq) {
+                    q.push_back(Err(err));
+                }
+                return;
+            }
+        }
+
+        if self.listening {
+            self.events.push_back(Err(err));
+        }
+    }
+
+    /// Hands an error that could affect any request to all of them.
+    fn fail_all(&mut self, err: &NetlinkError) {
+        for waiter in self.pending.values_mut() {
+            if let Waiter::Active(q) = waiter {
+                q.push_back(Err(match err {
+                    NetlinkError::Io(e) => io::Error::new(e.kind(), e.to_string()).into(),
+                    _ => NetlinkError::Overrun,
+                }));
+            }
+        }
+    }
+}
+
+/// Responses is a blocking iterator over the responses to a single request,
+/// returned by `SharedNetlinkSocket::request`. It ends once the request is
+/// complete, or after an error.
+pub struct Responses {
+    inner: Arc<Inner>,
+    seq: u32,
+    request: Vec<u8>,
+    ack: bool,
+    dump: bool,
+    done: bool,
+    interrupted: bool,
+}
+
+impl Responses {
+    /// The sequence number assigned to the request.
+    pub fn seq(&self) -> u32 {
+        self.seq
+    }
+
+    /// Whether the kernel flagged the dump as interrupted (NLM_F_DUMP_INTR),
+    /// meaning the responses may be inconsistent. Only final once the
+    /// iterator has ended.
+    pub fn interrupted(&self) -> bool {
+        self.interrupted
+    }
+
+    /// Stops returning responses. `finished` says the kernel has sent
+    /// everything it is going to; if it hasn't, the rest of a dump is read
+    /// and thrown away before the next dump can start.
+    fn finish(&mut self, finished: bool) {
+        if self.done {
+            return;
+        }
+        self.done = true;
+
+        let mut state = self.inner.lock();
+        // The end of the dump may already be waiting for us.
+        let finished = finished
+            || match state.pending.get(&self.seq) {
+                Some(Waiter::Active(q)) => q.iter().flatten().any(|m| is_last(&m.header)),
+                _ => false,
+            };
+        if self.dump && !finished {
+            state.pending.insert(self.seq, Waiter::Draining);
+            return;
+        }
+
+        state.pending.remove(&self.seq);
+        if self.dump {
+            state.dumping = false;
+            self.inner.cond.notify_all();
+        }
+    }
+}
+
+impl Iterator for Responses {
+    type Item = Result<NetlinkMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let seq = self.seq;
+        let resp = match self.inner.wait(
+            |_| true,
+            |state| match state.pending.get_mut(&seq)? {
+                Waiter::Active(q) => q.pop_front(),
+                Waiter::Draining => None,
+            },
+        ) {
+            Ok(resp) => resp,
+            // The kernel may still be sending the rest of the dump.
+            Err(e) => {
+                self.finish(false);
+                return Some(Err(e));
+            }
+        };
+
+        self.interrupted |= is_interrupted(&resp.header);
+        match check_response(resp, &self.request, self.ack) {
+            Ok(Response::Done) => {
+                self.finish(true);
+                None
+            }
+            Ok(Response::Data(resp, last)) => {
+                if last {
+                    self.finish(true);
+                }
+                Some(Ok(resp))
+            }
+            Err(e) => {
+                self.finish(true);
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl Drop for Responses {
+    fn drop(&mut self) {
+        self.finish(false);
+    }
+}
+
+/// Events is a blocking iterator over the unsolicited messages received by a
+/// SharedNetlinkSocket. It is returned by `SharedNetlinkSocket::events()`.
+pub struct Events {
+    inner: Arc<Inner>,
+}
+
+impl Iterator for Events {
+    type Item = Result<NetlinkMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.inner.wait(
+            |_| true,
+            |state| {
+                if state.overrun {
+                    state.overrun = false;
+                    return Some(Err(NetlinkError::Overrun));
+                }
+                state.events.pop_front()
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SharedNetlinkSocket;
+    use crate::hl::iface::test_link;
+    use crate::proto::conn::FakeKernel;
+    use crate::proto::{NetlinkMessage, NlmFlags};
+    use crate::type_route::{IfInfoMsg, RtmType};
+    use crate::{uapi, NetlinkError, Serializable};
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    fn sockets() -> (SharedNetlinkSocket, FakeKernel) {
+        let sock = SharedNetlinkSocket::new(uapi::NETLINK_ROUTE as i32).unwrap();
+        let kernel = FakeKernel::new(sock.socket().pid().unwrap());
+        (sock, kernel)
+    }
+
+    fn index(msg: &NetlinkMessage) -> i32 {
+        IfInfoMsg::from_bytes(&msg.data).unwrap().index
+    }
+
+    #[test]
+    fn test_concurrent() {
+        let (sock, kernel) = sockets();
+        let a = sock
+            .request(&mut FakeKernel::request(NlmFlags::empty()))
+            .unwrap();
+        let b = sock
+            .request(&mut FakeKernel::request(NlmFlags::empty()))
+            .unwrap();
+        let (seq_a, seq_b) = (a.seq(), b.seq());
+
+        let threads: Vec<_> = vec![a, b]
+            .into_iter()
+            .map(|resps| thread::spawn(move || resps.collect::<Vec<_>>()))
+            .collect();
+        kernel.send(seq_b, vec![test_link(2, "b", NlmFlags::empty())]);
+        kernel.send(seq_a, vec![test_link(1, "a", NlmFlags::empty())]);
+
+        for (t, want) in threads.into_iter().zip(vec![1, 2]) {
+            let resps = t.join().unwrap();
+            assert_eq!(resps.len(), 1);
+            assert_eq!(index(resps[0].as_ref().unwrap()), want);
+        }
+    }
+
+    #[test]
+    fn test_abandoned_dump() {
+        let (sock, kernel) = sockets();
+        let mut first = sock
+            .request(&mut FakeKernel::request(NlmFlags::DUMP))
+            .unwrap();
+        let seq = first.seq();
+        kernel.send(seq, vec![test_link(1, "lo", NlmFlags::MULTI)]);
+        assert_eq!(index(&first.next().unwrap().unwrap()), 1);
+        drop(first);
+
+        // A real dump, which has to wait for the kernel to finish this one.
+        let (tx, rx) = mpsc::channel();
+        let s = sock.clone();
+        let t = thread::spawn(move || {
+            let mut req = NetlinkMessage::new(RtmType::GETLINK, NlmFlags::DUMP | NlmFlags::REQUEST);
+            req.add_data(IfInfoMsg::default().to_bytes());
+            tx.send(s.exec(&mut req, Some(RtmType::NEWLINK.into())))
+                .unwrap();
+        });
+        thread::sleep(Duration::from_millis(50));
+        assert!(rx.try_recv().is_err());
+
+        let rest = vec![test_link(2, "eth0", NlmFlags::MULTI), FakeKernel::done()];
+        kernel.send(seq, rest);
+        t.join().unwrap();
+        assert!(!rx.recv().unwrap().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_error_routing() {
+        let (sock, kernel) = sockets();
+        let mut a = sock
+            .request(&mut FakeKernel::request(NlmFlags::empty()))
+            .unwrap();
+        let mut b = sock
+            .request(&mut FakeKernel::request(NlmFlags::empty()))
+            .unwrap();
+        let seq_a = a.seq();
+
+        // b's response claims to be longer than it is
+        let mut bad = kernel.datagram(b.seq(), vec![test_link(2, "b", NlmFlags::empty())]);
+        bad[0] = 64;
+
+        // a reads both, and keeps only its own
+        let t = thread::spawn(move || a.next());
+        kernel.send_raw(&bad);
+        kernel.send(seq_a, vec![test_link(1, "a", NlmFlags::empty())]);
+        assert_eq!(index(&t.join().unwrap().unwrap().unwrap()), 1);
+
+        match b.next() {
+            Some(Err(NetlinkError::Truncated { .. })) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(b.next().is_none());
+    }
+}
@@ -0,0 +1,220 @@
+use super::conn::NetlinkSocket;
+use crate::uapi;
+use crate::Result;
+use std::io::{Error, ErrorKind};
+use std::mem;
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+/// Socket options. The netlink-specific ones are documented in netlink(7).
+impl NetlinkSocket {
+    pub(crate) fn setsockopt<T>(&self, level: i32, name: i32, val: &T) -> Result<()> {
+        if self.as_raw_fd() <= 0 {
+            return Err(Error::new(ErrorKind::NotConnected, "not connected").into());
+        }
+
+        let res = unsafe {
+            libc::setsockopt(
+                self.as_raw_fd(),
+                level,
+                name,
+                val as *const T as *const libc::c_void,
+                mem::size_of::<T>() as libc::socklen_t,
+            )
+        };
+        if res < 0 {
+            return Err(Error::last_os_error().into());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn getsockopt<T>(&self, level: i32, name: i32) -> Result<T> {
+        if self.as_raw_fd() <= 0 {
+            return Err(Error::new(ErrorKind::NotConnected, "not connected").into());
+        }
+
+        let mut val: T = unsafe { mem::zeroed() };
+        let mut len = mem::size_of::<T>() as libc::socklen_t;
+        let res = unsafe {
+            libc::getsockopt(
+                self.as_raw_fd(),
+                level,
+                name,
+                &mut val as *mut T as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if res < 0 {
+            return Err(Error::last_os_error().into());
+        }
+        Ok(val)
+    }
+
+    pub(crate) fn setsockopt_u32(&self, level: i32, name: i32, val: u32) -> Result<()> {
+        self.setsockopt(level, name, &val)
+    }
+
+    fn set_netlink_flag(&self, name: u32, on: bool) -> Result<()> {
+        self.setsockopt_u32(libc::SOL_NETLINK, name as i32, on as u32)
+    }
+
+    fn netlink_flag(&self, name: u32) -> Result<bool> {
+        let val: u32 = self.getsockopt(libc::SOL_NETLINK, name as i32)?;
+        Ok(val != 0)
+    }
+
+    /// NETLINK_EXT_ACK: the kernel explains errors. Enabled by default.
+    pub fn set_ext_ack(&self, on: bool) -> Result<()> {
+        self.set_netlink_flag(uapi::NETLINK_EXT_ACK, on)
+    }
+
+    pub fn ext_ack(&self) -> Result<bool> {
+        self.netlink_flag(uapi::NETLINK_EXT_ACK)
+    }
+
+    /// NETLINK_GET_STRICT_CHK: the kernel validates requests strictly, and
+    /// applies the filters in dump requests instead of ignoring them.
+    pub fn set_strict_checking(&self, on: bool) -> Result<()> {
+        self.set_netlink_flag(uapi::NETLINK_GET_STRICT_CHK, on)
+    }
+
+    pub fn strict_checking(&self) -> Result<bool> {
+        self.netlink_flag(uapi::NETLINK_GET_STRICT_CHK)
+    }
+
+    /// NETLINK_CAP_ACK: error messages don't echo the whole request back,
+    /// only its header.
+    pub fn set_cap_ack(&self, on: bool) -> Result<()> {
+        self.set_netlink_flag(uapi::NETLINK_CAP_ACK, on)
+    }
+
+    pub fn cap_ack(&self) -> Result<bool> {
+        self.netlink_flag(uapi::NETLINK_CAP_ACK)
+    }
+
+    /// NETLINK_NO_ENOBUFS: don't report receive buffer overruns. Messages
+    /// are still lost, you just don't find out.
+    pub fn set_no_enobufs(&self, on: bool) -> Result<()> {
+        self.set_netlink_flag(uapi::NETLINK_NO_ENOBUFS, on)
+    }
+
+    pub fn no_enobufs(&self) -> Result<bool> {
+        self.netlink_flag(uapi::NETLINK_NO_ENOBUFS)
+    }
+
+    /// NETLINK_BROADCAST_ERROR: notification senders learn about delivery
+    /// failures to this socket.
+    pub fn set_broadcast_error(&self, on: bool) -> Result<()> {
+        self.set_netlink_flag(uapi::NETLINK_BROADCAST_ERROR, on)
+    }
+
+    pub fn broadcast_error(&self) -> Result<bool> {
+        self.netlink_flag(uapi::NETLINK_BROADCAST_ERROR)
+    }
+
+    /// NETLINK_LISTEN_ALL_NSID: receive notifications from every network
+    /// namespace that has an id assigned in ours. The kernel doesn't report
+    /// this one back, so there is no getter.
+    pub fn set_listen_all_nsid(&self, on: bool) -> Result<()> {
+        self.set_netlink_flag(uapi::NETLINK_LISTEN_ALL_NSID, on)
+    }
+
+    /// NETLINK_PKTINFO: the kernel attaches the multicast group to each
+    /// received message as ancillary data.
+    pub fn set_pktinfo(&self, on: bool) -> Result<()> {
+        self.set_netlink_flag(uapi::NETLINK_PKTINFO, on)
+    }
+
+    pub fn pktinfo(&self) -> Result<bool> {
+        self.netlink_flag(uapi::NETLINK_PKTINFO)
+    }
+
+    /// SO_RCVBUF: the size of the receive buffer. The kernel doubles the
+    /// requested value for bookkeeping, and caps it at net.core.rmem_max.
+    pub fn set_recv_buffer_size(&self, size: usize) -> Result<()> {
+        self.setsockopt(libc::SOL_SOCKET, libc::SO_RCVBUF, &(size as libc::c_int))
+    }
+
+    /// SO_RCVBUFFORCE: like set_recv_buffer_size, but may exceed
+    /// net.core.rmem_max. Requires CAP_NET_ADMIN.
+    pub fn set_recv_buffer_size_force(&self, size: usize) -> Result<()> {
+        self.setsockopt(
+            libc::SOL_SOCKET,
+            libc::SO_RCVBUFFORCE,
+            &(size as libc::c_int),
+        )
+    }
+
+    pub fn recv_buffer_size(&self) -> Result<usize> {
+        let size: libc::c_int = self.getsockopt(libc::SOL_SOCKET, libc::SO_RCVBUF)?;
+        Ok(size as usize)
+    }
+
+    /// SO_SNDBUF: the size of the send buffer, which limits the size of a
+    /// single datagram.
+    pub fn send_buffer_size(&self) -> Result<usize> {
+        let size: libc::c_int = self.getsockopt(libc::SOL_SOCKET, libc::SO_SNDBUF)?;
+        Ok(size as usize)
+    }
+
+    /// SO_RCVTIMEO: how long a blocking receive waits before failing with
+    /// ErrorKind::WouldBlock. None waits forever.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        if timeout == Some(Duration::new(0, 0)) {
+            return Err(Error::new(ErrorKind::InvalidInput, "zero timeout").into());
+        }
+        let tv = match timeout {
+            Some(d) => libc::timeval {
+                tv_sec: d.as_secs() as libc::time_t,
+                tv_usec: d.subsec_micros() as libc::suseconds_t,
+            },
+            None => libc::timeval {
+                tv_sec: 0,
+                tv_usec: 0,
+            },
+        };
+        self.setsockopt(libc::SOL_SOCKET, libc::SO_RCVTIMEO, &tv)
+    }
+
+    pub fn read_timeout(&self) -> Result<Option<Duration>> {
+        let tv: libc::timeval = self.getsockopt(libc::SOL_SOCKET, libc::SO_RCVTIMEO)?;
+        if tv.tv_sec == 0 && tv.tv_usec == 0 {
+            return Ok(None);
+        }
+        Ok(Some(Duration::new(
+            tv.tv_sec as u64,
+            tv.tv_usec as u32 * 1000,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::proto::conn::NetlinkSocket;
+    use crate::uapi;
+    use std::time::Duration;
+
+    #[test]
+    fn test_sockopts() {
+        let sock = NetlinkSocket::new(uapi::NETLINK_ROUTE as i32).unwrap();
+
+        sock.set_strict_checking(true).unwrap();
+        assert!(sock.strict_checking().unwrap());
+        sock.set_strict_checking(false).unwrap();
+        assert!(!sock.strict_checking().unwrap());
+
+        sock.set_pktinfo(true).unwrap();
+        assert!(sock.pktinfo().unwrap());
+
+        sock.set_recv_buffer_size(100000).unwrap();
+        assert!(sock.recv_buffer_size().unwrap() >= 100000);
+
+        assert_eq!(sock.read_timeout().unwrap(), None);
+        sock.set_read_timeout(Some(Duration::from_millis(1500)))
+            .unwrap();
+        assert_eq!(
+            sock.read_timeout().unwrap(),
+            Some(Duration::from_millis(1500))
+        );
+    }
+}
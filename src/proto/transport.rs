@@ -0,0 +1,264 @@
+use super::conn::{check_response, complete, is_interrupted, DumpMode, Response};
+use super::{NetlinkMessage, NetlinkMessageRef, NlmFlags};
+use crate::{NetlinkError, Result, Serializable};
+use std::collections::VecDeque;
+
+/// Transport is what requests are executed over. Normally that is a
+/// NetlinkSocket, but anything that can pass datagrams back and forth will
+/// do, e.g. a MockTransport in tests.
+///
+/// Implementations only deal in datagrams; matching responses to requests,
+/// acks, errors and dumps are handled by the provided methods.
+pub trait Transport {
+    /// Sends a single datagram.
+    fn send(&mut self, buf: &[u8]) -> Result<()>;
+
+    /// Receives a single datagram, blocking until one arrives.
+    fn recv(&mut self) -> Result<Vec<NetlinkMessage>>;
+
+    /// The port id that responses are addressed to.
+    fn pid(&self) -> Result<u32>;
+
+    /// Allocates a sequence number for a request.
+    fn next_seq(&mut self) -> u32;
+
+    /// Takes a message that isn't a response to the request being executed.
+    /// Returns false if no such messages are expected, which fails the
+    /// request. The default expects none.
+    fn unsolicited(&mut self, _msg: NetlinkMessage) -> bool {
+        false
+    }
+
+    /// Sends a request, returning a blocking iterator over its responses.
+    /// Unlike `exec`, responses are handed out as they arrive, so a large
+    /// dump doesn't have to fit in memory.
+    ///
+    /// The iterator borrows the transport until it is dropped. If it is
+    /// dropped early, the rest of the responses are read and discarded, so
+    /// that the transport is ready for the next request.
+    fn request(&mut self, request: &mut NetlinkMessage) -> Result<Responses<'_, Self>> {
+        request.header.seq = self.next_seq();
+
+        let b = request.to_bytes();
+        self.send(&b)?;
+        let pid = self.pid()?;
+
+        Ok(Responses {
+            sock: self,
+            pid,
+            seq: request.header.seq,
+            ack: request.header.nlm_flags().contains(NlmFlags::ACK),
+            request: b,
+            queue: VecDeque::new(),
+            done: false,
+            interrupted: false,
+        })
+    }
+
+    /// Sends a request and collects all of its responses. If `resp_typ` is
+    /// set, responses of other types are skipped.
+    ///
+    /// If the request was a dump that the kernel flagged as interrupted,
+    /// this fails with NetlinkError::DumpInterrupted. See `exec_dump`.
+    fn exec(
+        &mut self,
+        request: &mut NetlinkMessage,
+        resp_typ: Option<u16>,
+    ) -> Result<Vec<NetlinkMessage>> {
+        let mut out = vec![];
+        let mut resps = self.request(request)?;
+        for resp in resps.by_ref() {
+            let resp = resp?;
+            // If we know which type of message we want, skip those
+            // that don't match
+            if resp_typ.is_none() || resp_typ == Some(resp.header.typ) {
+                out.push(resp);
+            }
+        }
+        complete(out, resps.interrupted())
+    }
+
+    /// Sends a request and hands each of its responses to `f`, as a view
+    /// into the buffer it was received into. This is the cheapest way to
+    /// walk a large dump: a NetlinkSocket doesn't copy the responses at all.
+    ///
+    /// If `f` fails, the rest of the responses are read and discarded, and
+    /// its error is returned. An interrupted dump ends with
+    /// NetlinkError::DumpInterrupted (with no messages in it), after all of
+    /// the responses have been handed out.
+    fn request_with(
+        &mut self,
+        request: &mut NetlinkMessage,
+        f: &mut dyn FnMut(NetlinkMessageRef<'_>) -> Result<()>,
+    ) -> Result<()> {
+        let mut res = Ok(());
+        let mut resps = self.request(request)?;
+        for resp in resps.by_ref() {
+            let resp = resp?;
+            if res.is_ok() {
+                res = f(NetlinkMessageRef::from(&resp));
+            }
+        }
+        res?;
+        complete(vec![], resps.interrupted()).map(|_| ())
+    }
+
+    /// Executes a dump request like `exec`, handling interrupted dumps
+    /// according to `mode`.
+    fn exec_dump(
+        &mut self,
+        request: &mut NetlinkMessage,
+        resp_typ: Option<u16>,
+        mode: DumpMode,
+    ) -> Result<Vec<NetlinkMessage>> {
+        dump_with(self, request, mode, |resp| {
+            if resp_typ.is_none() || resp_typ == Some(resp.header.typ) {
+                return Ok(Some(resp.to_message()));
+            }
+            Ok(None)
+        })
+    }
+}
+
+/// Executes a dump request with `Transport::request_with`, collecting what
+/// `f` makes of each response; responses it returns None for are skipped.
+/// Interrupted dumps are handled according to `mode`, starting over with
+/// an empty result on each retry.
+///
+/// If the dump is still interrupted in the end, the
+/// NetlinkError::DumpInterrupted holds the responses that `f` accepted on
+/// the last attempt.
+pub fn dump_with<T, R, F>(
+    sock: &mut T,
+    request: &mut NetlinkMessage,
+    mode: DumpMode,
+    mut f: F,
+) -> Result<Vec<R>>
+where
+    T: Transport + ?Sized,
+    F: FnMut(NetlinkMessageRef<'_>) -> Result<Option<R>>,
+{
+    let mut retries = match mode {
+        DumpMode::Retry(n) => n,
+        DumpMode::Partial => 0,
+    };
+    loop {
+        // Only the last attempt needs copies of the messages.
+        let last = retries == 0;
+        let mut out = vec![];
+        let mut partial = vec![];
+        let res = sock.request_with(request, &mut |resp| {
+            if let Some(r) = f(resp.clone())? {
+                if last {
+                    partial.push(resp.to_message());
+                }
+                out.push(r);
+            }
+            Ok(())
+        });
+        match res {
+            Ok(()) => return Ok(out),
+            Err(NetlinkError::DumpInterrupted(_)) if !last => retries -= 1,
+            Err(NetlinkError::DumpInterrupted(_)) => {
+                return Err(NetlinkError::DumpInterrupted(partial))
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Responses is a blocking iterator over the responses to a single request,
+/// returned by `Transport::request`. It ends once the request is complete,
+/// or after an error.
+pub struct Responses<'a, T: Transport + ?Sized> {
+    sock: &'a mut T,
+    pid: u32,
+    seq: u32,
+    ack: bool,
+    request: Vec<u8>,
+
+    /// The rest of the datagram being handed out.
+    queue: VecDeque<NetlinkMessage>,
+    done: bool,
+    interrupted: bool,
+}
+
+impl<'a, T: Transport + ?Sized> Responses<'a, T> {
+    /// The sequence number assigned to the request.
+    pub fn seq(&self) -> u32 {
+        self.seq
+    }
+
+    /// Whether the kernel flagged the dump as interrupted (NLM_F_DUMP_INTR),
+    /// meaning the responses may be inconsistent. Only final once the
+    /// iterator has ended.
+    pub fn interrupted(&self) -> bool {
+        self.interrupted
+    }
+
+    fn next_inner(&mut self) -> Result<Option<NetlinkMessage>> {
+        loop {
+            let resp = match self.queue.pop_front() {
+                Some(resp) => resp,
+                None => {
+                    self.queue.extend(self.sock.recv()?);
+                    continue;
+                }
+            };
+
+            // Validate response: seq no and port id match. Anything else
+            // had better be a notification.
+            let wrong_seq = resp.header.seq != self.seq;
+            let wrong_pid = resp.header.pid != self.pid;
+            if wrong_seq || wrong_pid {
+                if self.sock.unsolicited(resp) {
+                    continue;
+                }
+                if wrong_seq {
+                    // Use a SharedNetlinkSocket to share a socket
+                    return Err(NetlinkError::Protocol("Incorrect seq number"));
+                }
+                return Err(NetlinkError::Protocol("Got incorrect responding port ID."));
+            }
+
+            self.interrupted |= is_interrupted(&resp.header);
+            return match check_response(resp, &self.request, self.ack)? {
+                Response::Done => Ok(None),
+                Response::Data(resp, last) => {
+                    self.done = last;
+                    Ok(Some(resp))
+                }
+            };
+        }
+    }
+}
+
+impl<'a, T: Transport + ?Sized> Iterator for Responses<'a, T> {
+    type Item = Result<NetlinkMessage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let res = self.next_inner();
+        match res {
+            Ok(Some(_)) => {}
+            _ => self.done = true,
+        }
+        res.transpose()
+    }
+}
+
+impl<'a, T: Transport + ?Sized> Drop for Responses<'a, T> {
+    fn drop(&mut self) {
+        // Read the rest of the responses, so they aren't mistaken for the
+        // responses to the next request.
+        while self.next().is_some() {}
+
+        // Anything else in the datagram is someone else's.
+        for msg in self.queue.drain(..) {
+            self.sock.unsolicited(msg);
+        }
+    }
+}
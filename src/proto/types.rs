@@ -0,0 +1,74 @@
+//! Typed versions of the netlink header's message type and flags, for the
+//! values that mean the same thing in every netlink family.
+use crate::uapi;
+use bitflags::bitflags;
+
+typed_consts! {
+    /// A control message type, NLMSG_*. Family-specific types start at
+    /// NLMSG_MIN_TYPE; see e.g. `type_route::RtmType`.
+    pub struct NlmsgType(u16): "NLMSG_" {
+        NOOP = uapi::NLMSG_NOOP;
+        ERROR = uapi::NLMSG_ERROR;
+        DONE = uapi::NLMSG_DONE;
+        OVERRUN = uapi::NLMSG_OVERRUN;
+    }
+}
+
+bitflags! {
+    /// The flags in a netlink header, NLM_F_*. The meaning of the upper
+    /// byte depends on the kind of request, so several of the constants
+    /// share a value.
+    pub struct NlmFlags: u16 {
+        /// It is a request message.
+        const REQUEST = uapi::NLM_F_REQUEST as u16;
+        /// Multipart message, terminated by NLMSG_DONE.
+        const MULTI = uapi::NLM_F_MULTI as u16;
+        /// Reply with an ack, with zero or an error code.
+        const ACK = uapi::NLM_F_ACK as u16;
+        /// Echo this request.
+        const ECHO = uapi::NLM_F_ECHO as u16;
+        /// The dump was inconsistent due to a change while it ran.
+        const DUMP_INTR = uapi::NLM_F_DUMP_INTR as u16;
+        /// The dump was filtered as requested.
+        const DUMP_FILTERED = uapi::NLM_F_DUMP_FILTERED as u16;
+
+        // for GET requests
+        /// Return the complete table instead of a single entry.
+        const ROOT = uapi::NLM_F_ROOT as u16;
+        /// Return all entries matching the criteria in the request.
+        const MATCH = uapi::NLM_F_MATCH as u16;
+        /// Return an atomic snapshot of the table.
+        const ATOMIC = uapi::NLM_F_ATOMIC as u16;
+        /// ROOT | MATCH, for dumps.
+        const DUMP = uapi::NLM_F_DUMP as u16;
+
+        // for NEW requests
+        /// Replace the existing object.
+        const REPLACE = uapi::NLM_F_REPLACE as u16;
+        /// Don't touch the object if it already exists.
+        const EXCL = uapi::NLM_F_EXCL as u16;
+        /// Create the object if it doesn't exist.
+        const CREATE = uapi::NLM_F_CREATE as u16;
+        /// Add to the end of the object list.
+        const APPEND = uapi::NLM_F_APPEND as u16;
+
+        // for DELETE requests
+        /// Do not delete recursively.
+        const NONREC = uapi::NLM_F_NONREC as u16;
+        /// Delete multiple objects.
+        // NLM_F_BULK, which the 5.15 headers don't have.
+        const BULK = 0x200;
+
+        // for acks
+        /// The request was capped, and is not in the error message.
+        const CAPPED = uapi::NLM_F_CAPPED as u16;
+        /// Extended ack TLVs are included.
+        const ACK_TLVS = uapi::NLM_F_ACK_TLVS as u16;
+    }
+}
+
+impl From<NlmFlags> for u16 {
+    fn from(f: NlmFlags) -> u16 {
+        f.bits()
+    }
+}
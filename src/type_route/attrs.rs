@@ -0,0 +1,262 @@
+use super::{RtAttr, RtAttrRef};
+use crate::{Result, Serializable};
+use std::ffi::CString;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// AttrSet is a type that maps to a list of attributes, such as the
+/// kind-specific IFLA_INFO_DATA of a link. It is normally derived:
+///
+/// ```ignore
+/// #[derive(AttrSet, Default, Debug)]
+/// struct Vlan {
+///     #[nla(type = IflaVlan::ID)]
+///     vlan_id: Option<u16>,
+/// }
+/// ```
+///
+/// See the netlink-derive crate for the options.
+pub trait AttrSet: Sized {
+    /// Parses a list of attributes, e.g. `RtAttrRef::nested()`.
+    fn from_attrs<'a, I>(attrs: I) -> Result<Self>
+    where
+        I: Iterator<Item = Result<RtAttrRef<'a>>>;
+
+    /// Serializes to a list of attributes, e.g. for `RtAttr::new_nested`.
+    fn to_attrs(&self) -> Vec<RtAttr>;
+
+    /// Parses a buffer of attributes.
+    fn from_bytes(v: &[u8]) -> Result<Self> {
+        Self::from_attrs(RtAttrRef::iter(v))
+    }
+
+    /// Serializes to a buffer of attributes, as `from_bytes` parses.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for attr in self.to_attrs() {
+            out.append(&mut attr.to_bytes());
+            out.resize(crate::util::align(out.len()), 0);
+        }
+        out
+    }
+}
+
+/// Lets an attribute set contain itself, as some do.
+impl<T: AttrSet> AttrSet for Box<T> {
+    fn from_attrs<'a, I>(attrs: I) -> Result<Self>
+    where
+        I: Iterator<Item = Result<RtAttrRef<'a>>>,
+    {
+        Ok(Box::new(T::from_attrs(attrs)?))
+    }
+
+    fn to_attrs(&self) -> Vec<RtAttr> {
+        (**self).to_attrs()
+    }
+}
+
+/// AttrValue is a type that can be held by a single attribute.
+pub trait AttrValue: Sized {
+    fn from_attr(attr: &RtAttrRef) -> Result<Self>;
+    fn to_attr(&self, typ: u16) -> RtAttr;
+}
+
+/// Be holds an integer that is sent in network byte order, with
+/// NLA_F_NET_BYTEORDER set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Be<T>(pub T);
+
+macro_rules! attr_value {
+    ($($t:ty: $get:ident, $new:ident;)*) => {
+        $(
+            impl AttrValue for $t {
+                fn from_attr(attr: &RtAttrRef) -> Result<Self> {
+                    attr.$get()
+                }
+
+                fn to_attr(&self, typ: u16) -> RtAttr {
+                    RtAttr::$new(typ, *self)
+                }
+            }
+        )*
+    };
+}
+
+attr_value! {
+    u8: as_u8, new_u8;
+    u16: as_u16, new_u16;
+    u32: as_u32, new_u32;
+    u64: as_u64, new_u64;
+    i32: as_i32, new_i32;
+    Ipv4Addr: as_ipv4, new_ipv4;
+    Ipv6Addr: as_ipv6, new_ipv6;
+    IpAddr: as_ip, new_ip;
+    [u8; 6]: as_mac, new_mac;
+}
+
+macro_rules! attr_value_be {
+    ($($t:ty: $get:ident, $new:ident;)*) => {
+        $(
+            impl AttrValue for Be<$t> {
+                fn from_attr(attr: &RtAttrRef) -> Result<Self> {
+                    Ok(Be(attr.$get()?))
+                }
+
+                fn to_attr(&self, typ: u16) -> RtAttr {
+                    RtAttr::$new(typ, self.0)
+                }
+            }
+        )*
+    };
+}
+
+attr_value_be! {
+    u16: as_be16, new_be16;
+    u32: as_be32, new_be32;
+    u64: as_be64, new_be64;
+}
+
+macro_rules! attr_value_signed {
+    ($($t:ty: $u:ty, $get:ident, $new:ident;)*) => {
+        $(
+            impl AttrValue for $t {
+                fn from_attr(attr: &RtAttrRef) -> Result<Self> {
+                    Ok(attr.$get()? as $t)
+                }
+
+                fn to_attr(&self, typ: u16) -> RtAttr {
+                    RtAttr::$new(typ, *self as $u)
+                }
+            }
+        )*
+    };
+}
+
+attr_value_signed! {
+    i8: u8, as_u8, new_u8;
+    i16: u16, as_u16, new_u16;
+    i64: u64, as_u64, new_u64;
+}
+
+/// A bool is sent as a u8, which is how most boolean options are passed.
+impl AttrValue for bool {
+    fn from_attr(attr: &RtAttrRef) -> Result<Self> {
+        attr.as_bool()
+    }
+
+    fn to_attr(&self, typ: u16) -> RtAttr {
+        RtAttr::new_u8(typ, *self as u8)
+    }
+}
+
+/// A flag attribute, which has no data; all that matters is whether it's
+/// there.
+impl AttrValue for () {
+    fn from_attr(_attr: &RtAttrRef) -> Result<Self> {
+        Ok(())
+    }
+
+    fn to_attr(&self, typ: u16) -> RtAttr {
+        RtAttr::new(typ, vec![])
+    }
+}
+
+impl AttrValue for String {
+    fn from_attr(attr: &RtAttrRef) -> Result<Self> {
+        Ok(attr.as_str()?.to_owned())
+    }
+
+    fn to_attr(&self, typ: u16) -> RtAttr {
+        RtAttr::new_str(typ, self)
+    }
+}
+
+impl AttrValue for CString {
+    fn from_attr(attr: &RtAttrRef) -> Result<Self> {
+        attr.to_cstring()
+    }
+
+    fn to_attr(&self, typ: u16) -> RtAttr {
+        RtAttr::new_cstr(typ, self)
+    }
+}
+
+/// Raw bytes, for anything without a better type.
+impl AttrValue for Vec<u8> {
+    fn from_attr(attr: &RtAttrRef) -> Result<Self> {
+        Ok(attr.data.to_vec())
+    }
+
+    fn to_attr(&self, typ: u16) -> RtAttr {
+        RtAttr::new(typ, self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Be;
+    use crate::type_route::{AttrSet, RtAttr};
+    use crate::{NetlinkError, Serializable};
+    use std::net::Ipv4Addr;
+
+    #[derive(AttrSet, Debug, Default, PartialEq)]
+    struct Inner {
+        #[nla(type = 1)]
+        id: u32,
+        #[nla(type = 2)]
+        port: Option<Be<u16>>,
+    }
+
+    #[derive(AttrSet, Debug, Default, PartialEq)]
+    struct Outer {
+        #[nla(type = 1)]
+        name: Option<String>,
+        #[nla(type = 2, repeated)]
+        addrs: Vec<Ipv4Addr>,
+        #[nla(type = 3, nested)]
+        inner: Option<Inner>,
+        #[nla(type = 4)]
+        up: Option<bool>,
+        #[nla(unknown)]
+        other: Vec<RtAttr>,
+
+        /// Not an attribute
+        cookie: u32,
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let o = Outer {
+            name: Some("eth0".to_string()),
+            addrs: vec![Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2)],
+            inner: Some(Inner {
+                id: 7,
+                port: Some(Be(80)),
+            }),
+            up: None,
+            other: vec![RtAttr::new_u32(99u16, 5)],
+            cookie: 0,
+        };
+
+        let b = o.to_bytes();
+        let attrs = RtAttr::from_bytes(&b).unwrap();
+        let typs: Vec<u16> = attrs.iter().map(|a| a.get_typ()).collect();
+        assert_eq!(typs, vec![1, 2, 2, 3, 99]);
+        assert!(attrs[3].is_nested());
+
+        assert_eq!(Outer::from_bytes(&b).unwrap(), o);
+    }
+
+    #[test]
+    fn test_errors() {
+        // Inner.id is required
+        let b = RtAttr::new_nested(3u16, vec![]).to_bytes();
+        match Outer::from_bytes(&b) {
+            Err(NetlinkError::Malformed { attr, .. }) => assert_eq!(attr, Some(1)),
+            r => panic!("unexpected result {:?}", r),
+        }
+
+        // a bad value is an error, not a panic
+        let b = RtAttr::new(1u16, vec![1, 2]).to_bytes();
+        assert!(Inner::from_bytes(&b).is_err());
+    }
+}
@@ -0,0 +1,715 @@
+//! A symbolic decoder for rtnetlink messages, used by the Display impl of
+//! NetlinkMessage. It knows the names of message types, flags and the common
+//! attributes; anything it doesn't know is shown as hex.
+use super::{IfInfoMsg, RtAttr};
+use crate::hl::iface::IfFlags;
+use crate::proto::{ErrorMessage, NetlinkMessage};
+use crate::uapi;
+use crate::util::{u16_at, u32_at};
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// (value, name) pairs, named after the uapi constants.
+macro_rules! names {
+    ($($c:ident),* $(,)?) => {
+        &[$((uapi::$c as u32, stringify!($c))),*]
+    };
+}
+
+/// Attribute descriptions, named after the uapi constants.
+macro_rules! attrs {
+    ($($c:ident: $kind:expr),* $(,)?) => {
+        &[$(AttrDesc {
+            typ: uapi::$c as u16,
+            name: stringify!($c),
+            kind: $kind,
+        }),*]
+    };
+}
+
+/// How to show the payload of an attribute.
+#[derive(Clone, Copy)]
+enum Kind {
+    U8,
+    U16,
+    U32,
+    I32,
+    /// A u16 in network byte order
+    Be16,
+    /// A NUL-terminated string
+    Str,
+    /// A hardware address
+    Mac,
+    /// An IPv4 or IPv6 address
+    Addr,
+    /// More attributes, named according to the table
+    Nested(&'static [AttrDesc]),
+    Binary,
+}
+
+use self::Kind::*;
+
+struct AttrDesc {
+    typ: u16,
+    name: &'static str,
+    kind: Kind,
+}
+
+const MSG_TYPES: &[(u32, &str)] = names![
+    NLMSG_NOOP,
+    NLMSG_ERROR,
+    NLMSG_DONE,
+    NLMSG_OVERRUN,
+    RTM_NEWLINK,
+    RTM_DELLINK,
+    RTM_GETLINK,
+    RTM_SETLINK,
+    RTM_NEWADDR,
+    RTM_DELADDR,
+    RTM_GETADDR,
+    RTM_NEWROUTE,
+    RTM_DELROUTE,
+    RTM_GETROUTE,
+    RTM_NEWNEIGH,
+    RTM_DELNEIGH,
+    RTM_GETNEIGH,
+    RTM_NEWRULE,
+    RTM_DELRULE,
+    RTM_GETRULE,
+    RTM_NEWQDISC,
+    RTM_DELQDISC,
+    RTM_GETQDISC,
+    RTM_NEWTCLASS,
+    RTM_DELTCLASS,
+    RTM_GETTCLASS,
+    RTM_NEWTFILTER,
+    RTM_DELTFILTER,
+    RTM_GETTFILTER,
+    RTM_NEWACTION,
+    RTM_DELACTION,
+    RTM_GETACTION,
+    RTM_NEWPREFIX,
+    RTM_GETMULTICAST,
+    RTM_GETANYCAST,
+    RTM_NEWNEIGHTBL,
+    RTM_GETNEIGHTBL,
+    RTM_SETNEIGHTBL,
+    RTM_NEWNDUSEROPT,
+    RTM_NEWADDRLABEL,
+    RTM_DELADDRLABEL,
+    RTM_GETADDRLABEL,
+    RTM_GETDCB,
+    RTM_SETDCB,
+    RTM_NEWNETCONF,
+    RTM_DELNETCONF,
+    RTM_GETNETCONF,
+    RTM_NEWMDB,
+    RTM_DELMDB,
+    RTM_GETMDB,
+    RTM_NEWNSID,
+    RTM_DELNSID,
+    RTM_GETNSID,
+    RTM_NEWSTATS,
+    RTM_GETSTATS,
+    RTM_NEWCACHEREPORT,
+    RTM_NEWCHAIN,
+    RTM_DELCHAIN,
+    RTM_GETCHAIN,
+    RTM_NEWNEXTHOP,
+    RTM_DELNEXTHOP,
+    RTM_GETNEXTHOP,
+    RTM_NEWLINKPROP,
+    RTM_DELLINKPROP,
+    RTM_GETLINKPROP,
+    RTM_NEWVLAN,
+    RTM_DELVLAN,
+    RTM_GETVLAN,
+];
+
+/// Flags that mean the same for every message.
+const COMMON_FLAGS: &[(u32, &str)] = names![
+    NLM_F_REQUEST,
+    NLM_F_MULTI,
+    NLM_F_ACK,
+    NLM_F_ECHO,
+    NLM_F_DUMP_INTR,
+    NLM_F_DUMP_FILTERED,
+];
+
+const GET_FLAGS: &[(u32, &str)] = names![NLM_F_ROOT, NLM_F_MATCH, NLM_F_ATOMIC];
+const NEW_FLAGS: &[(u32, &str)] = names![NLM_F_REPLACE, NLM_F_EXCL, NLM_F_CREATE, NLM_F_APPEND];
+// NLM_F_BULK is newer than the 5.15 headers, so it is written out.
+const DEL_FLAGS: &[(u32, &str)] = &[
+    (uapi::NLM_F_NONREC, "NLM_F_NONREC"),
+    (0x200, "NLM_F_BULK"),
+];
+const ACK_FLAGS: &[(u32, &str)] = names![NLM_F_CAPPED, NLM_F_ACK_TLVS];
+
+const FAMILIES: &[(u32, &str)] = &[
+    (libc::AF_UNSPEC as u32, "AF_UNSPEC"),
+    (libc::AF_INET as u32, "AF_INET"),
+    (libc::AF_INET6 as u32, "AF_INET6"),
+    (libc::AF_BRIDGE as u32, "AF_BRIDGE"),
+    (libc::AF_PACKET as u32, "AF_PACKET"),
+    (libc::AF_MPLS as u32, "AF_MPLS"),
+];
+
+const IFA_FLAGS: &[(u32, &str)] = names![
+    IFA_F_SECONDARY,
+    IFA_F_NODAD,
+    IFA_F_OPTIMISTIC,
+    IFA_F_DADFAILED,
+    IFA_F_HOMEADDRESS,
+    IFA_F_DEPRECATED,
+    IFA_F_TENTATIVE,
+    IFA_F_PERMANENT,
+];
+
+const RT_SCOPES: &[(u32, &str)] = names![
+    RT_SCOPE_UNIVERSE,
+    RT_SCOPE_SITE,
+    RT_SCOPE_LINK,
+    RT_SCOPE_HOST,
+    RT_SCOPE_NOWHERE,
+];
+
+const RT_TABLES: &[(u32, &str)] = names![
+    RT_TABLE_UNSPEC,
+    RT_TABLE_COMPAT,
+    RT_TABLE_DEFAULT,
+    RT_TABLE_MAIN,
+    RT_TABLE_LOCAL,
+];
+
+const RT_PROTOS: &[(u32, &str)] = names![
+    RTPROT_UNSPEC,
+    RTPROT_REDIRECT,
+    RTPROT_KERNEL,
+    RTPROT_BOOT,
+    RTPROT_STATIC,
+    RTPROT_RA,
+    RTPROT_DHCP,
+];
+
+const RT_TYPES: &[(u32, &str)] = names![
+    RTN_UNSPEC,
+    RTN_UNICAST,
+    RTN_LOCAL,
+    RTN_BROADCAST,
+    RTN_ANYCAST,
+    RTN_MULTICAST,
+    RTN_BLACKHOLE,
+    RTN_UNREACHABLE,
+    RTN_PROHIBIT,
+    RTN_THROW,
+    RTN_NAT,
+    RTN_XRESOLVE,
+];
+
+const NUD_STATES: &[(u32, &str)] = names![
+    NUD_INCOMPLETE,
+    NUD_REACHABLE,
+    NUD_STALE,
+    NUD_DELAY,
+    NUD_PROBE,
+    NUD_FAILED,
+    NUD_NOARP,
+    NUD_PERMANENT,
+];
+
+const IFLA_INFO: &[AttrDesc] = attrs![
+    IFLA_INFO_KIND: Str,
+    IFLA_INFO_DATA: Nested(&[]),
+    IFLA_INFO_XSTATS: Binary,
+    IFLA_INFO_SLAVE_KIND: Str,
+    IFLA_INFO_SLAVE_DATA: Nested(&[]),
+];
+
+const IFLA_PROP: &[AttrDesc] = attrs![IFLA_ALT_IFNAME: Str];
+
+const IFLA: &[AttrDesc] = attrs![
+    IFLA_ADDRESS: Mac,
+    IFLA_BROADCAST: Mac,
+    IFLA_IFNAME: Str,
+    IFLA_MTU: U32,
+    IFLA_LINK: U32,
+    IFLA_QDISC: Str,
+    IFLA_STATS: Binary,
+    IFLA_COST: Binary,
+    IFLA_PRIORITY: Binary,
+    IFLA_MASTER: U32,
+    IFLA_WIRELESS: Binary,
+    IFLA_PROTINFO: Nested(&[]),
+    IFLA_TXQLEN: U32,
+    IFLA_MAP: Binary,
+    IFLA_WEIGHT: U32,
+    IFLA_OPERSTATE: U8,
+    IFLA_LINKMODE: U8,
+    IFLA_LINKINFO: Nested(IFLA_INFO),
+    IFLA_NET_NS_PID: U32,
+    IFLA_IFALIAS: Str,
+    IFLA_NUM_VF: U32,
+    IFLA_VFINFO_LIST: Nested(&[]),
+    IFLA_STATS64: Binary,
+    IFLA_VF_PORTS: Nested(&[]),
+    IFLA_PORT_SELF: Nested(&[]),
+    IFLA_AF_SPEC: Nested(&[]),
+    IFLA_GROUP: U32,
+    IFLA_NET_NS_FD: U32,
+    IFLA_EXT_MASK: U32,
+    IFLA_PROMISCUITY: U32,
+    IFLA_NUM_TX_QUEUES: U32,
+    IFLA_NUM_RX_QUEUES: U32,
+    IFLA_CARRIER: U8,
+    IFLA_PHYS_PORT_ID: Binary,
+    IFLA_CARRIER_CHANGES: U32,
+    IFLA_PHYS_SWITCH_ID: Binary,
+    IFLA_LINK_NETNSID: I32,
+    IFLA_PHYS_PORT_NAME: Str,
+    IFLA_PROTO_DOWN: U8,
+    IFLA_GSO_MAX_SEGS: U32,
+    IFLA_GSO_MAX_SIZE: U32,
+    IFLA_PAD: Binary,
+    IFLA_XDP: Nested(&[]),
+    IFLA_EVENT: U32,
+    IFLA_NEW_NETNSID: I32,
+    IFLA_IF_NETNSID: I32,
+    IFLA_CARRIER_UP_COUNT: U32,
+    IFLA_CARRIER_DOWN_COUNT: U32,
+    IFLA_NEW_IFINDEX: I32,
+    IFLA_MIN_MTU: U32,
+    IFLA_MAX_MTU: U32,
+    IFLA_PROP_LIST: Nested(IFLA_PROP),
+    IFLA_ALT_IFNAME: Str,
+    IFLA_PERM_ADDRESS: Mac,
+];
+
+const IFA: &[AttrDesc] = attrs![
+    IFA_ADDRESS: Addr,
+    IFA_LOCAL: Addr,
+    IFA_LABEL: Str,
+    IFA_BROADCAST: Addr,
+    IFA_ANYCAST: Addr,
+    IFA_CACHEINFO: Binary,
+    IFA_MULTICAST: Addr,
+    IFA_FLAGS: U32,
+    IFA_RT_PRIORITY: U32,
+    IFA_TARGET_NETNSID: I32,
+];
+
+const RTA: &[AttrDesc] = attrs![
+    RTA_DST: Addr,
+    RTA_SRC: Addr,
+    RTA_IIF: U32,
+    RTA_OIF: U32,
+    RTA_GATEWAY: Addr,
+    RTA_PRIORITY: U32,
+    RTA_PREFSRC: Addr,
+    RTA_METRICS: Nested(&[]),
+    RTA_MULTIPATH: Binary,
+    RTA_FLOW: U32,
+    RTA_CACHEINFO: Binary,
+    RTA_TABLE: U32,
+    RTA_MARK: U32,
+    RTA_MFC_STATS: Binary,
+    RTA_VIA: Binary,
+    RTA_NEWDST: Addr,
+    RTA_PREF: U8,
+    RTA_ENCAP_TYPE: U16,
+    RTA_ENCAP: Nested(&[]),
+    RTA_EXPIRES: U32,
+    RTA_PAD: Binary,
+    RTA_UID: U32,
+    RTA_TTL_PROPAGATE: U8,
+    RTA_IP_PROTO: U8,
+    RTA_SPORT: Be16,
+    RTA_DPORT: Be16,
+    RTA_NH_ID: U32,
+];
+
+const NDA: &[AttrDesc] = attrs![
+    NDA_DST: Addr,
+    NDA_LLADDR: Mac,
+    NDA_CACHEINFO: Binary,
+    NDA_PROBES: U32,
+    NDA_VLAN: U16,
+    NDA_PORT: Be16,
+    NDA_VNI: U32,
+    NDA_IFINDEX: U32,
+    NDA_MASTER: U32,
+    NDA_LINK_NETNSID: I32,
+    NDA_SRC_VNI: U32,
+];
+
+/// The fixed header and attributes of a family of rtnetlink messages.
+struct Family {
+    header_len: usize,
+    header: fn(&[u8]) -> String,
+    attrs: &'static [AttrDesc],
+}
+
+fn family(typ: u16) -> Option<Family> {
+    let typ = typ as u32;
+    let (header_len, header, attrs): (usize, fn(&[u8]) -> String, _) = match typ {
+        uapi::RTM_NEWLINK..=uapi::RTM_SETLINK => (IfInfoMsg::size(), ifinfomsg, IFLA),
+        uapi::RTM_NEWADDR..=uapi::RTM_GETADDR => (8, ifaddrmsg, IFA),
+        uapi::RTM_NEWROUTE..=uapi::RTM_GETROUTE => (12, rtmsg, RTA),
+        uapi::RTM_NEWNEIGH..=uapi::RTM_GETNEIGH => (12, ndmsg, NDA),
+        _ => return None,
+    };
+    Some(Family {
+        header_len,
+        header,
+        attrs,
+    })
+}
+
+fn name(table: &[(u32, &str)], val: u32) -> String {
+    match table.iter().find(|(v, _)| *v == val) {
+        Some((_, n)) => n.to_string(),
+        None => val.to_string(),
+    }
+}
+
+/// The names of the bits set in `bits`, joined by '|'. Bits without a name
+/// are shown in hex.
+fn flag_names(tables: &[&[(u32, &str)]], mut bits: u32) -> String {
+    let mut out = vec![];
+    for table in tables {
+        for (bit, name) in table.iter() {
+            if bits & bit != 0 {
+                out.push(name.to_string());
+                bits &= !bit;
+            }
+        }
+    }
+    if bits != 0 || out.is_empty() {
+        out.push(format!("{:#x}", bits));
+    }
+    out.join("|")
+}
+
+fn msg_type(typ: u16) -> String {
+    match MSG_TYPES.iter().find(|(v, _)| *v == typ as u32) {
+        Some((_, n)) => n.to_string(),
+        None => format!("type {}", typ),
+    }
+}
+
+fn msg_flags(typ: u16, flags: u16) -> String {
+    let typ = typ as u32;
+    let mut flags = flags as u32;
+    let mut tables = vec![COMMON_FLAGS];
+    let mut dump = false;
+
+    if typ == uapi::NLMSG_ERROR {
+        tables.push(ACK_FLAGS);
+    } else if typ >= uapi::RTM_BASE {
+        // Requests come in groups of four: new, del, get, set.
+        match (typ - uapi::RTM_BASE) % 4 {
+            0 => tables.push(NEW_FLAGS),
+            1 => tables.push(DEL_FLAGS),
+            2 => {
+                dump = flags & uapi::NLM_F_DUMP == uapi::NLM_F_DUMP;
+                if dump {
+                    flags &= !uapi::NLM_F_DUMP;
+                }
+                tables.push(GET_FLAGS);
+            }
+            _ => {}
+        }
+    }
+
+    let names = flag_names(&tables, flags);
+    match (dump, flags) {
+        (true, 0) => "NLM_F_DUMP".to_string(),
+        (true, _) => format!("NLM_F_DUMP|{}", names),
+        _ => names,
+    }
+}
+
+fn ifinfomsg(b: &[u8]) -> String {
+    let m = match IfInfoMsg::from_bytes(b) {
+        Ok(m) => m,
+        Err(_) => return format!("ifinfomsg {}", Hex(b)),
+    };
+    format!(
+        "ifinfomsg family={} type={} index={} flags={:?} change={:#x}",
+        name(FAMILIES, m.family as u32),
+        m.typ,
+        m.index,
+        IfFlags::from_bits_truncate(m.flags),
+        m.change
+    )
+}
+
+fn ifaddrmsg(b: &[u8]) -> String {
+    format!(
+        "ifaddrmsg family={} prefixlen={} flags={} scope={} index={}",
+        name(FAMILIES, b[0] as u32),
+        b[1],
+        flag_names(&[IFA_FLAGS], b[2] as u32),
+        name(RT_SCOPES, b[3] as u32),
+        u32_at(b, 4)
+    )
+}
+
+fn rtmsg(b: &[u8]) -> String {
+    format!(
+        "rtmsg family={} dst_len={} src_len={} tos={} table={} protocol={} scope={} type={} flags={:#x}",
+        name(FAMILIES, b[0] as u32),
+        b[1],
+        b[2],
+        b[3],
+        name(RT_TABLES, b[4] as u32),
+        name(RT_PROTOS, b[5] as u32),
+        name(RT_SCOPES, b[6] as u32),
+        name(RT_TYPES, b[7] as u32),
+        u32_at(b, 8)
+    )
+}
+
+fn ndmsg(b: &[u8]) -> String {
+    format!(
+        "ndmsg family={} index={} state={} flags={:#x} type={}",
+        name(FAMILIES, b[0] as u32),
+        u32_at(b, 4) as i32,
+        flag_names(&[NUD_STATES], u16_at(b, 8) as u32),
+        b[10],
+        name(RT_TYPES, b[11] as u32)
+    )
+}
+
+/// Bytes shown as space-separated hex.
+struct Hex<'a>(&'a [u8]);
+
+impl<'a> fmt::Display for Hex<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, b) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Starts a new line, indented to `depth`.
+fn newline(f: &mut fmt::Formatter, depth: usize) -> fmt::Result {
+    writeln!(f)?;
+    for _ in 0..depth {
+        write!(f, "    ")?;
+    }
+    Ok(())
+}
+
+fn value(kind: Kind, d: &[u8]) -> Option<String> {
+    let s = match (kind, d.len()) {
+        (U8, 1) => d[0].to_string(),
+        (U16, 2) => u16_at(d, 0).to_string(),
+        (Be16, 2) => u16::from_be_bytes([d[0], d[1]]).to_string(),
+        (U32, 4) => u32_at(d, 0).to_string(),
+        (I32, 4) => (u32_at(d, 0) as i32).to_string(),
+        (Str, _) => match d.split_last() {
+            Some((0, s)) => format!("{:?}", std::str::from_utf8(s).ok()?),
+            _ => return None,
+        },
+        (Mac, n) if n > 0 => {
+            let parts: Vec<String> = d.iter().map(|b| format!("{:02x}", b)).collect();
+            parts.join(":")
+        }
+        (Addr, 4) => Ipv4Addr::new(d[0], d[1], d[2], d[3]).to_string(),
+        (Addr, 16) => {
+            let mut a = [0; 16];
+            a.copy_from_slice(d);
+            Ipv6Addr::from(a).to_string()
+        }
+        _ => return None,
+    };
+    Some(s)
+}
+
+fn write_attrs(
+    f: &mut fmt::Formatter,
+    data: &[u8],
+    table: &[AttrDesc],
+    depth: usize,
+) -> fmt::Result {
+    let attrs = match RtAttr::from_bytes(data) {
+        Ok(attrs) => attrs,
+        Err(_) => {
+            newline(f, depth)?;
+            return write!(f, "malformed attributes: {}", Hex(data));
+        }
+    };
+
+    for attr in attrs {
+        let typ = attr.get_typ();
+        newline(f, depth)?;
+
+        let desc = match table.iter().find(|d| d.typ == typ) {
+            Some(desc) => desc,
+            None => {
+                write!(f, "type {}:", typ)?;
+                if !attr.data.is_empty() {
+                    write!(f, " {}", Hex(&attr.data))?;
+                }
+                continue;
+            }
+        };
+
+        write!(f, "{}:", desc.name)?;
+        if let Nested(inner) = desc.kind {
+            if RtAttr::from_bytes(&attr.data).is_ok() {
+                write_attrs(f, &attr.data, inner, depth + 1)?;
+                continue;
+            }
+        }
+        match value(desc.kind, &attr.data) {
+            Some(v) => write!(f, " {}", v)?,
+            None if attr.data.is_empty() => {}
+            None => write!(f, " {}", Hex(&attr.data))?,
+        }
+    }
+    Ok(())
+}
+
+/// Messages are shown as an indented tree: the netlink header, the family
+/// header, and then the attributes.
+impl fmt::Display for NetlinkMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let h = &self.header;
+        write!(
+            f,
+            "{} flags={} len={} seq={} pid={}",
+            msg_type(h.typ),
+            msg_flags(h.typ, h.flags),
+            h.len,
+            h.seq,
+            h.pid
+        )?;
+
+        let data = &self.data;
+        if data.is_empty() {
+            return Ok(());
+        }
+        match h.typ as u32 {
+            uapi::NLMSG_ERROR => {
+                newline(f, 1)?;
+                match ErrorMessage::from_message(self) {
+                    Ok(ref e) if e.is_ack() => write!(f, "ack for seq {}", e.seq),
+                    Ok(e) => write!(f, "error for seq {}: {}", e.seq, e),
+                    Err(_) => write!(f, "{}", Hex(data)),
+                }
+            }
+            uapi::NLMSG_DONE if data.len() >= 4 => {
+                newline(f, 1)?;
+                write!(f, "status={}", u32_at(data, 0) as i32)
+            }
+            _ => match family(h.typ) {
+                Some(fam) if data.len() >= fam.header_len => {
+                    newline(f, 1)?;
+                    write!(f, "{}", (fam.header)(&data[..fam.header_len]))?;
+                    let start = crate::util::align(fam.header_len);
+                    if start < data.len() {
+                        write_attrs(f, &data[start..], fam.attrs, 1)?;
+                    }
+                    Ok(())
+                }
+                _ => {
+                    newline(f, 1)?;
+                    write!(f, "{}", Hex(data))
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::proto::{NetlinkMessage, NlmFlags, NlmsgType};
+    use crate::type_route::{IfInfoMsg, Ifla, IflaInfo, RtAttr, Rta, RtmType};
+    use crate::Serializable;
+
+    #[test]
+    fn test_display_link() {
+        let mut msg = NetlinkMessage::new(
+            RtmType::NEWLINK,
+            NlmFlags::REQUEST | NlmFlags::CREATE | NlmFlags::EXCL,
+        );
+        msg.header.seq = 3;
+        let info = IfInfoMsg {
+            index: 7,
+            flags: 0x1,
+            ..Default::default()
+        };
+        msg.add_data(info.to_bytes());
+        msg.add_data(RtAttr::new_str(Ifla::IFNAME, "br0").to_bytes());
+        msg.add_data(RtAttr::new_u32(Ifla::MTU, 1500).to_bytes());
+        msg.add_data(RtAttr::new_mac(Ifla::ADDRESS, [2, 0, 0, 0, 0, 0xab]).to_bytes());
+        let li = RtAttr::new_nested(
+            Ifla::LINKINFO,
+            vec![RtAttr::new_str(IflaInfo::KIND, "bridge")],
+        );
+        msg.add_data(li.to_bytes());
+        msg.add_data(RtAttr::new(999u16, vec![0xde, 0xad]).to_bytes());
+
+        assert_eq!(
+            msg.to_string(),
+            "RTM_NEWLINK flags=NLM_F_REQUEST|NLM_F_EXCL|NLM_F_CREATE len=84 seq=3 pid=0
+    ifinfomsg family=AF_UNSPEC type=0 index=7 flags=UP change=0x0
+    IFLA_IFNAME: \"br0\"
+    IFLA_MTU: 1500
+    IFLA_ADDRESS: 02:00:00:00:00:ab
+    IFLA_LINKINFO:
+        IFLA_INFO_KIND: \"bridge\"
+    type 999: de ad"
+        );
+    }
+
+    #[test]
+    fn test_display_other() {
+        let mut msg = NetlinkMessage::new(RtmType::GETROUTE, NlmFlags::REQUEST | NlmFlags::DUMP);
+        msg.add_data(vec![
+            libc::AF_INET as u8,
+            24,
+            0,
+            0,
+            254,
+            2,
+            0,
+            1,
+            0,
+            0,
+            0,
+            0,
+        ]);
+        msg.add_data(RtAttr::new(Rta::DST, vec![10, 1, 2, 0]).to_bytes());
+        assert_eq!(
+            msg.to_string(),
+            "RTM_GETROUTE flags=NLM_F_DUMP|NLM_F_REQUEST len=36 seq=0 pid=0
+    rtmsg family=AF_INET dst_len=24 src_len=0 tos=0 table=RT_TABLE_MAIN protocol=RTPROT_KERNEL scope=RT_SCOPE_UNIVERSE type=RTN_UNICAST flags=0x0
+    RTA_DST: 10.1.2.0"
+        );
+
+        let mut msg = NetlinkMessage::new(NlmsgType::ERROR, NlmFlags::empty());
+        let mut data = (-libc::ENODEV).to_ne_bytes().to_vec();
+        data.extend(NetlinkMessage::new(16u16, 0u16).header.to_bytes());
+        msg.add_data(data);
+        assert_eq!(
+            msg.to_string(),
+            format!(
+                "NLMSG_ERROR flags=0x0 len=36 seq=0 pid=0\n    error for seq 0: {}",
+                std::io::Error::from_raw_os_error(libc::ENODEV)
+            )
+        );
+
+        let mut msg = NetlinkMessage::new(1234u16, 0x8000u16);
+        msg.add_data(vec![1, 2, 3]);
+        assert_eq!(
+            msg.to_string(),
+            "type 1234 flags=0x8000 len=20 seq=0 pid=0\n    01 02 03 00"
+        );
+    }
+}
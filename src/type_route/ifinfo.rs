@@ -1,5 +1,5 @@
-use std::io::{Error, ErrorKind, Result};
-use std::ptr;
+use crate::util::{u16_at, u32_at};
+use crate::{NetlinkError, Result};
 
 #[repr(C)]
 #[derive(Debug, Eq, Clone, Default)]
@@ -18,16 +18,21 @@ pub struct IfInfoMsg {
 impl IfInfoMsg {
     pub fn from_bytes(v: &[u8]) -> Result<IfInfoMsg> {
         if v.len() < IfInfoMsg::size() {
-            return Err(Error::new(
-                ErrorKind::UnexpectedEof,
-                "buffer too short for message",
-            ));
+            return Err(NetlinkError::Truncated {
+                what: "ifinfomsg",
+                offset: 0,
+                attr: None,
+            });
         }
 
-        let mem = v.to_owned();
-        let m: IfInfoMsg = unsafe { std::ptr::read(mem.as_ptr() as *mut IfInfoMsg) };
-
-        Ok(m)
+        Ok(IfInfoMsg {
+            family: v[0],
+            _pad: v[1],
+            typ: u16_at(v, 2),
+            index: u32_at(v, 4) as i32,
+            flags: u32_at(v, 8),
+            change: u32_at(v, 12),
+        })
     }
 
     pub fn size() -> usize {
@@ -48,10 +53,12 @@ impl std::cmp::PartialEq for IfInfoMsg {
 impl crate::Serializable for IfInfoMsg {
     fn to_bytes(&self) -> Vec<u8> {
         let mut out: Vec<u8> = Vec::with_capacity(IfInfoMsg::size());
-        unsafe {
-            ptr::copy_nonoverlapping(self, out.as_mut_ptr() as *mut IfInfoMsg, 1);
-            out.set_len(IfInfoMsg::size());
-        };
+        out.push(self.family);
+        out.push(self._pad);
+        out.extend_from_slice(&self.typ.to_ne_bytes());
+        out.extend_from_slice(&self.index.to_ne_bytes());
+        out.extend_from_slice(&self.flags.to_ne_bytes());
+        out.extend_from_slice(&self.change.to_ne_bytes());
         return out;
     }
 }
@@ -63,27 +70,26 @@ mod tests {
 
     #[test]
     fn test_from_bytes() {
-        let b = vec![
+        let mut b = vec![
             1, // family
             0, //padding
-            2, 0, //typ
-            3, 0, 0, 0, //index
-            4, 0, 0, 0, //flags
-            5, 0, 0, 0, // change
-            1, 2, 3, 4, // extra junk
         ];
+        b.extend(&2u16.to_ne_bytes()); //typ
+        b.extend(&(-3i32).to_ne_bytes()); //index
+        b.extend(&4u32.to_ne_bytes()); //flags
+        b.extend(&5u32.to_ne_bytes()); // change
+        b.extend(&[1, 2, 3, 4]); // extra junk
 
         let msg = IfInfoMsg::from_bytes(&b).unwrap();
-        assert_eq!(
-            msg,
-            IfInfoMsg {
-                family: 1,
-                typ: 2,
-                index: 3,
-                flags: 4,
-                change: 5,
-                ..Default::default()
-            }
-        );
+        let expected = IfInfoMsg {
+            family: 1,
+            typ: 2,
+            index: -3,
+            flags: 4,
+            change: 5,
+            ..Default::default()
+        };
+        assert_eq!(msg, expected);
+        assert_eq!(expected.to_bytes(), &b[0..IfInfoMsg::size()]);
     }
 }
@@ -1,7 +1,19 @@
 /// Includes all the types used in the Route protocol.
 /// (this statement is aspirational)
 mod rtattr;
-pub use self::rtattr::RtAttr;
+pub use self::rtattr::{RtAttr, RtAttrRef, RtAttrs};
 
 mod ifinfo;
 pub use self::ifinfo::IfInfoMsg;
+
+mod attrs;
+pub use self::attrs::{AttrSet, AttrValue, Be};
+pub use netlink_derive::AttrSet;
+
+mod decode;
+
+mod types;
+pub use self::types::{
+    BridgeVlandb, BridgeVlandbDump, BridgeVlandbEntry, BridgeVlandbGopts, BridgeVlandbStats, Ifa,
+    Ifla, IflaBr, IflaBridge, IflaBrport, IflaInfo, IflaVlan, Nda, Rta, RtmType, VethInfo,
+};
@@ -1,10 +1,25 @@
-use crate::Serializable;
+use crate::uapi;
+use crate::{NetlinkError, Result, Serializable};
 use std::ffi::{CStr, CString};
-use std::io::{Error, ErrorKind, Result};
-use std::ptr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// The bits of an attribute's type that are flags rather than the type.
+const NLA_F_MASK: u16 = (uapi::NLA_F_NESTED | uapi::NLA_F_NET_BYTEORDER) as u16;
+
+/// Defines RtAttr accessors that just borrow the attribute as an RtAttrRef.
+macro_rules! accessors {
+    ($($(#[$doc:meta])* $name:ident -> $t:ty;)*) => {
+        $(
+            $(#[$doc])*
+            pub fn $name(&self) -> Result<$t> {
+                RtAttrRef::from(self).$name()
+            }
+        )*
+    };
+}
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 /// RtAttr is the length-type-value struct that holds data.
 pub struct RtAttr {
     header: RtAttrHeader,
@@ -12,7 +27,7 @@ pub struct RtAttr {
 }
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 struct RtAttrHeader {
     pub len: u16,
     pub typ: u16,
@@ -29,146 +44,448 @@ impl RtAttrHeader {
 
     pub fn from_bytes(v: &[u8]) -> Result<RtAttrHeader> {
         if v.len() < RtAttrHeader::size() {
-            return Err(Error::new(ErrorKind::UnexpectedEof, "message too short"));
+            return Err(NetlinkError::Truncated {
+                what: "rtattr header",
+                offset: 0,
+                attr: None,
+            });
         }
 
-        let mem = v.to_owned();
-        let h: RtAttrHeader = unsafe { ptr::read(mem.as_ptr() as *mut RtAttrHeader) };
+        Ok(RtAttrHeader {
+            len: crate::util::u16_at(v, 0),
+            typ: crate::util::u16_at(v, 2),
+        })
+    }
+}
 
-        Ok(h)
+/// The length of an attribute that is `len` bytes long so far, once `more`
+/// are added.
+fn attr_len(len: usize, more: usize) -> u16 {
+    match len.checked_add(more) {
+        Some(l) if l <= u16::MAX as usize => l as u16,
+        _ => panic!(
+            "rtattr too long: {} bytes, the most is {}",
+            len.saturating_add(more),
+            u16::MAX
+        ),
     }
 }
 
 impl RtAttr {
-    // question: is there a good way to make data generic or
-    // more user-friendly? Could you write something like
-    // new(IFLA_MTU, 1500)
-    // and have it automatically convert to bytes
-    pub fn new(typ: u16, data: Vec<u8>) -> RtAttr {
+    /// Creates an attribute holding raw bytes. The typed constructors below
+    /// cover the common cases. The type is usually one of the per-family
+    /// attribute types, such as `Ifla::MTU`.
+    ///
+    /// Panics if `data` doesn't fit in an attribute, whose length, header
+    /// included, is a u16.
+    pub fn new(typ: impl Into<u16>, data: Vec<u8>) -> RtAttr {
         RtAttr {
             header: RtAttrHeader {
-                len: 0x4 + data.len() as u16,
-                typ: typ,
+                len: attr_len(0x4, data.len()),
+                typ: typ.into(),
             },
             data: data,
         }
     }
 
+    pub fn new_u8(typ: impl Into<u16>, v: u8) -> RtAttr {
+        RtAttr::new(typ, vec![v])
+    }
+
+    pub fn new_u16(typ: impl Into<u16>, v: u16) -> RtAttr {
+        RtAttr::new(typ, v.to_ne_bytes().to_vec())
+    }
+
+    pub fn new_u32(typ: impl Into<u16>, v: u32) -> RtAttr {
+        RtAttr::new(typ, v.to_ne_bytes().to_vec())
+    }
+
+    pub fn new_u64(typ: impl Into<u16>, v: u64) -> RtAttr {
+        RtAttr::new(typ, v.to_ne_bytes().to_vec())
+    }
+
+    pub fn new_i32(typ: impl Into<u16>, v: i32) -> RtAttr {
+        RtAttr::new(typ, v.to_ne_bytes().to_vec())
+    }
+
+    /// Creates an attribute holding a u16 in network byte order, flagged
+    /// with NLA_F_NET_BYTEORDER.
+    pub fn new_be16(typ: impl Into<u16>, v: u16) -> RtAttr {
+        RtAttr::new(
+            typ.into() | uapi::NLA_F_NET_BYTEORDER as u16,
+            v.to_be_bytes().to_vec(),
+        )
+    }
+
+    /// Like `new_be16`, for a u32.
+    pub fn new_be32(typ: impl Into<u16>, v: u32) -> RtAttr {
+        RtAttr::new(
+            typ.into() | uapi::NLA_F_NET_BYTEORDER as u16,
+            v.to_be_bytes().to_vec(),
+        )
+    }
+
+    /// Like `new_be16`, for a u64.
+    pub fn new_be64(typ: impl Into<u16>, v: u64) -> RtAttr {
+        RtAttr::new(
+            typ.into() | uapi::NLA_F_NET_BYTEORDER as u16,
+            v.to_be_bytes().to_vec(),
+        )
+    }
+
+    /// Creates a string attribute, adding the NUL terminator the kernel
+    /// expects. `s` shouldn't contain any NULs itself.
+    pub fn new_str(typ: impl Into<u16>, s: &str) -> RtAttr {
+        let mut data = Vec::with_capacity(s.len() + 1);
+        data.extend_from_slice(s.as_bytes());
+        data.push(0);
+        RtAttr::new(typ, data)
+    }
+
+    pub fn new_cstr(typ: impl Into<u16>, s: &CStr) -> RtAttr {
+        RtAttr::new(typ, s.to_bytes_with_nul().to_vec())
+    }
+
+    pub fn new_ipv4(typ: impl Into<u16>, addr: Ipv4Addr) -> RtAttr {
+        RtAttr::new(typ, addr.octets().to_vec())
+    }
+
+    pub fn new_ipv6(typ: impl Into<u16>, addr: Ipv6Addr) -> RtAttr {
+        RtAttr::new(typ, addr.octets().to_vec())
+    }
+
+    pub fn new_ip(typ: impl Into<u16>, addr: IpAddr) -> RtAttr {
+        match addr {
+            IpAddr::V4(addr) => RtAttr::new_ipv4(typ, addr),
+            IpAddr::V6(addr) => RtAttr::new_ipv6(typ, addr),
+        }
+    }
+
+    pub fn new_mac(typ: impl Into<u16>, mac: [u8; 6]) -> RtAttr {
+        RtAttr::new(typ, mac.to_vec())
+    }
+
+    /// Creates an attribute holding a list of attributes, flagged with
+    /// NLA_F_NESTED. More can be added with `add_data`.
+    pub fn new_nested(typ: impl Into<u16>, attrs: Vec<RtAttr>) -> RtAttr {
+        let mut out = RtAttr::new(typ.into() | uapi::NLA_F_NESTED as u16, vec![]);
+        for attr in attrs.iter() {
+            out.add_data(attr);
+        }
+        out
+    }
+
+    /// Appends `data`, padded to the attribute alignment. Panics if the
+    /// attribute grows too long for its u16 length.
     pub fn add_data<S: Serializable>(&mut self, data: &S) {
         let mut d = data.to_bytes();
         let l = d.len();
         let aligned_len = crate::util::align(l);
         let padding = aligned_len - l;
+        let len = attr_len(self.header.len as usize, aligned_len);
 
         self.data.append(&mut d);
         for _ in 0..padding {
             self.data.push(0);
         }
-        self.header.len += aligned_len as u16;
+        self.header.len = len;
     }
 
+    /// The attribute type, without the NLA_F_NESTED and NLA_F_NET_BYTEORDER
+    /// flags.
     pub fn get_typ(&self) -> u16 {
-        self.header.typ
+        self.header.typ & !NLA_F_MASK
     }
 
-    pub fn as_u32(&self) -> Result<u32> {
-        if self.header.data_size() < 4 {
-            return Err(Error::new(ErrorKind::InvalidData, ""));
-        }
+    pub fn is_nested(&self) -> bool {
+        RtAttrRef::from(self).is_nested()
+    }
 
-        let mut d: [u8; 4] = [0; 4];
-        d.copy_from_slice(&self.data[0..4]);
-        Ok(u32::from_ne_bytes(d))
+    pub fn is_net_byteorder(&self) -> bool {
+        RtAttrRef::from(self).is_net_byteorder()
     }
 
-    pub fn as_u16(&self) -> Result<u16> {
-        if self.header.data_size() < 2 {
-            return Err(Error::new(ErrorKind::InvalidData, ""));
-        }
+    /// Iterates over the attributes nested in this one.
+    pub fn nested(&self) -> RtAttrs<'_> {
+        RtAttrRef::iter(&self.data)
+    }
 
-        let mut d: [u8; 2] = [0; 2];
-        d.copy_from_slice(&self.data[0..2]);
-        Ok(u16::from_ne_bytes(d))
+    accessors! {
+        as_u8 -> u8;
+        as_u16 -> u16;
+        as_u32 -> u32;
+        as_u64 -> u64;
+        as_i32 -> i32;
+        /// Reads a u16 in network byte order, e.g. a port.
+        as_be16 -> u16;
+        as_be32 -> u32;
+        as_be64 -> u64;
+        as_bool -> bool;
+        as_str -> &str;
+        as_cstr -> &CStr;
+        to_cstring -> CString;
+        as_ipv4 -> Ipv4Addr;
+        as_ipv6 -> Ipv6Addr;
+        as_ip -> IpAddr;
+        as_mac -> [u8; 6];
     }
 
-    pub fn as_bool(&self) -> Result<bool> {
-        if self.header.data_size() == 0 {
-            return Err(Error::new(ErrorKind::InvalidData, ""));
-        }
-        Ok(self.data[0] == 1)
+    pub fn one_from_bytes(v: &[u8], idx: usize) -> Result<RtAttr> {
+        Ok(RtAttrRef::one_from_bytes(v, idx)?.to_rtattr())
     }
 
-    pub fn to_cstring(&self) -> Result<CString> {
-        let cstr = match CStr::from_bytes_with_nul(&self.data) {
-            Ok(cstr) => cstr,
-            Err(_) => return Err(Error::new(ErrorKind::InvalidData, "invalid interface name")),
-        };
-        Ok(CString::from(cstr))
+    pub fn from_bytes(v: &[u8]) -> Result<Vec<RtAttr>> {
+        RtAttrRef::iter(v)
+            .map(|attr| attr.map(|attr| attr.to_rtattr()))
+            .collect()
     }
+}
 
-    pub fn one_from_bytes(v: &[u8], idx: usize) -> Result<RtAttr> {
+impl crate::Serializable for RtAttr {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out: Vec<u8> = Vec::with_capacity(self.header.len as usize);
+
+        out.extend_from_slice(&self.header.len.to_ne_bytes());
+        out.extend_from_slice(&self.header.typ.to_ne_bytes());
+        out.extend(self.data.iter());
+        return out;
+    }
+}
+
+/// RtAttrRef is an attribute borrowed from a buffer, such as the payload of
+/// a NetlinkMessageRef. Nested attributes can be walked with `nested()`
+/// without copying anything.
+#[derive(Debug, Clone, Copy)]
+pub struct RtAttrRef<'a> {
+    typ: u16,
+    pub data: &'a [u8],
+}
+
+impl<'a> RtAttrRef<'a> {
+    /// Returns a lazy iterator over the attributes in `v`. It ends after the
+    /// first error.
+    pub fn iter(v: &'a [u8]) -> RtAttrs<'a> {
+        RtAttrs { buf: v, idx: 0 }
+    }
+
+    pub fn one_from_bytes(v: &'a [u8], idx: usize) -> Result<RtAttrRef<'a>> {
         if v.len() < (idx + RtAttrHeader::size()) {
-            return Err(Error::new(
-                ErrorKind::UnexpectedEof,
-                "message too short for rtattr header",
-            ));
+            return Err(NetlinkError::Truncated {
+                what: "rtattr header",
+                offset: idx,
+                attr: None,
+            });
         }
 
         let header = RtAttrHeader::from_bytes(&v[idx..idx + RtAttrHeader::size()])?;
         let header_len = header.len as usize;
+        if header_len < RtAttrHeader::size() {
+            return Err(NetlinkError::Malformed {
+                what: "rtattr length",
+                offset: idx,
+                attr: Some(header.typ),
+            });
+        }
         if v.len() < (idx + header_len) {
-            return Err(Error::new(
-                ErrorKind::UnexpectedEof,
-                "buffer too short for message",
-            ));
+            return Err(NetlinkError::Truncated {
+                what: "rtattr",
+                offset: idx,
+                attr: Some(header.typ),
+            });
         }
 
         // the leftover data is [idx + header .. idx +  len]
-        let attr = RtAttr {
-            header: header,
-            data: v[idx + RtAttrHeader::size()..idx + header_len].to_owned(),
-        };
-        return Ok(attr);
+        Ok(RtAttrRef {
+            typ: header.typ,
+            data: &v[idx + RtAttrHeader::size()..idx + header_len],
+        })
     }
 
-    pub fn from_bytes(v: &[u8]) -> Result<Vec<RtAttr>> {
-        let mut idx = 0;
-        let mut res = Vec::new();
-        let len = v.len();
+    /// The attribute type, without the NLA_F_NESTED and NLA_F_NET_BYTEORDER
+    /// flags.
+    pub fn get_typ(&self) -> u16 {
+        self.typ & !NLA_F_MASK
+    }
+
+    pub fn is_nested(&self) -> bool {
+        self.typ & uapi::NLA_F_NESTED as u16 != 0
+    }
+
+    pub fn is_net_byteorder(&self) -> bool {
+        self.typ & uapi::NLA_F_NET_BYTEORDER as u16 != 0
+    }
 
-        while idx < len {
-            let msg = RtAttr::one_from_bytes(v, idx)?;
-            idx += msg.header.len as usize;
-            idx = crate::util::align(idx);
-            res.push(msg);
+    /// Iterates over the attributes nested in this one.
+    pub fn nested(&self) -> RtAttrs<'a> {
+        RtAttrRef::iter(self.data)
+    }
+
+    /// Copies the attribute out of the buffer.
+    pub fn to_rtattr(&self) -> RtAttr {
+        RtAttr::new(self.typ, self.data.to_owned())
+    }
+
+    fn malformed(&self, what: &'static str) -> NetlinkError {
+        NetlinkError::Malformed {
+            what,
+            offset: 0,
+            attr: Some(self.get_typ()),
         }
+    }
 
-        Ok(res)
+    /// The first N bytes of the data, which must be at least that long.
+    fn bytes<const N: usize>(&self, what: &'static str) -> Result<[u8; N]> {
+        if self.data.len() < N {
+            return Err(self.malformed(what));
+        }
+        let mut b = [0; N];
+        b.copy_from_slice(&self.data[0..N]);
+        Ok(b)
     }
-}
 
-impl crate::Serializable for RtAttr {
-    fn to_bytes(&self) -> Vec<u8> {
-        let mut out: Vec<u8> = Vec::with_capacity(self.header.len as usize);
+    pub fn as_u8(&self) -> Result<u8> {
+        Ok(self.bytes::<1>("u8 attribute")?[0])
+    }
+
+    pub fn as_u16(&self) -> Result<u16> {
+        Ok(u16::from_ne_bytes(self.bytes("u16 attribute")?))
+    }
+
+    pub fn as_u32(&self) -> Result<u32> {
+        Ok(u32::from_ne_bytes(self.bytes("u32 attribute")?))
+    }
+
+    pub fn as_u64(&self) -> Result<u64> {
+        Ok(u64::from_ne_bytes(self.bytes("u64 attribute")?))
+    }
+
+    pub fn as_i32(&self) -> Result<i32> {
+        Ok(i32::from_ne_bytes(self.bytes("i32 attribute")?))
+    }
+
+    /// Reads a u16 in network byte order, e.g. a port.
+    pub fn as_be16(&self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.bytes("be16 attribute")?))
+    }
+
+    pub fn as_be32(&self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.bytes("be32 attribute")?))
+    }
+
+    pub fn as_be64(&self) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.bytes("be64 attribute")?))
+    }
+
+    pub fn as_bool(&self) -> Result<bool> {
+        Ok(self.bytes::<1>("bool attribute")?[0] == 1)
+    }
+
+    /// Borrows a string attribute. Anything from the first NUL on is
+    /// ignored, and the NUL is optional, since not every string the kernel
+    /// sends has one.
+    pub fn as_str(&self) -> Result<&'a str> {
+        let end = self
+            .data
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(self.data.len());
+        std::str::from_utf8(&self.data[..end]).map_err(|_| self.malformed("string attribute"))
+    }
+
+    /// Borrows a NUL-terminated string attribute.
+    pub fn as_cstr(&self) -> Result<&'a CStr> {
+        CStr::from_bytes_with_nul(self.data).map_err(|_| self.malformed("string attribute"))
+    }
 
-        // poop the header to the head of the vector
-        unsafe {
-            ptr::copy_nonoverlapping(&self.header, out.as_mut_ptr() as *mut RtAttrHeader, 1);
-            out.set_len(RtAttrHeader::size());
+    pub fn to_cstring(&self) -> Result<CString> {
+        Ok(CString::from(self.as_cstr()?))
+    }
+
+    pub fn as_ipv4(&self) -> Result<Ipv4Addr> {
+        if self.data.len() != 4 {
+            return Err(self.malformed("ipv4 address attribute"));
         }
+        Ok(Ipv4Addr::from(self.bytes::<4>("ipv4 address attribute")?))
+    }
 
-        out.extend(self.data.iter());
-        return out;
+    pub fn as_ipv6(&self) -> Result<Ipv6Addr> {
+        if self.data.len() != 16 {
+            return Err(self.malformed("ipv6 address attribute"));
+        }
+        Ok(Ipv6Addr::from(self.bytes::<16>("ipv6 address attribute")?))
+    }
+
+    /// Reads an address of either family, going by its length.
+    pub fn as_ip(&self) -> Result<IpAddr> {
+        match self.data.len() {
+            4 => Ok(IpAddr::V4(self.as_ipv4()?)),
+            16 => Ok(IpAddr::V6(self.as_ipv6()?)),
+            _ => Err(self.malformed("ip address attribute")),
+        }
+    }
+
+    pub fn as_mac(&self) -> Result<[u8; 6]> {
+        if self.data.len() != 6 {
+            return Err(self.malformed("mac address attribute"));
+        }
+        self.bytes("mac address attribute")
+    }
+}
+
+impl<'a> From<&'a RtAttr> for RtAttrRef<'a> {
+    fn from(attr: &'a RtAttr) -> Self {
+        RtAttrRef {
+            typ: attr.header.typ,
+            data: &attr.data,
+        }
+    }
+}
+
+/// RtAttrs is an iterator over the attributes in a buffer, returned by
+/// `RtAttrRef::iter` and `RtAttrRef::nested`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RtAttrs<'a> {
+    buf: &'a [u8],
+    idx: usize,
+}
+
+impl<'a> Iterator for RtAttrs<'a> {
+    type Item = Result<RtAttrRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.idx >= self.buf.len() {
+            return None;
+        }
+        match RtAttrRef::one_from_bytes(self.buf, self.idx) {
+            Ok(attr) => {
+                let len = RtAttrHeader::size() + attr.data.len();
+                self.idx = crate::util::align(self.idx + len);
+                Some(Ok(attr))
+            }
+            Err(e) => {
+                self.idx = self.buf.len();
+                Some(Err(e))
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::RtAttr;
-    use crate::Serializable;
+    use super::{RtAttr, RtAttrRef};
+    use crate::{NetlinkError, Serializable};
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    /// Builds the wire form of an attribute header in host byte order.
+    fn header(len: u16, typ: u16) -> Vec<u8> {
+        let mut b = len.to_ne_bytes().to_vec();
+        b.extend(&typ.to_ne_bytes());
+        b
+    }
+
     #[test]
     fn test_rtattr() {
-        let mut ra = RtAttr::new(1, vec![]);
+        let mut ra = RtAttr::new(1u16, vec![]);
 
         assert_eq!(ra.header.typ, 1);
         assert_eq!(ra.header.len, 0x4);
@@ -197,13 +514,131 @@ mod tests {
         let v = vec![7, 8, 9];
         ra.add_data(&v);
 
+        let mut expected = header(0x14, 1);
+        expected.extend(&[0x12, 0x34, 0x56, 0x78, 1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 0]);
+        assert_eq!(ra.to_bytes(), expected);
+    }
+
+    #[test]
+    fn test_rtattr_max_len() {
+        assert_eq!(RtAttr::new(1u16, vec![0; 0xfffb]).header.len, 0xffff);
+
+        let mut ra = RtAttr::new(1u16, vec![0; 0xfff0]);
+        ra.add_data(&vec![0u8; 0x8]);
+        assert_eq!(ra.header.len, 0xfffc);
+    }
+
+    #[test]
+    #[should_panic(expected = "rtattr too long")]
+    fn test_rtattr_too_long() {
+        RtAttr::new(1u16, vec![0; 0xfffc]);
+    }
+
+    #[test]
+    #[should_panic(expected = "rtattr too long")]
+    fn test_add_data_too_long() {
+        let mut ra = RtAttr::new(1u16, vec![0; 0xfff8]);
+        ra.add_data(&vec![0u8; 1]);
+    }
+
+    #[test]
+    fn test_nested() {
+        let mut inner = RtAttr::new(2u16, vec![]);
+        inner.add_data(&5u32.to_ne_bytes().to_vec());
+        let mut outer = RtAttr::new(1u16, vec![]);
+        outer.add_data(&inner);
+        outer.add_data(&RtAttr::new(3u16, b"lo\0".to_vec()));
+
+        let mut b = outer.to_bytes();
+        b.extend(RtAttr::new(4u16, vec![1]).to_bytes());
+
+        let attrs: Vec<RtAttrRef> = RtAttrRef::iter(&b).collect::<Result<_, _>>().unwrap();
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(attrs[0].get_typ(), 1);
+        assert_eq!(attrs[1].get_typ(), 4);
+        assert_eq!(attrs[1].data, &[1]);
+
+        let mut nested = attrs[0].nested();
+        let a = nested.next().unwrap().unwrap();
+        assert_eq!(a.get_typ(), 2);
+        assert_eq!(a.as_u32().unwrap(), 5);
+        let a = nested.next().unwrap().unwrap();
+        assert_eq!(a.as_cstr().unwrap().to_bytes(), b"lo");
+        assert_eq!(a.data.as_ptr(), b[16..].as_ptr());
+        assert!(nested.next().is_none());
+    }
+
+    #[test]
+    fn test_typed() {
+        let attrs = vec![
+            RtAttr::new_u8(1u16, 7),
+            RtAttr::new_u16(2u16, 1500),
+            RtAttr::new_u64(3u16, 1 << 40),
+            RtAttr::new_i32(4u16, -2),
+            RtAttr::new_be16(5u16, 0x1234),
+            RtAttr::new_str(6u16, "eth0"),
+            RtAttr::new_ipv4(7u16, Ipv4Addr::new(10, 0, 0, 1)),
+            RtAttr::new_ip(8u16, "fe80::1".parse().unwrap()),
+            RtAttr::new_mac(9u16, [2, 0, 0, 0, 0, 1]),
+            RtAttr::new_nested(10u16, vec![RtAttr::new_u32(1u16, 42)]),
+        ];
+        // Nesting them pads each one out, as a message would.
+        let all = RtAttr::new_nested(11u16, attrs).to_bytes();
+
+        let attrs = RtAttr::from_bytes(&all[4..]).unwrap();
+        assert_eq!(attrs[0].as_u8().unwrap(), 7);
+        assert_eq!(attrs[1].as_u16().unwrap(), 1500);
+        assert_eq!(attrs[2].as_u64().unwrap(), 1 << 40);
+        assert_eq!(attrs[3].as_i32().unwrap(), -2);
+
+        assert_eq!(attrs[4].get_typ(), 5);
+        assert!(attrs[4].is_net_byteorder());
+        assert_eq!(attrs[4].data, vec![0x12, 0x34]);
+        assert_eq!(attrs[4].as_be16().unwrap(), 0x1234);
+
+        assert_eq!(attrs[5].data, b"eth0\0");
+        assert_eq!(attrs[5].as_str().unwrap(), "eth0");
+        assert_eq!(attrs[5].to_cstring().unwrap().as_bytes(), b"eth0");
+        assert_eq!(attrs[6].as_ip().unwrap(), Ipv4Addr::new(10, 0, 0, 1));
         assert_eq!(
-            ra.to_bytes(),
-            vec![
-                0x14, 0, //len
-                1, 0, //typ
-                0x12, 0x34, 0x56, 0x78, 1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 0,
-            ]
+            attrs[7].as_ipv6().unwrap(),
+            "fe80::1".parse::<Ipv6Addr>().unwrap()
         );
+        assert!(attrs[7].as_ipv4().is_err());
+        assert_eq!(attrs[8].as_mac().unwrap(), [2, 0, 0, 0, 0, 1]);
+
+        assert_eq!(attrs[9].get_typ(), 10);
+        assert!(attrs[9].is_nested());
+        let inner: Vec<_> = attrs[9].nested().collect::<Result<_, _>>().unwrap();
+        assert_eq!(inner.len(), 1);
+        assert_eq!(inner[0].as_u32().unwrap(), 42);
+        assert!(!inner[0].is_nested());
+    }
+
+    #[test]
+    fn test_malformed() {
+        // zero-length attribute
+        match RtAttr::from_bytes(&header(0, 1)) {
+            Err(NetlinkError::Malformed { offset, attr, .. }) => {
+                assert_eq!(offset, 0);
+                assert_eq!(attr, Some(1));
+            }
+            r => panic!("unexpected result {:?}", r),
+        }
+
+        // attribute runs past the end of the buffer
+        let mut b = header(4, 1);
+        b.extend(header(8, 2));
+        b.push(1);
+        match RtAttr::from_bytes(&b) {
+            Err(NetlinkError::Truncated { offset, attr, .. }) => {
+                assert_eq!(offset, 4);
+                assert_eq!(attr, Some(2));
+            }
+            r => panic!("unexpected result {:?}", r),
+        }
+
+        let ra = RtAttr::new(3u16, vec![1, 2]);
+        assert!(ra.as_u32().is_err());
     }
 }
@@ -0,0 +1,463 @@
+//! Typed message and attribute types for rtnetlink. Each is a newtype over
+//! the u16 on the wire, so they can be passed to `NetlinkMessage::new` and
+//! the `RtAttr` constructors, compared with the raw values, and matched on.
+//!
+//! Values that are missing from older kernel headers (anything after 5.15)
+//! are written out as numbers, so the crate builds against those headers.
+use crate::uapi;
+
+typed_consts! {
+    /// An rtnetlink message type, the `typ` in the netlink header.
+    pub struct RtmType(u16): "RTM_" {
+        NEWLINK = uapi::RTM_NEWLINK;
+        DELLINK = uapi::RTM_DELLINK;
+        GETLINK = uapi::RTM_GETLINK;
+        SETLINK = uapi::RTM_SETLINK;
+        NEWADDR = uapi::RTM_NEWADDR;
+        DELADDR = uapi::RTM_DELADDR;
+        GETADDR = uapi::RTM_GETADDR;
+        NEWROUTE = uapi::RTM_NEWROUTE;
+        DELROUTE = uapi::RTM_DELROUTE;
+        GETROUTE = uapi::RTM_GETROUTE;
+        NEWNEIGH = uapi::RTM_NEWNEIGH;
+        DELNEIGH = uapi::RTM_DELNEIGH;
+        GETNEIGH = uapi::RTM_GETNEIGH;
+        NEWRULE = uapi::RTM_NEWRULE;
+        DELRULE = uapi::RTM_DELRULE;
+        GETRULE = uapi::RTM_GETRULE;
+        NEWQDISC = uapi::RTM_NEWQDISC;
+        DELQDISC = uapi::RTM_DELQDISC;
+        GETQDISC = uapi::RTM_GETQDISC;
+        NEWTCLASS = uapi::RTM_NEWTCLASS;
+        DELTCLASS = uapi::RTM_DELTCLASS;
+        GETTCLASS = uapi::RTM_GETTCLASS;
+        NEWTFILTER = uapi::RTM_NEWTFILTER;
+        DELTFILTER = uapi::RTM_DELTFILTER;
+        GETTFILTER = uapi::RTM_GETTFILTER;
+        NEWACTION = uapi::RTM_NEWACTION;
+        DELACTION = uapi::RTM_DELACTION;
+        GETACTION = uapi::RTM_GETACTION;
+        NEWPREFIX = uapi::RTM_NEWPREFIX;
+        GETMULTICAST = uapi::RTM_GETMULTICAST;
+        GETANYCAST = uapi::RTM_GETANYCAST;
+        NEWNEIGHTBL = uapi::RTM_NEWNEIGHTBL;
+        GETNEIGHTBL = uapi::RTM_GETNEIGHTBL;
+        SETNEIGHTBL = uapi::RTM_SETNEIGHTBL;
+        NEWNDUSEROPT = uapi::RTM_NEWNDUSEROPT;
+        NEWADDRLABEL = uapi::RTM_NEWADDRLABEL;
+        DELADDRLABEL = uapi::RTM_DELADDRLABEL;
+        GETADDRLABEL = uapi::RTM_GETADDRLABEL;
+        GETDCB = uapi::RTM_GETDCB;
+        SETDCB = uapi::RTM_SETDCB;
+        NEWNETCONF = uapi::RTM_NEWNETCONF;
+        DELNETCONF = uapi::RTM_DELNETCONF;
+        GETNETCONF = uapi::RTM_GETNETCONF;
+        NEWMDB = uapi::RTM_NEWMDB;
+        DELMDB = uapi::RTM_DELMDB;
+        GETMDB = uapi::RTM_GETMDB;
+        NEWNSID = uapi::RTM_NEWNSID;
+        DELNSID = uapi::RTM_DELNSID;
+        GETNSID = uapi::RTM_GETNSID;
+        NEWSTATS = uapi::RTM_NEWSTATS;
+        GETSTATS = uapi::RTM_GETSTATS;
+        SETSTATS = uapi::RTM_SETSTATS;
+        NEWCACHEREPORT = uapi::RTM_NEWCACHEREPORT;
+        NEWCHAIN = uapi::RTM_NEWCHAIN;
+        DELCHAIN = uapi::RTM_DELCHAIN;
+        GETCHAIN = uapi::RTM_GETCHAIN;
+        NEWNEXTHOP = uapi::RTM_NEWNEXTHOP;
+        DELNEXTHOP = uapi::RTM_DELNEXTHOP;
+        GETNEXTHOP = uapi::RTM_GETNEXTHOP;
+        NEWLINKPROP = uapi::RTM_NEWLINKPROP;
+        DELLINKPROP = uapi::RTM_DELLINKPROP;
+        GETLINKPROP = uapi::RTM_GETLINKPROP;
+        NEWVLAN = uapi::RTM_NEWVLAN;
+        DELVLAN = uapi::RTM_DELVLAN;
+        GETVLAN = uapi::RTM_GETVLAN;
+        NEWNEXTHOPBUCKET = uapi::RTM_NEWNEXTHOPBUCKET;
+        DELNEXTHOPBUCKET = uapi::RTM_DELNEXTHOPBUCKET;
+        GETNEXTHOPBUCKET = uapi::RTM_GETNEXTHOPBUCKET;
+        // not in the 5.15 headers
+        NEWTUNNEL = 120;
+        DELTUNNEL = 121;
+        GETTUNNEL = 122;
+    }
+}
+
+typed_consts! {
+    /// A link attribute, IFLA_*, as sent with RTM_NEWLINK and friends.
+    pub struct Ifla(u16): "IFLA_" {
+        ADDRESS = uapi::IFLA_ADDRESS;
+        BROADCAST = uapi::IFLA_BROADCAST;
+        IFNAME = uapi::IFLA_IFNAME;
+        MTU = uapi::IFLA_MTU;
+        LINK = uapi::IFLA_LINK;
+        QDISC = uapi::IFLA_QDISC;
+        STATS = uapi::IFLA_STATS;
+        COST = uapi::IFLA_COST;
+        PRIORITY = uapi::IFLA_PRIORITY;
+        MASTER = uapi::IFLA_MASTER;
+        WIRELESS = uapi::IFLA_WIRELESS;
+        PROTINFO = uapi::IFLA_PROTINFO;
+        TXQLEN = uapi::IFLA_TXQLEN;
+        MAP = uapi::IFLA_MAP;
+        WEIGHT = uapi::IFLA_WEIGHT;
+        OPERSTATE = uapi::IFLA_OPERSTATE;
+        LINKMODE = uapi::IFLA_LINKMODE;
+        LINKINFO = uapi::IFLA_LINKINFO;
+        NET_NS_PID = uapi::IFLA_NET_NS_PID;
+        IFALIAS = uapi::IFLA_IFALIAS;
+        NUM_VF = uapi::IFLA_NUM_VF;
+        VFINFO_LIST = uapi::IFLA_VFINFO_LIST;
+        STATS64 = uapi::IFLA_STATS64;
+        VF_PORTS = uapi::IFLA_VF_PORTS;
+        PORT_SELF = uapi::IFLA_PORT_SELF;
+        AF_SPEC = uapi::IFLA_AF_SPEC;
+        GROUP = uapi::IFLA_GROUP;
+        NET_NS_FD = uapi::IFLA_NET_NS_FD;
+        EXT_MASK = uapi::IFLA_EXT_MASK;
+        PROMISCUITY = uapi::IFLA_PROMISCUITY;
+        NUM_TX_QUEUES = uapi::IFLA_NUM_TX_QUEUES;
+        NUM_RX_QUEUES = uapi::IFLA_NUM_RX_QUEUES;
+        CARRIER = uapi::IFLA_CARRIER;
+        PHYS_PORT_ID = uapi::IFLA_PHYS_PORT_ID;
+        CARRIER_CHANGES = uapi::IFLA_CARRIER_CHANGES;
+        PHYS_SWITCH_ID = uapi::IFLA_PHYS_SWITCH_ID;
+        LINK_NETNSID = uapi::IFLA_LINK_NETNSID;
+        PHYS_PORT_NAME = uapi::IFLA_PHYS_PORT_NAME;
+        PROTO_DOWN = uapi::IFLA_PROTO_DOWN;
+        GSO_MAX_SEGS = uapi::IFLA_GSO_MAX_SEGS;
+        GSO_MAX_SIZE = uapi::IFLA_GSO_MAX_SIZE;
+        PAD = uapi::IFLA_PAD;
+        XDP = uapi::IFLA_XDP;
+        EVENT = uapi::IFLA_EVENT;
+        NEW_NETNSID = uapi::IFLA_NEW_NETNSID;
+        IF_NETNSID = uapi::IFLA_IF_NETNSID;
+        TARGET_NETNSID = uapi::IFLA_TARGET_NETNSID;
+        CARRIER_UP_COUNT = uapi::IFLA_CARRIER_UP_COUNT;
+        CARRIER_DOWN_COUNT = uapi::IFLA_CARRIER_DOWN_COUNT;
+        NEW_IFINDEX = uapi::IFLA_NEW_IFINDEX;
+        MIN_MTU = uapi::IFLA_MIN_MTU;
+        MAX_MTU = uapi::IFLA_MAX_MTU;
+        PROP_LIST = uapi::IFLA_PROP_LIST;
+        ALT_IFNAME = uapi::IFLA_ALT_IFNAME;
+        PERM_ADDRESS = uapi::IFLA_PERM_ADDRESS;
+        PROTO_DOWN_REASON = uapi::IFLA_PROTO_DOWN_REASON;
+        // not in the 5.15 headers
+        PARENT_DEV_NAME = 56;
+        PARENT_DEV_BUS_NAME = 57;
+        GRO_MAX_SIZE = 58;
+        TSO_MAX_SIZE = 59;
+        TSO_MAX_SEGS = 60;
+        ALLMULTI = 61;
+    }
+}
+
+typed_consts! {
+    /// An attribute nested in IFLA_LINKINFO.
+    pub struct IflaInfo(u16): "IFLA_INFO_" {
+        KIND = uapi::IFLA_INFO_KIND;
+        DATA = uapi::IFLA_INFO_DATA;
+        XSTATS = uapi::IFLA_INFO_XSTATS;
+        SLAVE_KIND = uapi::IFLA_INFO_SLAVE_KIND;
+        SLAVE_DATA = uapi::IFLA_INFO_SLAVE_DATA;
+    }
+}
+
+typed_consts! {
+    /// An attribute in the IFLA_INFO_DATA of a vlan link.
+    pub struct IflaVlan(u16): "IFLA_VLAN_" {
+        ID = uapi::IFLA_VLAN_ID;
+        FLAGS = uapi::IFLA_VLAN_FLAGS;
+        EGRESS_QOS = uapi::IFLA_VLAN_EGRESS_QOS;
+        INGRESS_QOS = uapi::IFLA_VLAN_INGRESS_QOS;
+        PROTOCOL = uapi::IFLA_VLAN_PROTOCOL;
+    }
+}
+
+typed_consts! {
+    /// An attribute in the IFLA_INFO_DATA of a veth link.
+    pub struct VethInfo(u16): "VETH_INFO_" {
+        PEER = uapi::VETH_INFO_PEER;
+    }
+}
+
+typed_consts! {
+    /// An attribute in the IFLA_INFO_DATA of a bridge.
+    pub struct IflaBr(u16): "IFLA_BR_" {
+        FORWARD_DELAY = uapi::IFLA_BR_FORWARD_DELAY;
+        HELLO_TIME = uapi::IFLA_BR_HELLO_TIME;
+        MAX_AGE = uapi::IFLA_BR_MAX_AGE;
+        AGEING_TIME = uapi::IFLA_BR_AGEING_TIME;
+        STP_STATE = uapi::IFLA_BR_STP_STATE;
+        PRIORITY = uapi::IFLA_BR_PRIORITY;
+        VLAN_FILTERING = uapi::IFLA_BR_VLAN_FILTERING;
+        VLAN_PROTOCOL = uapi::IFLA_BR_VLAN_PROTOCOL;
+        GROUP_FWD_MASK = uapi::IFLA_BR_GROUP_FWD_MASK;
+        ROOT_ID = uapi::IFLA_BR_ROOT_ID;
+        BRIDGE_ID = uapi::IFLA_BR_BRIDGE_ID;
+        ROOT_PORT = uapi::IFLA_BR_ROOT_PORT;
+        ROOT_PATH_COST = uapi::IFLA_BR_ROOT_PATH_COST;
+        TOPOLOGY_CHANGE = uapi::IFLA_BR_TOPOLOGY_CHANGE;
+        TOPOLOGY_CHANGE_DETECTED = uapi::IFLA_BR_TOPOLOGY_CHANGE_DETECTED;
+        HELLO_TIMER = uapi::IFLA_BR_HELLO_TIMER;
+        TCN_TIMER = uapi::IFLA_BR_TCN_TIMER;
+        TOPOLOGY_CHANGE_TIMER = uapi::IFLA_BR_TOPOLOGY_CHANGE_TIMER;
+        GC_TIMER = uapi::IFLA_BR_GC_TIMER;
+        GROUP_ADDR = uapi::IFLA_BR_GROUP_ADDR;
+        FDB_FLUSH = uapi::IFLA_BR_FDB_FLUSH;
+        MCAST_ROUTER = uapi::IFLA_BR_MCAST_ROUTER;
+        MCAST_SNOOPING = uapi::IFLA_BR_MCAST_SNOOPING;
+        MCAST_QUERY_USE_IFADDR = uapi::IFLA_BR_MCAST_QUERY_USE_IFADDR;
+        MCAST_QUERIER = uapi::IFLA_BR_MCAST_QUERIER;
+        MCAST_HASH_ELASTICITY = uapi::IFLA_BR_MCAST_HASH_ELASTICITY;
+        MCAST_HASH_MAX = uapi::IFLA_BR_MCAST_HASH_MAX;
+        MCAST_LAST_MEMBER_CNT = uapi::IFLA_BR_MCAST_LAST_MEMBER_CNT;
+        MCAST_STARTUP_QUERY_CNT = uapi::IFLA_BR_MCAST_STARTUP_QUERY_CNT;
+        MCAST_LAST_MEMBER_INTVL = uapi::IFLA_BR_MCAST_LAST_MEMBER_INTVL;
+        MCAST_MEMBERSHIP_INTVL = uapi::IFLA_BR_MCAST_MEMBERSHIP_INTVL;
+        MCAST_QUERIER_INTVL = uapi::IFLA_BR_MCAST_QUERIER_INTVL;
+        MCAST_QUERY_INTVL = uapi::IFLA_BR_MCAST_QUERY_INTVL;
+        MCAST_QUERY_RESPONSE_INTVL = uapi::IFLA_BR_MCAST_QUERY_RESPONSE_INTVL;
+        MCAST_STARTUP_QUERY_INTVL = uapi::IFLA_BR_MCAST_STARTUP_QUERY_INTVL;
+        NF_CALL_IPTABLES = uapi::IFLA_BR_NF_CALL_IPTABLES;
+        NF_CALL_IP6TABLES = uapi::IFLA_BR_NF_CALL_IP6TABLES;
+        NF_CALL_ARPTABLES = uapi::IFLA_BR_NF_CALL_ARPTABLES;
+        VLAN_DEFAULT_PVID = uapi::IFLA_BR_VLAN_DEFAULT_PVID;
+        PAD = uapi::IFLA_BR_PAD;
+        VLAN_STATS_ENABLED = uapi::IFLA_BR_VLAN_STATS_ENABLED;
+        MCAST_STATS_ENABLED = uapi::IFLA_BR_MCAST_STATS_ENABLED;
+        MCAST_IGMP_VERSION = uapi::IFLA_BR_MCAST_IGMP_VERSION;
+        MCAST_MLD_VERSION = uapi::IFLA_BR_MCAST_MLD_VERSION;
+        VLAN_STATS_PER_PORT = uapi::IFLA_BR_VLAN_STATS_PER_PORT;
+        MULTI_BOOLOPT = uapi::IFLA_BR_MULTI_BOOLOPT;
+        MCAST_QUERIER_STATE = uapi::IFLA_BR_MCAST_QUERIER_STATE;
+    }
+}
+
+typed_consts! {
+    /// An attribute describing a bridge port, in IFLA_INFO_SLAVE_DATA or
+    /// (for AF_BRIDGE) IFLA_PROTINFO.
+    pub struct IflaBrport(u16): "IFLA_BRPORT_" {
+        STATE = uapi::IFLA_BRPORT_STATE;
+        PRIORITY = uapi::IFLA_BRPORT_PRIORITY;
+        COST = uapi::IFLA_BRPORT_COST;
+        MODE = uapi::IFLA_BRPORT_MODE;
+        GUARD = uapi::IFLA_BRPORT_GUARD;
+        PROTECT = uapi::IFLA_BRPORT_PROTECT;
+        FAST_LEAVE = uapi::IFLA_BRPORT_FAST_LEAVE;
+        LEARNING = uapi::IFLA_BRPORT_LEARNING;
+        UNICAST_FLOOD = uapi::IFLA_BRPORT_UNICAST_FLOOD;
+        PROXYARP = uapi::IFLA_BRPORT_PROXYARP;
+        LEARNING_SYNC = uapi::IFLA_BRPORT_LEARNING_SYNC;
+        PROXYARP_WIFI = uapi::IFLA_BRPORT_PROXYARP_WIFI;
+        ROOT_ID = uapi::IFLA_BRPORT_ROOT_ID;
+        BRIDGE_ID = uapi::IFLA_BRPORT_BRIDGE_ID;
+        DESIGNATED_PORT = uapi::IFLA_BRPORT_DESIGNATED_PORT;
+        DESIGNATED_COST = uapi::IFLA_BRPORT_DESIGNATED_COST;
+        ID = uapi::IFLA_BRPORT_ID;
+        NO = uapi::IFLA_BRPORT_NO;
+        TOPOLOGY_CHANGE_ACK = uapi::IFLA_BRPORT_TOPOLOGY_CHANGE_ACK;
+        CONFIG_PENDING = uapi::IFLA_BRPORT_CONFIG_PENDING;
+        MESSAGE_AGE_TIMER = uapi::IFLA_BRPORT_MESSAGE_AGE_TIMER;
+        FORWARD_DELAY_TIMER = uapi::IFLA_BRPORT_FORWARD_DELAY_TIMER;
+        HOLD_TIMER = uapi::IFLA_BRPORT_HOLD_TIMER;
+        FLUSH = uapi::IFLA_BRPORT_FLUSH;
+        MULTICAST_ROUTER = uapi::IFLA_BRPORT_MULTICAST_ROUTER;
+        PAD = uapi::IFLA_BRPORT_PAD;
+        MCAST_FLOOD = uapi::IFLA_BRPORT_MCAST_FLOOD;
+        MCAST_TO_UCAST = uapi::IFLA_BRPORT_MCAST_TO_UCAST;
+        VLAN_TUNNEL = uapi::IFLA_BRPORT_VLAN_TUNNEL;
+        BCAST_FLOOD = uapi::IFLA_BRPORT_BCAST_FLOOD;
+        GROUP_FWD_MASK = uapi::IFLA_BRPORT_GROUP_FWD_MASK;
+        NEIGH_SUPPRESS = uapi::IFLA_BRPORT_NEIGH_SUPPRESS;
+        ISOLATED = uapi::IFLA_BRPORT_ISOLATED;
+        BACKUP_PORT = uapi::IFLA_BRPORT_BACKUP_PORT;
+        MRP_RING_OPEN = uapi::IFLA_BRPORT_MRP_RING_OPEN;
+        MRP_IN_OPEN = uapi::IFLA_BRPORT_MRP_IN_OPEN;
+        MCAST_EHT_HOSTS_LIMIT = uapi::IFLA_BRPORT_MCAST_EHT_HOSTS_LIMIT;
+        MCAST_EHT_HOSTS_CNT = uapi::IFLA_BRPORT_MCAST_EHT_HOSTS_CNT;
+        // not in the 5.15 headers
+        LOCKED = 39;
+    }
+}
+
+typed_consts! {
+    /// An attribute in the IFLA_AF_SPEC of an AF_BRIDGE link message.
+    pub struct IflaBridge(u16): "IFLA_BRIDGE_" {
+        FLAGS = uapi::IFLA_BRIDGE_FLAGS;
+        MODE = uapi::IFLA_BRIDGE_MODE;
+        VLAN_INFO = uapi::IFLA_BRIDGE_VLAN_INFO;
+        VLAN_TUNNEL_INFO = uapi::IFLA_BRIDGE_VLAN_TUNNEL_INFO;
+        MRP = uapi::IFLA_BRIDGE_MRP;
+        CFM = uapi::IFLA_BRIDGE_CFM;
+        // not in the 5.15 headers
+        MST = 6;
+    }
+}
+
+typed_consts! {
+    /// A top-level attribute of RTM_NEWVLAN and friends.
+    pub struct BridgeVlandb(u16): "BRIDGE_VLANDB_" {
+        ENTRY = uapi::BRIDGE_VLANDB_ENTRY;
+        GLOBAL_OPTIONS = uapi::BRIDGE_VLANDB_GLOBAL_OPTIONS;
+    }
+}
+
+typed_consts! {
+    /// An attribute of an RTM_GETVLAN dump request.
+    pub struct BridgeVlandbDump(u16): "BRIDGE_VLANDB_DUMP_" {
+        FLAGS = uapi::BRIDGE_VLANDB_DUMP_FLAGS;
+    }
+}
+
+typed_consts! {
+    /// An attribute of a port VLAN, in BRIDGE_VLANDB_ENTRY.
+    pub struct BridgeVlandbEntry(u16): "BRIDGE_VLANDB_ENTRY_" {
+        INFO = uapi::BRIDGE_VLANDB_ENTRY_INFO;
+        RANGE = uapi::BRIDGE_VLANDB_ENTRY_RANGE;
+        STATE = uapi::BRIDGE_VLANDB_ENTRY_STATE;
+        TUNNEL_INFO = uapi::BRIDGE_VLANDB_ENTRY_TUNNEL_INFO;
+        STATS = uapi::BRIDGE_VLANDB_ENTRY_STATS;
+        MCAST_ROUTER = uapi::BRIDGE_VLANDB_ENTRY_MCAST_ROUTER;
+    }
+}
+
+typed_consts! {
+    /// A VLAN counter, in BRIDGE_VLANDB_ENTRY_STATS.
+    pub struct BridgeVlandbStats(u16): "BRIDGE_VLANDB_STATS_" {
+        RX_BYTES = uapi::BRIDGE_VLANDB_STATS_RX_BYTES;
+        RX_PACKETS = uapi::BRIDGE_VLANDB_STATS_RX_PACKETS;
+        TX_BYTES = uapi::BRIDGE_VLANDB_STATS_TX_BYTES;
+        TX_PACKETS = uapi::BRIDGE_VLANDB_STATS_TX_PACKETS;
+    }
+}
+
+typed_consts! {
+    /// A bridge-wide VLAN option, in BRIDGE_VLANDB_GLOBAL_OPTIONS.
+    pub struct BridgeVlandbGopts(u16): "BRIDGE_VLANDB_GOPTS_" {
+        ID = uapi::BRIDGE_VLANDB_GOPTS_ID;
+        RANGE = uapi::BRIDGE_VLANDB_GOPTS_RANGE;
+        MCAST_SNOOPING = uapi::BRIDGE_VLANDB_GOPTS_MCAST_SNOOPING;
+        MCAST_IGMP_VERSION = uapi::BRIDGE_VLANDB_GOPTS_MCAST_IGMP_VERSION;
+        MCAST_MLD_VERSION = uapi::BRIDGE_VLANDB_GOPTS_MCAST_MLD_VERSION;
+        MCAST_LAST_MEMBER_CNT = uapi::BRIDGE_VLANDB_GOPTS_MCAST_LAST_MEMBER_CNT;
+        MCAST_STARTUP_QUERY_CNT = uapi::BRIDGE_VLANDB_GOPTS_MCAST_STARTUP_QUERY_CNT;
+        MCAST_LAST_MEMBER_INTVL = uapi::BRIDGE_VLANDB_GOPTS_MCAST_LAST_MEMBER_INTVL;
+        MCAST_MEMBERSHIP_INTVL = uapi::BRIDGE_VLANDB_GOPTS_MCAST_MEMBERSHIP_INTVL;
+        MCAST_QUERIER_INTVL = uapi::BRIDGE_VLANDB_GOPTS_MCAST_QUERIER_INTVL;
+        MCAST_QUERY_INTVL = uapi::BRIDGE_VLANDB_GOPTS_MCAST_QUERY_INTVL;
+        MCAST_QUERY_RESPONSE_INTVL = uapi::BRIDGE_VLANDB_GOPTS_MCAST_QUERY_RESPONSE_INTVL;
+        MCAST_STARTUP_QUERY_INTVL = uapi::BRIDGE_VLANDB_GOPTS_MCAST_STARTUP_QUERY_INTVL;
+        MCAST_QUERIER = uapi::BRIDGE_VLANDB_GOPTS_MCAST_QUERIER;
+        MCAST_ROUTER_PORTS = uapi::BRIDGE_VLANDB_GOPTS_MCAST_ROUTER_PORTS;
+        MCAST_QUERIER_STATE = uapi::BRIDGE_VLANDB_GOPTS_MCAST_QUERIER_STATE;
+        // not in the 5.15 headers
+        MSTI = 19;
+    }
+}
+
+typed_consts! {
+    /// An address attribute, as sent with RTM_NEWADDR.
+    pub struct Ifa(u16): "IFA_" {
+        ADDRESS = uapi::IFA_ADDRESS;
+        LOCAL = uapi::IFA_LOCAL;
+        LABEL = uapi::IFA_LABEL;
+        BROADCAST = uapi::IFA_BROADCAST;
+        ANYCAST = uapi::IFA_ANYCAST;
+        CACHEINFO = uapi::IFA_CACHEINFO;
+        MULTICAST = uapi::IFA_MULTICAST;
+        FLAGS = uapi::IFA_FLAGS;
+        RT_PRIORITY = uapi::IFA_RT_PRIORITY;
+        TARGET_NETNSID = uapi::IFA_TARGET_NETNSID;
+        // not in the 5.15 headers
+        PROTO = 11;
+    }
+}
+
+typed_consts! {
+    /// A route attribute, as sent with RTM_NEWROUTE.
+    pub struct Rta(u16): "RTA_" {
+        DST = uapi::RTA_DST;
+        SRC = uapi::RTA_SRC;
+        IIF = uapi::RTA_IIF;
+        OIF = uapi::RTA_OIF;
+        GATEWAY = uapi::RTA_GATEWAY;
+        PRIORITY = uapi::RTA_PRIORITY;
+        PREFSRC = uapi::RTA_PREFSRC;
+        METRICS = uapi::RTA_METRICS;
+        MULTIPATH = uapi::RTA_MULTIPATH;
+        PROTOINFO = uapi::RTA_PROTOINFO;
+        FLOW = uapi::RTA_FLOW;
+        CACHEINFO = uapi::RTA_CACHEINFO;
+        SESSION = uapi::RTA_SESSION;
+        MP_ALGO = uapi::RTA_MP_ALGO;
+        TABLE = uapi::RTA_TABLE;
+        MARK = uapi::RTA_MARK;
+        MFC_STATS = uapi::RTA_MFC_STATS;
+        VIA = uapi::RTA_VIA;
+        NEWDST = uapi::RTA_NEWDST;
+        PREF = uapi::RTA_PREF;
+        ENCAP_TYPE = uapi::RTA_ENCAP_TYPE;
+        ENCAP = uapi::RTA_ENCAP;
+        EXPIRES = uapi::RTA_EXPIRES;
+        PAD = uapi::RTA_PAD;
+        UID = uapi::RTA_UID;
+        TTL_PROPAGATE = uapi::RTA_TTL_PROPAGATE;
+        IP_PROTO = uapi::RTA_IP_PROTO;
+        SPORT = uapi::RTA_SPORT;
+        DPORT = uapi::RTA_DPORT;
+        NH_ID = uapi::RTA_NH_ID;
+    }
+}
+
+typed_consts! {
+    /// A neighbor attribute, as sent with RTM_NEWNEIGH.
+    pub struct Nda(u16): "NDA_" {
+        DST = uapi::NDA_DST;
+        LLADDR = uapi::NDA_LLADDR;
+        CACHEINFO = uapi::NDA_CACHEINFO;
+        PROBES = uapi::NDA_PROBES;
+        VLAN = uapi::NDA_VLAN;
+        PORT = uapi::NDA_PORT;
+        VNI = uapi::NDA_VNI;
+        IFINDEX = uapi::NDA_IFINDEX;
+        MASTER = uapi::NDA_MASTER;
+        LINK_NETNSID = uapi::NDA_LINK_NETNSID;
+        SRC_VNI = uapi::NDA_SRC_VNI;
+        PROTOCOL = uapi::NDA_PROTOCOL;
+        NH_ID = uapi::NDA_NH_ID;
+        FDB_EXT_ATTRS = uapi::NDA_FDB_EXT_ATTRS;
+        // not in the 5.15 headers
+        FLAGS_EXT = 15;
+        NDM_STATE_MASK = 16;
+        NDM_FLAGS_MASK = 17;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ifla, RtmType};
+    use crate::proto::{NetlinkMessage, NlmFlags};
+    use crate::type_route::RtAttr;
+
+    #[test]
+    fn test_typed_consts() {
+        assert_eq!(format!("{:?}", RtmType::NEWLINK), "RTM_NEWLINK");
+        assert_eq!(format!("{:?}", Ifla(4)), "IFLA_MTU");
+        assert_eq!(format!("{:?}", Ifla(9999)), "Ifla(9999)");
+        assert_eq!(Ifla(9999).name(), None);
+
+        let msg = NetlinkMessage::new(RtmType::GETLINK, NlmFlags::REQUEST | NlmFlags::DUMP);
+        assert_eq!(msg.header.typ, RtmType::GETLINK);
+        assert_eq!(msg.header.flags, 0x301);
+        assert!(msg.header.nlm_flags().contains(NlmFlags::DUMP));
+
+        let attr = RtAttr::new_u32(Ifla::MTU, 1500);
+        assert_eq!(attr.get_typ(), Ifla::MTU);
+        match Ifla(attr.get_typ()) {
+            Ifla::MTU => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
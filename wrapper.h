@@ -2,3 +2,5 @@
 #include <linux/rtnetlink.h>
 #include <linux/if_link.h>
 #include <linux/if.h>
+#include <linux/veth.h>
+#include <linux/if_bridge.h>